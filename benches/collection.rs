@@ -0,0 +1,73 @@
+//! Benchmarks of the procfs parsers and of a full collection, on the fixture tree
+//!
+//! Run with `cargo bench --features bench`.
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use criterion::{Criterion, black_box};
+    use pwrzv::bench;
+    use pwrzv::config::PwrzvConfig;
+    use std::path::{Path, PathBuf};
+
+    /// A parser with its benchmark name and the fixture file it reads
+    type Parser = (&'static str, &'static str, fn(&str) -> Option<f64>);
+
+    fn fixture_root() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/linux")
+    }
+
+    fn fixture(path: &str) -> String {
+        std::fs::read_to_string(fixture_root().join(path)).unwrap()
+    }
+
+    pub fn parsers(c: &mut Criterion) {
+        let parsers: [Parser; 7] = [
+            ("stat", "proc/stat", |s| {
+                bench::parse_cpu_stat(s).map(|v| v as f64)
+            }),
+            ("loadavg", "proc/loadavg", |s| {
+                bench::parse_load_average(s).map(f64::from)
+            }),
+            ("cpuinfo", "proc/cpuinfo", |s| {
+                bench::parse_cpu_cores(s).map(f64::from)
+            }),
+            ("meminfo", "proc/meminfo", |s| {
+                bench::parse_memory_usage(s).map(f64::from)
+            }),
+            ("pressure", "proc/pressure/memory", |s| {
+                bench::parse_memory_pressure(s).map(f64::from)
+            }),
+            ("net_dev", "proc/net/dev", |s| {
+                bench::parse_network_stats(s).map(f64::from)
+            }),
+            ("diskstats", "proc/diskstats", |s| {
+                bench::parse_disk_stats(s).map(|ticks| ticks as f64)
+            }),
+        ];
+
+        let mut group = c.benchmark_group("parse");
+        for (name, path, parse) in parsers {
+            let content = fixture(path);
+            assert!(parse(&content).is_some(), "{path} does not parse");
+            group.bench_function(name, |b| b.iter(|| parse(black_box(&content))));
+        }
+        group.finish();
+    }
+
+    pub fn collection(c: &mut Criterion) {
+        let mut config = PwrzvConfig::default();
+        config.collection.root = fixture_root();
+
+        c.bench_function("collect_fixture", |b| {
+            b.iter(|| pwrzv::blocking::get_power_reserve_report_with_config(black_box(&config)))
+        });
+    }
+}
+
+#[cfg(target_os = "linux")]
+criterion::criterion_group!(benches, linux::parsers, linux::collection);
+#[cfg(target_os = "linux")]
+criterion::criterion_main!(benches);
+
+#[cfg(not(target_os = "linux"))]
+fn main() {}
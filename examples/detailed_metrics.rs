@@ -121,14 +121,27 @@ fn categorize_level(level: f32) -> String {
 /// Display metrics organized by category
 fn display_metrics_by_category(details: &HashMap<String, f32>) {
     let categories = [
-        ("CPU Metrics", vec!["CPU Usage", "CPU Load", "CPU IO Wait"]),
+        (
+            "CPU Metrics",
+            vec![
+                "CPU Usage",
+                "CPU Load",
+                "CPU IO Wait",
+                "CPU Steal",
+                "CPU Throttling",
+                "CPU Core Saturation",
+            ],
+        ),
         (
             "Memory Metrics",
             vec!["Memory Usage", "Memory Compressed", "Memory Pressure"],
         ),
         ("Storage Metrics", vec!["Disk IO"]),
         ("Network Metrics", vec!["Network"]),
-        ("System Metrics", vec!["File Descriptors", "Process Count"]),
+        (
+            "System Metrics",
+            vec!["File Descriptors", "Process Count", "Thermal Load"],
+        ),
     ];
 
     // Collect all known prefixes first
@@ -208,6 +221,12 @@ fn get_metric_explanation(key: &str) -> &'static str {
         "System load average is high"
     } else if key.contains("CPU IO Wait") {
         "CPU waiting for I/O operations"
+    } else if key.contains("CPU Steal") {
+        "Hypervisor is running other guests on this CPU"
+    } else if key.contains("CPU Throttling") {
+        "CPU runs below its maximum frequency"
+    } else if key.contains("CPU Core Saturation") {
+        "A single core is busy even if the others are idle"
     } else if key.contains("Memory Usage") {
         "RAM usage is elevated"
     } else if key.contains("Memory Compressed") {
@@ -222,6 +241,8 @@ fn get_metric_explanation(key: &str) -> &'static str {
         "File descriptor usage is high"
     } else if key.contains("Process Count") {
         "Many processes are running"
+    } else if key.contains("Thermal Load") {
+        "Temperature is nearing its critical trip point"
     } else {
         "Resource utilization metric"
     }
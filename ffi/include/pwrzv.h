@@ -0,0 +1,131 @@
+#ifndef PWRZV_H
+#define PWRZV_H
+
+/* Generated by cbindgen from src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Status codes returned by the C API
+//
+// Codes are stable: new ones are only ever appended.
+enum PwrzvStatus {
+  // The call succeeded
+  PWRZV_STATUS_OK = 0,
+  // A required pointer argument was NULL
+  PWRZV_STATUS_NULL_POINTER = 1,
+  // The platform is not supported
+  PWRZV_STATUS_UNSUPPORTED_PLATFORM = 2,
+  // An I/O error occurred
+  PWRZV_STATUS_IO = 3,
+  // A system file could not be read
+  PWRZV_STATUS_READ = 4,
+  // A system command could not be run or failed
+  PWRZV_STATUS_COMMAND = 5,
+  // System data could not be parsed
+  PWRZV_STATUS_PARSE = 6,
+  // A system resource could not be accessed
+  PWRZV_STATUS_RESOURCE_ACCESS = 7,
+  // Sources were read but did not yield a usable value
+  PWRZV_STATUS_COLLECTION = 8,
+  // An operation did not finish in time
+  PWRZV_STATUS_TIMED_OUT = 9,
+  // The score could not be calculated
+  PWRZV_STATUS_CALCULATION = 10,
+  // A value or the configuration was invalid
+  PWRZV_STATUS_INVALID_VALUE = 11,
+  // The monitor has not taken a sample yet
+  PWRZV_STATUS_NO_SAMPLE = 12,
+  // pwrzv panicked; this is a bug
+  PWRZV_STATUS_PANIC = 13,
+  // Any other error
+  PWRZV_STATUS_OTHER = 99,
+};
+typedef int32_t PwrzvStatus;
+
+// Monitor handle keeping the latest sample between calls
+//
+// Opaque to C. A handle must not be used from two threads at once.
+typedef struct PwrzvMonitor PwrzvMonitor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Get the power reserve level of the system
+//
+// # Safety
+//
+// `level_out` must be NULL or point to writable memory for a `float`.
+PwrzvStatus pwrzv_get_level(float *level_out);
+
+// Get a detailed power reserve report as JSON
+//
+// On success `*json_out` holds a string to release with [`pwrzv_string_free`].
+//
+// # Safety
+//
+// `json_out` must be NULL or point to writable memory for a `char *`.
+PwrzvStatus pwrzv_get_report_json(char **json_out);
+
+// Create a monitor handle
+//
+// Returns NULL if the platform is not supported; see
+// [`pwrzv_last_error_message`]. Release the handle with [`pwrzv_monitor_free`].
+PwrzvMonitor *pwrzv_monitor_new(void);
+
+// Take a sample with a monitor
+//
+// The report is kept in the monitor for [`pwrzv_monitor_report_json`]; a
+// failed sample leaves the previous one in place. `level_out` may be NULL if
+// only the report is wanted.
+//
+// # Safety
+//
+// `monitor` must be NULL or a handle from [`pwrzv_monitor_new`] that has not
+// been freed; `level_out` must be NULL or point to writable memory for a
+// `float`.
+PwrzvStatus pwrzv_monitor_sample(PwrzvMonitor *monitor, float *level_out);
+
+// Get the monitor's latest report as JSON
+//
+// Returns `PWRZV_STATUS_NO_SAMPLE` until a sample has succeeded. On success
+// `*json_out` holds a string to release with [`pwrzv_string_free`].
+//
+// # Safety
+//
+// `monitor` must be NULL or a live handle from [`pwrzv_monitor_new`];
+// `json_out` must be NULL or point to writable memory for a `char *`.
+PwrzvStatus pwrzv_monitor_report_json(const PwrzvMonitor *monitor, char **json_out);
+
+// Release a monitor handle; NULL is ignored
+//
+// # Safety
+//
+// `monitor` must be NULL or a handle from [`pwrzv_monitor_new`] that has not
+// been freed yet.
+void pwrzv_monitor_free(PwrzvMonitor *monitor);
+
+// Release a string returned by pwrzv; NULL is ignored
+//
+// # Safety
+//
+// `string` must be NULL or a string returned by pwrzv that has not been freed yet.
+void pwrzv_string_free(char *string);
+
+// Get the message describing the last failure on the calling thread
+//
+// Returns NULL if the last call succeeded. The string is owned by pwrzv and
+// stays valid until the thread's next pwrzv call.
+const char *pwrzv_last_error_message(void);
+
+// Get the version of the library, e.g. "0.6.2"
+const char *pwrzv_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PWRZV_H */
@@ -0,0 +1,346 @@
+//! C bindings for pwrzv
+//!
+//! A stable C ABI over [`pwrzv::blocking`], built as `libpwrzv_ffi.so` /
+//! `libpwrzv_ffi.dylib` and `libpwrzv_ffi.a`. The declarations live in
+//! `include/pwrzv.h`. No function needs an async runtime: collection runs on
+//! plain threads inside the call.
+//!
+//! Conventions:
+//!
+//! - Fallible functions return a [`PwrzvStatus`]; `PWRZV_STATUS_OK` is 0.
+//! - After a failure, [`pwrzv_last_error_message`] describes it. The message
+//!   belongs to the calling thread and stays valid until its next pwrzv call.
+//! - Strings returned through out-pointers are owned by the caller and must be
+//!   released with [`pwrzv_string_free`].
+//! - Panics never cross the boundary; they are reported as
+//!   `PWRZV_STATUS_PANIC`.
+
+use pwrzv::{PowerReserveReport, PwrzvError, blocking};
+use std::cell::RefCell;
+use std::ffi::{CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Status codes returned by the C API
+///
+/// Codes are stable: new ones are only ever appended.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwrzvStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument was NULL
+    NullPointer = 1,
+    /// The platform is not supported
+    UnsupportedPlatform = 2,
+    /// An I/O error occurred
+    Io = 3,
+    /// A system file could not be read
+    Read = 4,
+    /// A system command could not be run or failed
+    Command = 5,
+    /// System data could not be parsed
+    Parse = 6,
+    /// A system resource could not be accessed
+    ResourceAccess = 7,
+    /// Sources were read but did not yield a usable value
+    Collection = 8,
+    /// An operation did not finish in time
+    TimedOut = 9,
+    /// The score could not be calculated
+    Calculation = 10,
+    /// A value or the configuration was invalid
+    InvalidValue = 11,
+    /// The monitor has not taken a sample yet
+    NoSample = 12,
+    /// pwrzv panicked; this is a bug
+    Panic = 13,
+    /// Any other error
+    Other = 99,
+}
+
+impl From<&PwrzvError> for PwrzvStatus {
+    fn from(error: &PwrzvError) -> Self {
+        match error {
+            PwrzvError::UnsupportedPlatform { .. } => PwrzvStatus::UnsupportedPlatform,
+            PwrzvError::IoError(_) => PwrzvStatus::Io,
+            PwrzvError::ReadError { .. } => PwrzvStatus::Read,
+            PwrzvError::CommandError { .. } => PwrzvStatus::Command,
+            PwrzvError::ParseError { .. } => PwrzvStatus::Parse,
+            PwrzvError::ResourceAccessError { .. } => PwrzvStatus::ResourceAccess,
+            PwrzvError::CollectionError { .. } => PwrzvStatus::Collection,
+            PwrzvError::TimedOut { .. } => PwrzvStatus::TimedOut,
+            PwrzvError::CalculationError { .. } => PwrzvStatus::Calculation,
+            PwrzvError::InvalidValue { .. } => PwrzvStatus::InvalidValue,
+            _ => PwrzvStatus::Other,
+        }
+    }
+}
+
+/// Monitor handle keeping the latest sample between calls
+///
+/// Opaque to C. A handle must not be used from two threads at once.
+#[derive(Debug, Default)]
+pub struct PwrzvMonitor {
+    latest: Option<PowerReserveReport>,
+}
+
+thread_local! {
+    /// Message describing the last failure on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Remember the message of a failure for [`pwrzv_last_error_message`]
+fn set_last_error(message: &str) {
+    // Interior NUL bytes would truncate the message anyway
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run `call`, turning errors and panics into a status code
+fn guard(call: impl FnOnce() -> Result<(), PwrzvStatus>) -> PwrzvStatus {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(())) => PwrzvStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => {
+            set_last_error("pwrzv panicked");
+            PwrzvStatus::Panic
+        }
+    }
+}
+
+/// Record a pwrzv error and map it to its status code
+fn fail(error: PwrzvError) -> PwrzvStatus {
+    set_last_error(&error.to_string());
+    PwrzvStatus::from(&error)
+}
+
+/// Record a NULL pointer argument
+fn null_pointer(argument: &str) -> PwrzvStatus {
+    set_last_error(&format!("{argument} must not be NULL"));
+    PwrzvStatus::NullPointer
+}
+
+/// Serialize a report into a caller-owned C string
+fn report_json(report: &PowerReserveReport) -> Result<*mut c_char, PwrzvStatus> {
+    let json = serde_json::to_string(report).map_err(|e| {
+        set_last_error(&format!("Failed to serialize report: {e}"));
+        PwrzvStatus::Other
+    })?;
+    // JSON escapes control characters, so it never contains NUL
+    Ok(CString::new(json).unwrap_or_default().into_raw())
+}
+
+/// Get the power reserve level of the system
+///
+/// # Safety
+///
+/// `level_out` must be NULL or point to writable memory for a `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_get_level(level_out: *mut f32) -> PwrzvStatus {
+    guard(|| {
+        if level_out.is_null() {
+            return Err(null_pointer("level_out"));
+        }
+        let level = blocking::get_power_reserve_level().map_err(fail)?;
+        // SAFETY: checked for NULL above; validity is the caller's contract
+        unsafe { *level_out = level };
+        Ok(())
+    })
+}
+
+/// Get a detailed power reserve report as JSON
+///
+/// On success `*json_out` holds a string to release with [`pwrzv_string_free`].
+///
+/// # Safety
+///
+/// `json_out` must be NULL or point to writable memory for a `char *`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_get_report_json(json_out: *mut *mut c_char) -> PwrzvStatus {
+    guard(|| {
+        if json_out.is_null() {
+            return Err(null_pointer("json_out"));
+        }
+        let report = blocking::get_power_reserve_report().map_err(fail)?;
+        let json = report_json(&report)?;
+        // SAFETY: checked for NULL above; validity is the caller's contract
+        unsafe { *json_out = json };
+        Ok(())
+    })
+}
+
+/// Create a monitor handle
+///
+/// Returns NULL if the platform is not supported; see
+/// [`pwrzv_last_error_message`]. Release the handle with [`pwrzv_monitor_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn pwrzv_monitor_new() -> *mut PwrzvMonitor {
+    let mut monitor = ptr::null_mut();
+    guard(|| {
+        pwrzv::check_platform().map_err(fail)?;
+        monitor = Box::into_raw(Box::default());
+        Ok(())
+    });
+    monitor
+}
+
+/// Take a sample with a monitor
+///
+/// The report is kept in the monitor for [`pwrzv_monitor_report_json`]; a
+/// failed sample leaves the previous one in place. `level_out` may be NULL if
+/// only the report is wanted.
+///
+/// # Safety
+///
+/// `monitor` must be NULL or a handle from [`pwrzv_monitor_new`] that has not
+/// been freed; `level_out` must be NULL or point to writable memory for a
+/// `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_monitor_sample(
+    monitor: *mut PwrzvMonitor,
+    level_out: *mut f32,
+) -> PwrzvStatus {
+    guard(|| {
+        // SAFETY: a live handle from `pwrzv_monitor_new` per the contract
+        let Some(monitor) = (unsafe { monitor.as_mut() }) else {
+            return Err(null_pointer("monitor"));
+        };
+        let report = blocking::get_power_reserve_report().map_err(fail)?;
+        if !level_out.is_null() {
+            // SAFETY: checked for NULL above; validity is the caller's contract
+            unsafe { *level_out = report.power_reserve_level };
+        }
+        monitor.latest = Some(report);
+        Ok(())
+    })
+}
+
+/// Get the monitor's latest report as JSON
+///
+/// Returns `PWRZV_STATUS_NO_SAMPLE` until a sample has succeeded. On success
+/// `*json_out` holds a string to release with [`pwrzv_string_free`].
+///
+/// # Safety
+///
+/// `monitor` must be NULL or a live handle from [`pwrzv_monitor_new`];
+/// `json_out` must be NULL or point to writable memory for a `char *`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_monitor_report_json(
+    monitor: *const PwrzvMonitor,
+    json_out: *mut *mut c_char,
+) -> PwrzvStatus {
+    guard(|| {
+        // SAFETY: a live handle from `pwrzv_monitor_new` per the contract
+        let Some(monitor) = (unsafe { monitor.as_ref() }) else {
+            return Err(null_pointer("monitor"));
+        };
+        if json_out.is_null() {
+            return Err(null_pointer("json_out"));
+        }
+        let Some(report) = &monitor.latest else {
+            set_last_error("The monitor has not taken a sample yet");
+            return Err(PwrzvStatus::NoSample);
+        };
+        let json = report_json(report)?;
+        // SAFETY: checked for NULL above; validity is the caller's contract
+        unsafe { *json_out = json };
+        Ok(())
+    })
+}
+
+/// Release a monitor handle; NULL is ignored
+///
+/// # Safety
+///
+/// `monitor` must be NULL or a handle from [`pwrzv_monitor_new`] that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_monitor_free(monitor: *mut PwrzvMonitor) {
+    if !monitor.is_null() {
+        // SAFETY: created by `Box::into_raw` in `pwrzv_monitor_new`
+        drop(unsafe { Box::from_raw(monitor) });
+    }
+}
+
+/// Release a string returned by pwrzv; NULL is ignored
+///
+/// # Safety
+///
+/// `string` must be NULL or a string returned by pwrzv that has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pwrzv_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: created by `CString::into_raw` in `report_json`
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Get the message describing the last failure on the calling thread
+///
+/// Returns NULL if the last call succeeded. The string is owned by pwrzv and
+/// stays valid until the thread's next pwrzv call.
+#[unsafe(no_mangle)]
+pub extern "C" fn pwrzv_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Get the version of the library, e.g. "0.6.2"
+#[unsafe(no_mangle)]
+pub extern "C" fn pwrzv_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_status_from_error() {
+        let error = PwrzvError::IoError(std::io::Error::other("boom"));
+        assert_eq!(PwrzvStatus::from(&error), PwrzvStatus::Io);
+    }
+
+    #[test]
+    fn test_null_pointers() {
+        assert_eq!(
+            unsafe { pwrzv_get_level(ptr::null_mut()) },
+            PwrzvStatus::NullPointer
+        );
+        let message = unsafe { CStr::from_ptr(pwrzv_last_error_message()) };
+        assert!(message.to_str().unwrap().contains("level_out"));
+
+        assert_eq!(
+            unsafe { pwrzv_monitor_sample(ptr::null_mut(), ptr::null_mut()) },
+            PwrzvStatus::NullPointer
+        );
+        unsafe { pwrzv_monitor_free(ptr::null_mut()) };
+        unsafe { pwrzv_string_free(ptr::null_mut()) };
+    }
+
+    #[test]
+    fn test_monitor_without_sample() {
+        let monitor = pwrzv_monitor_new();
+        assert!(!monitor.is_null());
+
+        let mut json = ptr::null_mut();
+        assert_eq!(
+            unsafe { pwrzv_monitor_report_json(monitor, &mut json) },
+            PwrzvStatus::NoSample
+        );
+        assert!(json.is_null());
+        unsafe { pwrzv_monitor_free(monitor) };
+    }
+
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(pwrzv_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -0,0 +1,50 @@
+/* Exercises the C API end to end; run by tests/c_api.rs */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "pwrzv.h"
+
+#define CHECK(condition)                                                   \
+  do {                                                                     \
+    if (!(condition)) {                                                    \
+      const char *error = pwrzv_last_error_message();                      \
+      fprintf(stderr, "%s:%d: check failed: %s (%s)\n", __FILE__, __LINE__, \
+              #condition, error ? error : "no error message");             \
+      return 1;                                                            \
+    }                                                                      \
+  } while (0)
+
+int main(void) {
+  float level = 0.0f;
+  char *json = NULL;
+
+  CHECK(strlen(pwrzv_version()) > 0);
+
+  CHECK(pwrzv_get_level(&level) == PWRZV_STATUS_OK);
+  CHECK(level >= 1.0f && level <= 5.0f);
+  CHECK(pwrzv_last_error_message() == NULL);
+
+  CHECK(pwrzv_get_report_json(&json) == PWRZV_STATUS_OK);
+  CHECK(strstr(json, "\"power_reserve_level\"") != NULL);
+  pwrzv_string_free(json);
+  json = NULL;
+
+  CHECK(pwrzv_get_level(NULL) == PWRZV_STATUS_NULL_POINTER);
+  CHECK(pwrzv_last_error_message() != NULL);
+
+  PwrzvMonitor *monitor = pwrzv_monitor_new();
+  CHECK(monitor != NULL);
+  CHECK(pwrzv_monitor_report_json(monitor, &json) == PWRZV_STATUS_NO_SAMPLE);
+  for (int i = 0; i < 2; i++) {
+    CHECK(pwrzv_monitor_sample(monitor, &level) == PWRZV_STATUS_OK);
+    CHECK(level >= 1.0f && level <= 5.0f);
+  }
+  CHECK(pwrzv_monitor_report_json(monitor, &json) == PWRZV_STATUS_OK);
+  CHECK(strstr(json, "\"platform\"") != NULL);
+  pwrzv_string_free(json);
+  pwrzv_monitor_free(monitor);
+
+  printf("pwrzv %s: level %.2f\n", pwrzv_version(), level);
+  return 0;
+}
@@ -0,0 +1,47 @@
+//! Compiles `tests/c/pwrzv_test.c` against the shared library and runs it
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory holding the libraries built for this test run
+fn library_dir() -> PathBuf {
+    // Test binaries are built next to the libraries, in `<profile>/deps`
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn test_c_program() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let output = lib_dir.join("pwrzv_test");
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let status = Command::new(&cc)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests/c/pwrzv_test.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lpwrzv_ffi")
+        .arg("-o")
+        .arg(&output)
+        .status()
+        .unwrap_or_else(|e| panic!("failed to run {cc}: {e}"));
+    assert!(status.success(), "compiling the C test program failed");
+
+    let result = Command::new(&output).output().unwrap();
+    assert!(
+        result.status.success(),
+        "C test program failed:\n{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    assert!(String::from_utf8_lossy(&result.stdout).contains("level"));
+}
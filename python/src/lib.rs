@@ -0,0 +1,233 @@
+//! Python bindings for pwrzv
+//!
+//! Built with maturin into the `pwrzv` extension module:
+//!
+//! ```python
+//! import pwrzv
+//!
+//! if pwrzv.get_level() < 2.0:
+//!     pause_data_loading()
+//!
+//! monitor = pwrzv.Monitor(pwrzv.load_config("/etc/pwrzv.toml"))
+//! report = monitor.sample()
+//! print(report["power_reserve_level"], report["metrics"])
+//! ```
+//!
+//! Reports are plain dicts with the same keys as `pwrzv --detailed json`.
+//! Collection releases the GIL, so other Python threads keep running while a
+//! sample is taken.
+
+use pwrzv::config::{self, PwrzvConfig};
+use pwrzv::{PowerReserveReport, blocking};
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::path::PathBuf;
+
+mod exceptions {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyException;
+
+    create_exception!(
+        pwrzv,
+        PwrzvError,
+        PyException,
+        "Raised when pwrzv cannot take a sample or load a configuration."
+    );
+    create_exception!(
+        pwrzv,
+        TransientError,
+        PwrzvError,
+        "A PwrzvError that may go away when the call is retried."
+    );
+}
+
+use exceptions::{PwrzvError, TransientError};
+
+/// Convert a pwrzv error into the matching Python exception
+fn to_py_err(error: pwrzv::PwrzvError) -> PyErr {
+    if error.is_transient() {
+        TransientError::new_err(error.to_string())
+    } else {
+        PwrzvError::new_err(error.to_string())
+    }
+}
+
+/// Convert a report into a dict via its JSON form
+fn report_dict<'py>(py: Python<'py>, report: &PowerReserveReport) -> PyResult<Bound<'py, PyDict>> {
+    let json = serde_json::to_string(report)
+        .map_err(|e| PyException::new_err(format!("Failed to serialize report: {e}")))?;
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (json,))?
+        .downcast_into::<PyDict>()?)
+}
+
+/// Take a sample with `config`, or the process-wide configuration, without the GIL
+fn sample(py: Python<'_>, config: Option<&Config>) -> PyResult<PowerReserveReport> {
+    let config = match config {
+        Some(config) => config.inner.clone(),
+        None => (*config::global()).clone(),
+    };
+    py.allow_threads(|| blocking::get_power_reserve_report_with_config(&config))
+        .map_err(to_py_err)
+}
+
+/// pwrzv configuration: scoring parameters and collection settings
+#[pyclass(module = "pwrzv")]
+#[derive(Clone)]
+struct Config {
+    inner: PwrzvConfig,
+}
+
+#[pymethods]
+impl Config {
+    /// Parse a configuration from TOML, or use the defaults
+    #[new]
+    #[pyo3(signature = (toml = None))]
+    fn new(toml: Option<&str>) -> PyResult<Self> {
+        let inner = match toml {
+            Some(toml) => PwrzvConfig::from_toml(toml).map_err(to_py_err)?,
+            None => PwrzvConfig::default(),
+        };
+        Ok(Self { inner })
+    }
+
+    /// Load a configuration file
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        let inner = PwrzvConfig::from_file(path).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Directory that /proc is read under (Linux); "/" unless overridden
+    #[getter]
+    fn root(&self) -> PathBuf {
+        self.inner.collection.root.clone()
+    }
+
+    #[setter]
+    fn set_root(&mut self, root: PathBuf) {
+        self.inner.collection.root = root;
+    }
+
+    /// Render the configuration as TOML
+    fn to_toml(&self) -> String {
+        self.inner.to_toml()
+    }
+
+    /// List the problems found in the configuration, if any
+    fn validate(&self) -> Vec<String> {
+        self.inner
+            .validate()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Config(root={:?})", self.inner.collection.root)
+    }
+}
+
+/// Takes samples with a fixed configuration and keeps the latest one
+#[pyclass(module = "pwrzv")]
+struct Monitor {
+    config: Config,
+    latest: Option<PowerReserveReport>,
+}
+
+#[pymethods]
+impl Monitor {
+    /// Create a monitor; without a config the process-wide one is used
+    #[new]
+    #[pyo3(signature = (config = None))]
+    fn new(config: Option<Config>) -> Self {
+        let config = config.unwrap_or_else(|| Config {
+            inner: (*config::global()).clone(),
+        });
+        Self {
+            config,
+            latest: None,
+        }
+    }
+
+    /// Take a sample and return its report
+    ///
+    /// A failed sample raises and leaves the previous one in place.
+    fn sample<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let report = sample(py, Some(&self.config))?;
+        let dict = report_dict(py, &report)?;
+        self.latest = Some(report);
+        Ok(dict)
+    }
+
+    /// Report of the latest successful sample, or None
+    #[getter]
+    fn latest<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.latest
+            .as_ref()
+            .map(|report| report_dict(py, report))
+            .transpose()
+    }
+
+    /// Power reserve level (1.0-5.0) of the latest successful sample, or None
+    #[getter]
+    fn level(&self) -> Option<f32> {
+        self.latest
+            .as_ref()
+            .map(|report| report.power_reserve_level)
+    }
+
+    /// The configuration samples are taken with
+    #[getter]
+    fn config(&self) -> Config {
+        self.config.clone()
+    }
+}
+
+/// Get the power reserve level (1.0-5.0) of the system
+#[pyfunction]
+#[pyo3(signature = (config = None))]
+fn get_level(py: Python<'_>, config: Option<PyRef<'_, Config>>) -> PyResult<f32> {
+    let report = sample(py, config.as_deref())?;
+    Ok(report.power_reserve_level)
+}
+
+/// Get a detailed power reserve report of the system as a dict
+#[pyfunction]
+#[pyo3(signature = (config = None))]
+fn get_report<'py>(
+    py: Python<'py>,
+    config: Option<PyRef<'py, Config>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let report = sample(py, config.as_deref())?;
+    report_dict(py, &report)
+}
+
+/// Load a configuration file, or the one named by PWRZV_CONFIG if no path is given
+#[pyfunction]
+#[pyo3(signature = (path = None))]
+fn load_config(path: Option<PathBuf>) -> PyResult<Config> {
+    let inner = match path {
+        Some(path) => PwrzvConfig::from_file(path),
+        None => PwrzvConfig::from_env(),
+    }
+    .map_err(to_py_err)?;
+    Ok(Config { inner })
+}
+
+#[pymodule]
+#[pyo3(name = "pwrzv")]
+fn pwrzv_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add("PwrzvError", py.get_type::<PwrzvError>())?;
+    m.add("TransientError", py.get_type::<TransientError>())?;
+    m.add_class::<Config>()?;
+    m.add_class::<Monitor>()?;
+    m.add_function(wrap_pyfunction!(get_level, m)?)?;
+    m.add_function(wrap_pyfunction!(get_report, m)?)?;
+    m.add_function(wrap_pyfunction!(load_config, m)?)?;
+    Ok(())
+}
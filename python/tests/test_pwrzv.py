@@ -0,0 +1,89 @@
+"""Tests for the pwrzv Python bindings.
+
+Samples are taken from the fixture /proc tree in tests/fixtures/linux rather
+than the live machine, so the expected values are exact. Run after building
+the module, e.g. with `maturin develop && python -m unittest discover tests`.
+"""
+
+import pathlib
+import sys
+import tempfile
+import unittest
+
+import pwrzv
+
+FIXTURE_ROOT = pathlib.Path(__file__).resolve().parents[2] / "tests" / "fixtures" / "linux"
+
+linux_only = unittest.skipUnless(
+    sys.platform.startswith("linux"), "the fixture root is only read on Linux"
+)
+
+
+def fixture_config():
+    config = pwrzv.Config()
+    config.root = FIXTURE_ROOT
+    return config
+
+
+class ConfigTest(unittest.TestCase):
+    def test_defaults(self):
+        config = pwrzv.Config()
+        self.assertEqual(str(config.root), "/")
+        self.assertEqual(config.validate(), [])
+
+    def test_from_toml(self):
+        config = pwrzv.Config('aggregation = "mean"\n\n[linux.cpu_usage]\nmidpoint = 0.75\n')
+        self.assertIn('aggregation = "mean"', config.to_toml())
+
+    def test_invalid_toml(self):
+        with self.assertRaises(pwrzv.PwrzvError):
+            pwrzv.Config("[collection]\ntimeout_ms = 0\n")
+
+    def test_load_config(self):
+        with tempfile.NamedTemporaryFile("w", suffix=".toml", delete=False) as file:
+            file.write(f'[collection]\nroot = "{FIXTURE_ROOT}"\n')
+        config = pwrzv.load_config(file.name)
+        self.assertEqual(pathlib.Path(config.root), FIXTURE_ROOT)
+
+        with self.assertRaises(pwrzv.PwrzvError):
+            pwrzv.load_config("/nonexistent/pwrzv.toml")
+
+
+@linux_only
+class SampleTest(unittest.TestCase):
+    def test_get_report(self):
+        report = pwrzv.get_report(fixture_config())
+        self.assertEqual(report["platform"], "linux")
+        self.assertTrue(1.0 <= report["power_reserve_level"] <= 5.0)
+        self.assertEqual(report["total_metrics"], len(report["metrics"]))
+
+        statuses = {c["name"]: c["status"] for c in report["collectors"]}
+        self.assertEqual(set(statuses.values()), {"ok"})
+
+    def test_get_level_matches_report(self):
+        config = fixture_config()
+        self.assertAlmostEqual(
+            pwrzv.get_level(config),
+            pwrzv.get_report(config)["power_reserve_level"],
+            places=4,
+        )
+
+    def test_monitor(self):
+        monitor = pwrzv.Monitor(fixture_config())
+        self.assertIsNone(monitor.latest)
+        self.assertIsNone(monitor.level)
+
+        report = monitor.sample()
+        self.assertEqual(monitor.latest["power_reserve_level"], report["power_reserve_level"])
+        self.assertAlmostEqual(monitor.level, report["power_reserve_level"], places=4)
+        self.assertEqual(pathlib.Path(monitor.config.root), FIXTURE_ROOT)
+
+    def test_missing_root(self):
+        config = pwrzv.Config()
+        config.root = "/nonexistent"
+        with self.assertRaises(pwrzv.PwrzvError):
+            pwrzv.get_report(config)
+
+
+if __name__ == "__main__":
+    unittest.main()
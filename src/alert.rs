@@ -0,0 +1,1142 @@
+//! Alerting module
+//!
+//! Evaluates alert rules against every sample and notifies webhooks or local
+//! commands when a rule starts firing or resolves.
+//!
+//! ## Rules File
+//!
+//! ```yaml
+//! rules:
+//!   - name: low-reserve
+//!     condition: level < 2.0 for 60s
+//!     cooldown: 10m
+//!     actions:
+//!       - type: webhook
+//!         url: http://127.0.0.1:9000/alerts
+//!         body: '{"text": "{{rule}} is {{state}} on {{host}}: reserve {{level}}"}'
+//!   - name: memory-pressure
+//!     condition: metric memory_pressure > 0.4
+//!     actions:
+//!       - type: command
+//!         command: logger -t pwrzv
+//! ```
+//!
+//! Conditions have the form `level <op> <threshold> [for <duration>]` or
+//! `metric <id>[.score] <op> <threshold> [for <duration>]`, where `<op>` is one
+//! of `<`, `<=`, `>`, `>=` and metric IDs are those of
+//! [`PowerReserveReport::readings`]. A rule fires once its condition has held
+//! for the given duration and resolves as soon as it no longer holds.
+//!
+//! `cooldown` (default `5m`) rate-limits notifications: a rule that fires again
+//! within the cooldown of its previous notification stays silent, and so does
+//! its resolution if it resolves before the cooldown is over. A rule still
+//! firing at the end of the cooldown notifies then. Resolved notifications can be disabled with
+//! `notify_resolved: false`.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::report::PowerReserveReport;
+use crate::timespec;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Default minimum time between two notifications of the same rule
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Time allowed for a webhook request to complete
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for a command action to finish
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Placeholders available in templates
+const TEMPLATE_VARIABLES: [&str; 12] = [
+    "rule",
+    "state",
+    "condition",
+    "level",
+    "level_description",
+    "value",
+    "threshold",
+    "since",
+    "timestamp",
+    "host",
+    "platform",
+    "report",
+];
+
+// ================================
+// Conditions
+// ================================
+
+/// What a condition compares against its threshold
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    /// The overall power reserve level
+    Level,
+    /// A single metric's raw value, or its score when `score` is set
+    Metric { id: String, score: bool },
+}
+
+/// Comparison operator of a condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Check whether `value <op> threshold` holds
+    pub fn holds(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Greater => value > threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+/// A parsed rule condition such as `level < 2.0 for 60s`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    /// Value being compared
+    pub subject: Subject,
+    /// Comparison operator
+    pub comparison: Comparison,
+    /// Threshold the value is compared with
+    pub threshold: f32,
+    /// How long the comparison must hold before the rule fires
+    pub hold: Duration,
+}
+
+impl Condition {
+    /// Get the observed value of the subject in a report
+    ///
+    /// Returns `None` when the report does not contain the metric.
+    pub fn observe(&self, report: &PowerReserveReport) -> Option<f32> {
+        match &self.subject {
+            Subject::Level => Some(report.power_reserve_level),
+            Subject::Metric { id, score } => report
+                .reading(id)
+                .map(|reading| if *score { reading.score } else { reading.value }),
+        }
+    }
+
+    /// Check whether the comparison holds for a report (ignoring the hold duration)
+    pub fn matches(&self, report: &PowerReserveReport) -> bool {
+        self.observe(report)
+            .is_some_and(|value| self.comparison.holds(value, self.threshold))
+    }
+}
+
+impl FromStr for Condition {
+    type Err = PwrzvError;
+
+    /// Parse `level <op> <threshold> [for <duration>]` or
+    /// `metric <id>[.score] <op> <threshold> [for <duration>]`
+    fn from_str(s: &str) -> PwrzvResult<Self> {
+        let invalid =
+            |reason: &str| PwrzvError::invalid_value(&format!("condition '{s}': {reason}"));
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+
+        let (subject, rest) = match tokens.as_slice() {
+            ["level", rest @ ..] => (Subject::Level, rest),
+            ["metric", id, rest @ ..] => {
+                let (id, score) = match id.strip_suffix(".score") {
+                    Some(id) => (id, true),
+                    None => (id.strip_suffix(".value").unwrap_or(id), false),
+                };
+                let subject = Subject::Metric {
+                    id: id.to_string(),
+                    score,
+                };
+                (subject, rest)
+            }
+            _ => return Err(invalid("expected 'level' or 'metric <id>'")),
+        };
+
+        let (op, threshold, hold) = match rest {
+            [op, threshold] => (op, threshold, None),
+            [op, threshold, "for", hold] => (op, threshold, Some(hold)),
+            _ => return Err(invalid("expected '<op> <threshold> [for <duration>]'")),
+        };
+
+        let comparison = match *op {
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            other => return Err(invalid(&format!("unknown operator '{other}'"))),
+        };
+        let threshold = threshold
+            .parse::<f32>()
+            .ok()
+            .filter(|t| t.is_finite())
+            .ok_or_else(|| invalid(&format!("invalid threshold '{threshold}'")))?;
+        let hold = match hold {
+            Some(hold) => timespec::parse_duration(hold)?,
+            None => Duration::ZERO,
+        };
+
+        Ok(Self {
+            subject,
+            comparison,
+            threshold,
+            hold,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.subject {
+            Subject::Level => write!(f, "level")?,
+            Subject::Metric { id, score: false } => write!(f, "metric {id}")?,
+            Subject::Metric { id, score: true } => write!(f, "metric {id}.score")?,
+        }
+        write!(f, " {} {}", self.comparison.symbol(), self.threshold)?;
+        if !self.hold.is_zero() {
+            write!(f, " for {}s", self.hold.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+// ================================
+// Rules file
+// ================================
+
+/// Alert rules file as written by users
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AlertFile {
+    rules: Vec<RuleFile>,
+}
+
+/// A single rule in the rules file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    name: String,
+    condition: String,
+    #[serde(default)]
+    cooldown: Option<String>,
+    #[serde(default = "default_true")]
+    notify_resolved: bool,
+    actions: Vec<Action>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Notification action of a rule
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    /// HTTP POST to a webhook (plain `http://` URLs only)
+    ///
+    /// `body` is a JSON template; without it a JSON object describing the
+    /// alert and the full report is sent.
+    Webhook {
+        url: String,
+        #[serde(default)]
+        body: Option<String>,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+    /// Local command run with `sh -c`
+    ///
+    /// Receives the report JSON on stdin, or the rendered `body` template if
+    /// given, plus `PWRZV_ALERT_*` environment variables.
+    Command {
+        command: String,
+        #[serde(default)]
+        body: Option<String>,
+    },
+}
+
+/// A validated alert rule
+#[derive(Debug, Clone)]
+pub struct AlertRule {
+    /// Rule name used in notifications
+    pub name: String,
+    /// Condition that makes the rule fire
+    pub condition: Condition,
+    /// Minimum time between two notifications
+    pub cooldown: Duration,
+    /// Whether to notify when the rule resolves
+    pub notify_resolved: bool,
+    /// Actions run on every notification
+    pub actions: Vec<Action>,
+}
+
+impl AlertRule {
+    /// Create a rule with the default cooldown and resolved notifications
+    pub fn new(name: &str, condition: &str, actions: Vec<Action>) -> PwrzvResult<Self> {
+        let rule = Self {
+            name: name.to_string(),
+            condition: condition.parse()?,
+            cooldown: DEFAULT_COOLDOWN,
+            notify_resolved: true,
+            actions,
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    /// Check actions for unsupported URLs and unknown template placeholders
+    fn validate(&self) -> PwrzvResult<()> {
+        let invalid = |reason: String| {
+            PwrzvError::invalid_value(&format!("alert rule '{}': {reason}", self.name))
+        };
+
+        for action in &self.actions {
+            let body = match action {
+                Action::Webhook { url, body, .. } => {
+                    parse_http_url(url).map_err(|e| invalid(e.to_string()))?;
+                    body
+                }
+                Action::Command { body, .. } => body,
+            };
+            if let Some(body) = body {
+                check_template(body).map_err(invalid)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ================================
+// Engine
+// ================================
+
+/// Notification state of an alert event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    /// The rule's condition has held for its duration
+    Firing,
+    /// The rule's condition no longer holds
+    Resolved,
+}
+
+impl AlertState {
+    /// Lowercase name used in templates
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertState::Firing => "firing",
+            AlertState::Resolved => "resolved",
+        }
+    }
+}
+
+/// A notification produced by [`AlertEngine::evaluate`]
+#[derive(Debug, Clone)]
+pub struct AlertEvent {
+    /// Name of the rule
+    pub rule: String,
+    /// Firing or resolved
+    pub state: AlertState,
+    /// The rule's condition
+    pub condition: Condition,
+    /// Observed value of the condition's subject, if present in the report
+    pub value: Option<f32>,
+    /// Time the condition started to hold
+    pub since: DateTime<Utc>,
+    /// Report that triggered the transition
+    pub report: Arc<PowerReserveReport>,
+    actions: Arc<Vec<Action>>,
+}
+
+/// Evaluation phase of a rule
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Inactive,
+    Pending(DateTime<Utc>),
+    Firing(DateTime<Utc>),
+}
+
+/// A rule together with its evaluation state
+#[derive(Debug)]
+struct RuleState {
+    rule: AlertRule,
+    actions: Arc<Vec<Action>>,
+    phase: Phase,
+    /// Whether the current firing episode was notified
+    notified: bool,
+    last_notified: Option<DateTime<Utc>>,
+}
+
+/// Evaluates alert rules against a stream of reports
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::PowerReserveReport;
+/// use pwrzv::alert::{AlertEngine, AlertRule, AlertState};
+/// use std::collections::HashMap;
+///
+/// let rule = AlertRule::new("low-reserve", "level < 2.0", vec![]).unwrap();
+/// let mut engine = AlertEngine::new(vec![rule]);
+///
+/// let events = engine.evaluate(&PowerReserveReport::new(1.5, HashMap::new()));
+/// assert_eq!(events[0].state, AlertState::Firing);
+/// ```
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<RuleState>,
+}
+
+impl AlertEngine {
+    /// Create an engine from validated rules
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .map(|rule| RuleState {
+                actions: Arc::new(rule.actions.clone()),
+                rule,
+                phase: Phase::Inactive,
+                notified: false,
+                last_notified: None,
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Load rules from a YAML string
+    pub fn from_yaml(yaml: &str) -> PwrzvResult<Self> {
+        let file: AlertFile = serde_yaml::from_str(yaml)
+            .map_err(|e| PwrzvError::invalid_value(&format!("alert rules: {e}")))?;
+
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for rule in file.rules {
+            let cooldown = match &rule.cooldown {
+                Some(cooldown) => timespec::parse_duration(cooldown)?,
+                None => DEFAULT_COOLDOWN,
+            };
+            let mut parsed = AlertRule::new(&rule.name, &rule.condition, rule.actions)?;
+            parsed.cooldown = cooldown;
+            parsed.notify_resolved = rule.notify_resolved;
+            rules.push(parsed);
+        }
+        Ok(Self::new(rules))
+    }
+
+    /// Load rules from a YAML file
+    pub fn from_file(path: impl AsRef<Path>) -> PwrzvResult<Self> {
+        let path = path.as_ref();
+        let yaml = std::fs::read_to_string(path).map_err(|e| PwrzvError::read_error(path, e))?;
+        Self::from_yaml(&yaml)
+    }
+
+    /// Get the configured rules
+    pub fn rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().map(|state| &state.rule)
+    }
+
+    /// Get the names of rules that are currently firing
+    pub fn firing(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|state| matches!(state.phase, Phase::Firing(_)))
+            .map(|state| state.rule.name.as_str())
+            .collect()
+    }
+
+    /// Advance every rule with a new report and return the notifications to send
+    ///
+    /// Rule durations are measured with report timestamps, so reports must be
+    /// passed in chronological order.
+    pub fn evaluate(&mut self, report: &PowerReserveReport) -> Vec<AlertEvent> {
+        let now = report.timestamp;
+        let shared = Arc::new(report.clone());
+        let mut events = Vec::new();
+
+        for state in &mut self.rules {
+            let condition = &state.rule.condition;
+            let value = condition.observe(report);
+            let holds = condition.matches(report);
+
+            if holds && state.phase == Phase::Inactive {
+                state.phase = Phase::Pending(now);
+            }
+
+            let transition = match state.phase {
+                Phase::Pending(_) if !holds => {
+                    state.phase = Phase::Inactive;
+                    None
+                }
+                Phase::Pending(since) if elapsed(since, now) >= condition.hold => {
+                    state.phase = Phase::Firing(since);
+                    let rate_limited = state
+                        .last_notified
+                        .is_some_and(|last| elapsed(last, now) < state.rule.cooldown);
+                    state.notified = !rate_limited;
+                    if state.notified {
+                        state.last_notified = Some(now);
+                    }
+                    state.notified.then_some((AlertState::Firing, since))
+                }
+                // Send a firing notification deferred by the cooldown once it is over
+                Phase::Firing(since)
+                    if holds
+                        && !state.notified
+                        && state
+                            .last_notified
+                            .is_none_or(|last| elapsed(last, now) >= state.rule.cooldown) =>
+                {
+                    state.notified = true;
+                    state.last_notified = Some(now);
+                    Some((AlertState::Firing, since))
+                }
+                Phase::Firing(since) if !holds => {
+                    state.phase = Phase::Inactive;
+                    let notify = state.notified && state.rule.notify_resolved;
+                    state.notified = false;
+                    notify.then_some((AlertState::Resolved, since))
+                }
+                _ => None,
+            };
+
+            if let Some((alert_state, since)) = transition {
+                events.push(AlertEvent {
+                    rule: state.rule.name.clone(),
+                    state: alert_state,
+                    condition: condition.clone(),
+                    value,
+                    since,
+                    report: Arc::clone(&shared),
+                    actions: Arc::clone(&state.actions),
+                });
+            }
+        }
+
+        events
+    }
+}
+
+/// Time between two instants, zero if `to` precedes `from`
+fn elapsed(from: DateTime<Utc>, to: DateTime<Utc>) -> Duration {
+    (to - from).to_std().unwrap_or_default()
+}
+
+// ================================
+// Notifications
+// ================================
+
+impl AlertEvent {
+    /// Run every action of the rule, returning the result of each
+    pub async fn notify(&self) -> Vec<PwrzvResult<()>> {
+        let mut results = Vec::with_capacity(self.actions.len());
+        for action in self.actions.iter() {
+            results.push(self.run_action(action).await);
+        }
+        results
+    }
+
+    async fn run_action(&self, action: &Action) -> PwrzvResult<()> {
+        match action {
+            Action::Webhook { url, body, headers } => {
+                let body = match body {
+                    Some(template) => self.render(template),
+                    None => self.default_body(),
+                };
+                post_json(url, headers, &body).await
+            }
+            Action::Command { command, body } => {
+                let input = match body {
+                    Some(template) => self.render(template),
+                    None => serde_json::to_string(self.report.as_ref()).unwrap_or_default(),
+                };
+                self.run_command(command, &input).await
+            }
+        }
+    }
+
+    /// Render a template, JSON-escaping string values
+    pub fn render(&self, template: &str) -> String {
+        render_template(template, |name| self.variable(name))
+    }
+
+    /// Get the rendered value of a template variable
+    fn variable(&self, name: &str) -> Option<String> {
+        let escape = |s: &str| {
+            let quoted = serde_json::Value::String(s.to_string()).to_string();
+            quoted[1..quoted.len() - 1].to_string()
+        };
+        let value = match name {
+            "rule" => escape(&self.rule),
+            "state" => self.state.as_str().to_string(),
+            "condition" => escape(&self.condition.to_string()),
+            "level" => format!("{:.2}", self.report.power_reserve_level),
+            "level_description" => escape(&self.report.level_description),
+            "value" => self.value.map(|v| v.to_string()).unwrap_or_default(),
+            "threshold" => self.condition.threshold.to_string(),
+            "since" => self.since.to_rfc3339(),
+            "timestamp" => self.report.timestamp.to_rfc3339(),
+            "host" => escape(&hostname()),
+            "platform" => escape(&self.report.platform),
+            "report" => serde_json::to_string(self.report.as_ref()).unwrap_or_default(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// JSON body sent by webhooks without a template
+    fn default_body(&self) -> String {
+        serde_json::json!({
+            "rule": self.rule,
+            "state": self.state.as_str(),
+            "condition": self.condition.to_string(),
+            "value": self.value,
+            "since": self.since,
+            "host": hostname(),
+            "report": self.report.as_ref(),
+        })
+        .to_string()
+    }
+
+    async fn run_command(&self, command: &str, input: &str) -> PwrzvResult<()> {
+        let mut child = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("PWRZV_ALERT_RULE", &self.rule)
+            .env("PWRZV_ALERT_STATE", self.state.as_str())
+            .env("PWRZV_ALERT_CONDITION", self.condition.to_string())
+            .env(
+                "PWRZV_ALERT_LEVEL",
+                self.report.power_reserve_level.to_string(),
+            )
+            .env(
+                "PWRZV_ALERT_VALUE",
+                self.value.map(|v| v.to_string()).unwrap_or_default(),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| PwrzvError::command_error(command, e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that ignores its input may close stdin early
+            let _ = stdin.write_all(input.as_bytes()).await;
+        }
+
+        let status = tokio::time::timeout(COMMAND_TIMEOUT, child.wait())
+            .await
+            .map_err(|_| {
+                PwrzvError::timed_out(&format!("alert command {command}"), COMMAND_TIMEOUT)
+            })??;
+
+        if status.success() {
+            Ok(())
+        } else {
+            Err(PwrzvError::command_failed(
+                command,
+                &format!("exited with {status}"),
+            ))
+        }
+    }
+}
+
+/// Replace `{{ name }}` placeholders using `lookup`, leaving unknown ones untouched
+fn render_template(template: &str, mut lookup: impl FnMut(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = after[..end].trim();
+                match lookup(name) {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Ensure a template only uses known placeholders
+fn check_template(template: &str) -> Result<(), String> {
+    let mut unknown = None;
+    render_template(template, |name| {
+        if !TEMPLATE_VARIABLES.contains(&name) && unknown.is_none() {
+            unknown = Some(name.to_string());
+        }
+        Some(String::new())
+    });
+    match unknown {
+        Some(name) => Err(format!(
+            "unknown template variable '{{{{{name}}}}}' (available: {})",
+            TEMPLATE_VARIABLES.join(", ")
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Get the host name used in notifications
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Split an `http://host[:port]/path` URL into (host, port, path)
+fn parse_http_url(url: &str) -> PwrzvResult<(String, u16, String)> {
+    let Some(rest) = url.strip_prefix("http://") else {
+        return Err(PwrzvError::invalid_value(&format!(
+            "unsupported webhook URL '{url}': only http:// is supported \
+             (use a command action with curl for HTTPS endpoints)"
+        )));
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let invalid_port =
+        || PwrzvError::invalid_value(&format!("invalid port in webhook URL '{url}'"));
+    // Bracketed IPv6 literals contain colons of their own
+    let (host, port) = match authority.rfind(':') {
+        Some(i) if !authority[i..].contains(']') => (
+            &authority[..i],
+            authority[i + 1..].parse().map_err(|_| invalid_port())?,
+        ),
+        _ => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(PwrzvError::invalid_value(&format!(
+            "missing host in webhook URL '{url}'"
+        )));
+    }
+
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+/// POST a JSON body and require a 2xx response
+async fn post_json(url: &str, headers: &BTreeMap<String, String>, body: &str) -> PwrzvResult<()> {
+    let (host, port, path) = parse_http_url(url)?;
+
+    let request = async {
+        let mut stream = TcpStream::connect((host.trim_matches(['[', ']']), port)).await?;
+
+        let mut head = format!(
+            "POST {path} HTTP/1.1\r\nHost: {host}:{port}\r\nUser-Agent: pwrzv/{}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+            env!("CARGO_PKG_VERSION"),
+            body.len()
+        );
+        for (name, value) in headers {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+        head.push_str("\r\n");
+
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        Ok::<_, std::io::Error>(response)
+    };
+
+    let response = tokio::time::timeout(WEBHOOK_TIMEOUT, request)
+        .await
+        .map_err(|_| PwrzvError::timed_out(&format!("webhook {url}"), WEBHOOK_TIMEOUT))??;
+
+    let status_line = String::from_utf8_lossy(&response)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    match status_line.split_whitespace().nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(PwrzvError::resource_access_error(&format!(
+            "webhook {url} responded with '{status_line}'"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::net::TcpListener;
+
+    /// Report with the given level and memory pressure taken `secs` after a fixed epoch
+    fn report_at(secs: i64, level: f32, memory_pressure: f32) -> PowerReserveReport {
+        let mut metrics = HashMap::new();
+        metrics.insert(
+            format!("Memory Pressure: {memory_pressure:.3} (Score: {level:.3})"),
+            level,
+        );
+        let mut report = PowerReserveReport::new(level, metrics);
+        report.timestamp = DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap();
+        report
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        let condition: Condition = "level < 2.0 for 60s".parse().unwrap();
+        assert_eq!(condition.subject, Subject::Level);
+        assert_eq!(condition.comparison, Comparison::Less);
+        assert_eq!(condition.threshold, 2.0);
+        assert_eq!(condition.hold, Duration::from_secs(60));
+        assert_eq!(condition.to_string(), "level < 2 for 60s");
+
+        let condition: Condition = "metric memory_pressure > 0.4".parse().unwrap();
+        assert_eq!(
+            condition.subject,
+            Subject::Metric {
+                id: "memory_pressure".to_string(),
+                score: false
+            }
+        );
+        assert_eq!(condition.hold, Duration::ZERO);
+
+        let condition: Condition = "metric cpu_usage.score <= 1.5 for 2m".parse().unwrap();
+        assert!(matches!(
+            condition.subject,
+            Subject::Metric { score: true, .. }
+        ));
+
+        for invalid in [
+            "",
+            "level",
+            "level ~ 2",
+            "level < abc",
+            "level < 2 for ever",
+            "load < 2",
+            "level < 2 during 5s",
+        ] {
+            assert!(invalid.parse::<Condition>().is_err(), "'{invalid}'");
+        }
+    }
+
+    #[test]
+    fn test_rule_fires_after_hold_and_resolves() {
+        let rule = AlertRule::new("low", "level < 2.0 for 60s", vec![]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        assert!(engine.evaluate(&report_at(0, 1.5, 0.1)).is_empty());
+        assert!(engine.evaluate(&report_at(30, 1.5, 0.1)).is_empty());
+
+        let events = engine.evaluate(&report_at(60, 1.5, 0.1));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, AlertState::Firing);
+        assert_eq!(events[0].since, report_at(0, 0.0, 0.0).timestamp);
+        assert_eq!(engine.firing(), vec!["low"]);
+
+        // Still firing: no repeated notification
+        assert!(engine.evaluate(&report_at(90, 1.2, 0.1)).is_empty());
+
+        let events = engine.evaluate(&report_at(120, 3.0, 0.1));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, AlertState::Resolved);
+        assert!(engine.firing().is_empty());
+    }
+
+    #[test]
+    fn test_rule_pending_resets_on_recovery() {
+        let rule = AlertRule::new("low", "level < 2.0 for 60s", vec![]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        engine.evaluate(&report_at(0, 1.5, 0.1));
+        engine.evaluate(&report_at(50, 3.0, 0.1));
+        assert!(engine.evaluate(&report_at(70, 1.5, 0.1)).is_empty());
+        assert!(engine.evaluate(&report_at(100, 1.5, 0.1)).is_empty());
+        assert_eq!(engine.evaluate(&report_at(130, 1.5, 0.1)).len(), 1);
+    }
+
+    #[test]
+    fn test_metric_rule() {
+        let rule = AlertRule::new("mem", "metric memory_pressure > 0.4", vec![]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        assert!(engine.evaluate(&report_at(0, 4.0, 0.2)).is_empty());
+        let events = engine.evaluate(&report_at(3, 2.0, 0.5));
+        assert_eq!(events[0].state, AlertState::Firing);
+        assert_eq!(events[0].value, Some(0.5));
+
+        // A missing metric does not satisfy the condition
+        let mut missing = report_at(6, 2.0, 0.5);
+        missing.metrics.clear();
+        assert_eq!(engine.evaluate(&missing)[0].state, AlertState::Resolved);
+    }
+
+    #[test]
+    fn test_cooldown_rate_limits_notifications() {
+        let mut rule = AlertRule::new("low", "level < 2.0", vec![]).unwrap();
+        rule.cooldown = Duration::from_secs(300);
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        assert_eq!(engine.evaluate(&report_at(0, 1.0, 0.1)).len(), 1);
+        assert_eq!(engine.evaluate(&report_at(10, 3.0, 0.1)).len(), 1);
+
+        // Flapping within the cooldown is silent, including its resolution
+        assert!(engine.evaluate(&report_at(20, 1.0, 0.1)).is_empty());
+        assert_eq!(engine.firing(), vec!["low"]);
+        assert!(engine.evaluate(&report_at(30, 3.0, 0.1)).is_empty());
+
+        // After the cooldown, notifications resume
+        assert_eq!(engine.evaluate(&report_at(400, 1.0, 0.1)).len(), 1);
+    }
+
+    #[test]
+    fn test_cooldown_defers_sustained_alert() {
+        let mut rule = AlertRule::new("low", "level < 2.0", vec![]).unwrap();
+        rule.cooldown = Duration::from_secs(300);
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        // Flap, then stay low from within the cooldown
+        assert_eq!(engine.evaluate(&report_at(0, 1.0, 0.1)).len(), 1);
+        assert_eq!(engine.evaluate(&report_at(10, 3.0, 0.1)).len(), 1);
+        assert!(engine.evaluate(&report_at(20, 1.0, 0.1)).is_empty());
+        assert!(engine.evaluate(&report_at(200, 1.0, 0.1)).is_empty());
+
+        // The deferred notification goes out once the cooldown is over
+        let events = engine.evaluate(&report_at(300, 1.0, 0.1));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, AlertState::Firing);
+        assert_eq!(events[0].since, report_at(20, 1.0, 0.1).timestamp);
+        assert!(engine.evaluate(&report_at(310, 1.0, 0.1)).is_empty());
+
+        // And so does its resolution
+        let events = engine.evaluate(&report_at(320, 3.0, 0.1));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].state, AlertState::Resolved);
+    }
+
+    #[test]
+    fn test_from_yaml() {
+        let engine = AlertEngine::from_yaml(
+            r#"
+rules:
+  - name: low-reserve
+    condition: level < 2.0 for 60s
+    cooldown: 10m
+    notify_resolved: false
+    actions:
+      - type: webhook
+        url: http://127.0.0.1:9000/alerts
+        body: '{"text": "{{rule}} is {{ state }}"}'
+      - type: command
+        command: logger -t pwrzv
+"#,
+        )
+        .unwrap();
+
+        let rule = engine.rules().next().unwrap();
+        assert_eq!(rule.name, "low-reserve");
+        assert_eq!(rule.cooldown, Duration::from_secs(600));
+        assert!(!rule.notify_resolved);
+        assert_eq!(rule.actions.len(), 2);
+    }
+
+    #[test]
+    fn test_from_yaml_rejects_invalid_rules() {
+        let unknown_variable = r#"
+rules:
+  - name: x
+    condition: level < 2
+    actions:
+      - type: webhook
+        url: http://localhost/
+        body: '{"text": "{{nope}}"}'
+"#;
+        let https = r#"
+rules:
+  - name: x
+    condition: level < 2
+    actions:
+      - type: webhook
+        url: https://hooks.example.com/
+"#;
+        let unknown_field = r#"
+rules:
+  - name: x
+    condition: level < 2
+    actions: []
+    severity: high
+"#;
+        for yaml in [unknown_variable, https, unknown_field] {
+            assert!(AlertEngine::from_yaml(yaml).is_err());
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let rule = AlertRule::new("say \"hi\"", "level < 2.0", vec![]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+        let event = engine.evaluate(&report_at(0, 1.25, 0.1)).remove(0);
+
+        let body = event.render(r#"{"text": "{{rule}} {{state}} at {{level}}", "x": "{{other}}"}"#);
+        assert_eq!(
+            body,
+            r#"{"text": "say \"hi\" firing at 1.25", "x": "{{other}}"}"#
+        );
+
+        let report: serde_json::Value = serde_json::from_str(&event.render("{{report}}")).unwrap();
+        assert_eq!(report["power_reserve_level"], 1.25);
+    }
+
+    #[test]
+    fn test_parse_http_url() {
+        assert_eq!(
+            parse_http_url("http://example.com:8080/hook?x=1").unwrap(),
+            ("example.com".to_string(), 8080, "/hook?x=1".to_string())
+        );
+        assert_eq!(
+            parse_http_url("http://localhost").unwrap(),
+            ("localhost".to_string(), 80, "/".to_string())
+        );
+        assert!(parse_http_url("https://example.com/").is_err());
+        assert!(parse_http_url("http://:80/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notification() {
+        // Local stand-in for a webhook receiver
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let receiver = tokio::spawn(async move {
+            let mut bodies = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length: usize = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Content-Length: "))
+                            .unwrap()
+                            .parse()
+                            .unwrap();
+                        if body.len() >= length {
+                            bodies.push((head.to_string(), body.to_string()));
+                            break;
+                        }
+                    }
+                }
+                stream
+                    .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+            }
+            bodies
+        });
+
+        let action = Action::Webhook {
+            url: format!("http://{addr}/alerts"),
+            body: Some(r#"{"text": "{{rule}} is {{state}}"}"#.to_string()),
+            headers: BTreeMap::from([("X-Token".to_string(), "secret".to_string())]),
+        };
+        let rule = AlertRule::new("low-reserve", "level < 2.0", vec![action]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        for report in [report_at(0, 1.0, 0.1), report_at(5, 4.0, 0.1)] {
+            for event in engine.evaluate(&report) {
+                for result in event.notify().await {
+                    result.unwrap();
+                }
+            }
+        }
+
+        let bodies = receiver.await.unwrap();
+        assert!(bodies[0].0.starts_with("POST /alerts HTTP/1.1"));
+        assert!(bodies[0].0.contains("X-Token: secret"));
+        assert_eq!(bodies[0].1, r#"{"text": "low-reserve is firing"}"#);
+        assert_eq!(bodies[1].1, r#"{"text": "low-reserve is resolved"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_error_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\n\r\n")
+                .await;
+        });
+
+        let result = post_json(&format!("http://{addr}/"), &BTreeMap::new(), "{}").await;
+        assert!(result.unwrap_err().to_string().contains("500"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_notification() {
+        let out = std::env::temp_dir().join(format!("pwrzv-alert-cmd-{}", std::process::id()));
+        let action = Action::Command {
+            command: format!(
+                "{{ cat; echo; echo \"$PWRZV_ALERT_STATE\"; }} > {}",
+                out.display()
+            ),
+            body: None,
+        };
+        let rule = AlertRule::new("low-reserve", "level < 2.0", vec![action]).unwrap();
+        let mut engine = AlertEngine::new(vec![rule]);
+
+        let event = engine.evaluate(&report_at(0, 1.0, 0.1)).remove(0);
+        for result in event.notify().await {
+            result.unwrap();
+        }
+
+        let written = std::fs::read_to_string(&out).unwrap();
+        let (report, state) = written.trim_end().rsplit_once('\n').unwrap();
+        let report: PowerReserveReport = serde_json::from_str(report).unwrap();
+        assert_eq!(report.power_reserve_level, 1.0);
+        assert_eq!(state, "firing");
+        std::fs::remove_file(&out).unwrap();
+
+        let failing = Action::Command {
+            command: "exit 3".to_string(),
+            body: None,
+        };
+        let rule = AlertRule::new("x", "level < 2.0", vec![failing]).unwrap();
+        let event = AlertEngine::new(vec![rule])
+            .evaluate(&report_at(0, 1.0, 0.1))
+            .remove(0);
+        assert!(event.notify().await[0].is_err());
+    }
+}
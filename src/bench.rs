@@ -0,0 +1,42 @@
+//! Parser entry points for the benchmarks in `benches/`
+//!
+//! Only built with the `bench` feature. Not part of the public API; the
+//! signatures change with the parsers.
+
+use crate::linux::metrics::LinuxSystemMetrics;
+
+/// Parse `/proc/stat`, returning the total CPU time
+pub fn parse_cpu_stat(content: &str) -> Option<u64> {
+    LinuxSystemMetrics::parse_cpu_stat(content).map(|stat| stat.total())
+}
+
+/// Parse `/proc/loadavg`
+pub fn parse_load_average(content: &str) -> Option<f32> {
+    LinuxSystemMetrics::parse_load_average(content)
+}
+
+/// Parse `/proc/cpuinfo`, returning the core count
+pub fn parse_cpu_cores(content: &str) -> Option<u32> {
+    LinuxSystemMetrics::parse_cpu_cores(content)
+}
+
+/// Parse `/proc/meminfo`, returning the memory usage ratio
+pub fn parse_memory_usage(content: &str) -> Option<f32> {
+    LinuxSystemMetrics::parse_memory_usage(content)
+}
+
+/// Parse `/proc/pressure/memory`
+pub fn parse_memory_pressure(content: &str) -> Option<f32> {
+    LinuxSystemMetrics::parse_memory_pressure(content)
+}
+
+/// Parse `/proc/net/dev`, returning the dropped packets ratio
+pub fn parse_network_stats(content: &str) -> Option<f32> {
+    LinuxSystemMetrics::parse_network_stats(content)
+        .and_then(|stats| LinuxSystemMetrics::calculate_instant_dropped_packets_ratio(&stats))
+}
+
+/// Parse `/proc/diskstats`, returning the I/O time of the busiest disk
+pub fn parse_disk_stats(content: &str) -> Option<u64> {
+    LinuxSystemMetrics::parse_disk_stats(content).and_then(|stats| stats.into_values().max())
+}
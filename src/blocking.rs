@@ -0,0 +1,220 @@
+//! Blocking API
+//!
+//! Synchronous counterparts of the crate's async functions, for programs
+//! without an async runtime. They are available without the `async` feature,
+//! so the library can be used without tokio:
+//!
+//! ```toml
+//! [dependencies]
+//! pwrzv = { version = "0.6", default-features = false }
+//! ```
+//!
+//! Collectors still run in parallel on their own threads, each with its
+//! timeout from the `[collection]` configuration section.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pwrzv::blocking;
+//!
+//! fn main() -> Result<(), pwrzv::PwrzvError> {
+//!     let level = blocking::get_power_reserve_level()?;
+//!     println!("Power Reserve Level: {:.2}", level);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::config::{self, PwrzvConfig};
+use crate::error::PwrzvResult;
+use crate::report::{PowerReserveReport, score_readings};
+use crate::trace::TraceRecord;
+use crate::{Calculator, UnitPowerReserve, platform_metrics};
+use chrono::Utc;
+use std::collections::HashMap;
+
+#[cfg(not(target_os = "linux"))]
+use crate::error::PwrzvError;
+#[cfg(target_os = "linux")]
+use crate::linux::cgroup::{self, CgroupProvider};
+
+/// Get the power reserve level of the system
+///
+/// Blocking version of [`crate::get_power_reserve_level_direct`].
+///
+/// # Returns
+///
+/// Power reserve level as f32 (1.0-5.0) with decimal precision
+pub fn get_power_reserve_level() -> PwrzvResult<f32> {
+    Calculator::new()?.get_power_reserve_level()
+}
+
+/// Get the power reserve level of the system with detailed metrics
+///
+/// Blocking version of [`crate::get_power_reserve_level_with_details_direct`].
+///
+/// # Returns
+///
+/// A tuple of (level, details) where details holds the pressure score of
+/// each available metric
+pub fn get_power_reserve_level_with_details() -> PwrzvResult<(f32, HashMap<String, f32>)> {
+    Calculator::new()?.get_power_reserve_level_with_details()
+}
+
+/// Get a detailed power reserve report
+///
+/// Blocking version of [`crate::get_power_reserve_report`]. The report lists
+/// the status of every collector.
+///
+/// # Example
+///
+/// ```rust
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let report = pwrzv::blocking::get_power_reserve_report()?;
+///     println!("{}", serde_json::to_string_pretty(&report)?);
+///     Ok(())
+/// }
+/// ```
+pub fn get_power_reserve_report() -> PwrzvResult<PowerReserveReport> {
+    get_power_reserve_report_with_config(&config::global())
+}
+
+/// Get a detailed power reserve report using the given configuration
+///
+/// Unlike [`get_power_reserve_report`], this ignores the process-wide
+/// configuration, so callers can sample with several configurations or read
+/// a fixture tree through [`CollectionConfig::root`](crate::config::CollectionConfig::root).
+pub fn get_power_reserve_report_with_config(
+    config: &PwrzvConfig,
+) -> PwrzvResult<PowerReserveReport> {
+    let calculator = Calculator::new()?;
+    let collection = calculator.collect_checked(config)?;
+    let (level, details) = calculator.calculate_inputs(&collection.inputs, config);
+    let mut report = PowerReserveReport::new(level, details);
+    report.readings = score_readings(
+        std::env::consts::OS,
+        platform_metrics(),
+        &collection.inputs,
+        config,
+    );
+    report.collectors = collection.collectors;
+    report.notes = collection.notes;
+    report.effective_cpus = collection.effective_cpus;
+    Ok(report)
+}
+
+/// Record the raw metric values of the system as a trace record
+///
+/// Blocking version of [`crate::trace::record`].
+pub fn record_trace() -> PwrzvResult<TraceRecord> {
+    let calculator = Calculator::new()?;
+    let inputs = calculator.collect_inputs()?;
+    let (level, _) = calculator.calculate_inputs(&inputs, &config::global());
+
+    Ok(TraceRecord {
+        timestamp: Utc::now(),
+        platform: std::env::consts::OS.to_string(),
+        level,
+        inputs,
+        label: None,
+    })
+}
+
+/// Get the power reserve level of a systemd unit with detailed metrics
+///
+/// Blocking version of [`crate::get_unit_power_reserve_level_with_details`],
+/// which describes how the unit is resolved.
+///
+/// # Arguments
+///
+/// * `unit` - The unit name, e.g. `nginx.service`
+pub fn get_unit_power_reserve_level_with_details(
+    unit: &str,
+) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+    #[cfg(target_os = "linux")]
+    {
+        let root = cgroup::find_cgroup_root()?;
+        let path = cgroup::resolve_unit(&root, unit)?;
+        CgroupProvider::new(path).get_power_reserve_level_with_details()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = unit;
+        Err(PwrzvError::unsupported_platform(&format!(
+            "{} (systemd unit mode requires Linux)",
+            std::env::consts::OS
+        )))
+    }
+}
+
+/// Get the power reserve level of a systemd unit
+///
+/// Blocking version of [`crate::get_unit_power_reserve_level`].
+pub fn get_unit_power_reserve_level(unit: &str) -> PwrzvResult<f32> {
+    let (level, _) = get_unit_power_reserve_level_with_details(unit)?;
+    Ok(level)
+}
+
+/// Get the power reserve of every running systemd service
+///
+/// Blocking version of [`crate::get_running_units_power_reserve`].
+///
+/// # Returns
+///
+/// One entry per running unit, sorted by lowest reserve first
+pub fn get_running_units_power_reserve() -> PwrzvResult<Vec<UnitPowerReserve>> {
+    #[cfg(target_os = "linux")]
+    {
+        let root = cgroup::find_cgroup_root()?;
+        let mut reserves = Vec::new();
+
+        for (unit, path) in cgroup::list_running_units(&root)? {
+            // Units may stop between discovery and collection; skip those
+            if let Ok((level, details)) =
+                CgroupProvider::new(path).get_power_reserve_level_with_details()
+            {
+                reserves.push(UnitPowerReserve {
+                    unit,
+                    level,
+                    details,
+                });
+            }
+        }
+
+        reserves.sort_by(|a, b| {
+            a.level
+                .total_cmp(&b.level)
+                .then_with(|| a.unit.cmp(&b.unit))
+        });
+        Ok(reserves)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(PwrzvError::unsupported_platform(&format!(
+            "{} (systemd unit mode requires Linux)",
+            std::env::consts::OS
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_report() {
+        let report = get_power_reserve_report().unwrap();
+        assert!((1.0..=5.0).contains(&report.power_reserve_level));
+        assert_eq!(report.total_metrics, report.metrics.len());
+        assert_eq!(report.readings.len(), report.metrics.len());
+        assert!(!report.collectors.is_empty());
+        assert_eq!(
+            report.effective_cpus.is_some(),
+            cfg!(target_os = "linux"),
+            "{:?}",
+            report.effective_cpus
+        );
+
+        let level = get_power_reserve_level().unwrap();
+        assert!((1.0..=5.0).contains(&level));
+    }
+}
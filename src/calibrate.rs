@@ -0,0 +1,545 @@
+//! Curve calibration module
+//!
+//! Fits the sigmoid midpoint and steepness of every metric to a trace whose
+//! samples are labelled healthy or degraded, so that hosts with different
+//! normal profiles get their own curves.
+//!
+//! Calibration is driven by percentile targets such as `p95 of healthy = 4`:
+//! the 95th percentile of the healthy values of each metric should score 4.
+//! Two targets pin both curve parameters; with a single usable target only the
+//! midpoint is fitted and the current steepness is kept. Fitted metrics always
+//! use the logistic curve.
+
+use crate::config::{MetricConfig, PwrzvConfig};
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::platform::metrics_for;
+use crate::trace::{SampleLabel, TraceRecord};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Targets used when none are given
+pub const DEFAULT_TARGETS: [&str; 2] = ["p95 of healthy = 4", "p50 of degraded = 1.5"];
+
+/// A percentile of labelled samples that should map to a score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CalibrationTarget {
+    /// Percentile of the labelled values (0-100)
+    pub percentile: f32,
+    /// Which samples the percentile is taken over
+    pub label: SampleLabel,
+    /// Score the percentile should map to (exclusive 0.0-5.0)
+    pub score: f32,
+}
+
+impl FromStr for CalibrationTarget {
+    type Err = PwrzvError;
+
+    /// Parse a target such as `p95 of healthy = 4`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            PwrzvError::invalid_value(&format!("calibration target '{s}': {reason}"))
+        };
+
+        let (selector, score) = s
+            .split_once('=')
+            .ok_or_else(|| invalid("expected 'pNN of LABEL = SCORE'"))?;
+        let mut words = selector.split_whitespace();
+        let (Some(percentile), Some("of"), Some(label), None) =
+            (words.next(), words.next(), words.next(), words.next())
+        else {
+            return Err(invalid("expected 'pNN of LABEL = SCORE'"));
+        };
+
+        let percentile = percentile
+            .strip_prefix('p')
+            .and_then(|p| p.parse::<f32>().ok())
+            .filter(|p| (0.0..=100.0).contains(p))
+            .ok_or_else(|| invalid("percentile must be p0 to p100"))?;
+        let label = label.parse::<SampleLabel>()?;
+        let score = score
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|score| *score > 0.0 && *score < 5.0)
+            .ok_or_else(|| invalid("score must be between 0 and 5 (exclusive)"))?;
+
+        Ok(Self {
+            percentile,
+            label,
+            score,
+        })
+    }
+}
+
+impl fmt::Display for CalibrationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "p{} of {} = {}", self.percentile, self.label, self.score)
+    }
+}
+
+/// Value of a metric at a calibration target
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Anchor {
+    /// The target
+    pub target: CalibrationTarget,
+    /// Metric value at the target percentile
+    pub value: f32,
+}
+
+/// Fitted curve of a single metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricFit {
+    /// Metric ID
+    pub id: String,
+    /// Fitted sigmoid midpoint
+    pub midpoint: f32,
+    /// Fitted (or kept) sigmoid steepness
+    pub steepness: f32,
+    /// Targets the fit is based on
+    pub anchors: Vec<Anchor>,
+    /// Why the fit deviates from the targets, if it does
+    pub note: Option<String>,
+}
+
+/// Result of a calibration run
+#[derive(Debug, Clone, Serialize)]
+pub struct Calibration {
+    /// Base configuration with the fitted curves filled in
+    pub config: PwrzvConfig,
+    /// Fitted metrics
+    pub fits: Vec<MetricFit>,
+    /// Metrics left unchanged, with the reason
+    pub skipped: Vec<(String, String)>,
+    /// Number of healthy samples
+    pub healthy_samples: usize,
+    /// Number of degraded samples
+    pub degraded_samples: usize,
+}
+
+impl Calibration {
+    /// Render the configuration as TOML, with a comment header describing the fit
+    pub fn to_toml(&self) -> String {
+        let mut out = format!(
+            "# Generated by pwrzv calibrate from {} healthy and {} degraded samples\n#\n",
+            self.healthy_samples, self.degraded_samples
+        );
+        for fit in &self.fits {
+            let anchors = fit
+                .anchors
+                .iter()
+                .map(|anchor| {
+                    format!(
+                        "{} p{} = {:.4} -> {}",
+                        anchor.target.label,
+                        anchor.target.percentile,
+                        anchor.value,
+                        anchor.target.score
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            out.push_str(&format!("# {}: {anchors}\n", fit.id));
+            if let Some(note) = &fit.note {
+                out.push_str(&format!("#   {note}\n"));
+            }
+        }
+        for (id, reason) in &self.skipped {
+            out.push_str(&format!("# {id}: unchanged ({reason})\n"));
+        }
+        out.push('\n');
+        out.push_str(&self.config.to_toml());
+        out
+    }
+}
+
+/// Fit metric curves to a labelled trace
+///
+/// # Arguments
+///
+/// * `records` - Trace samples, all recorded on the same platform; unlabelled
+///   samples are ignored
+/// * `targets` - Percentile targets, see [`DEFAULT_TARGETS`]
+/// * `base` - Configuration the fitted curves are merged into; its effective
+///   steepness is kept when only the midpoint can be fitted
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::calibrate::{self, DEFAULT_TARGETS};
+/// use pwrzv::config::PwrzvConfig;
+///
+/// let trace = pwrzv::trace::parse_trace(concat!(
+///     r#"{"timestamp":"2025-01-01T00:00:00Z","platform":"linux","level":4.0,"#,
+///     r#""inputs":{"cpu_usage":0.3},"label":"healthy"}"#,
+/// )).unwrap();
+/// let targets = DEFAULT_TARGETS.map(|t| t.parse().unwrap());
+///
+/// let calibration = calibrate::calibrate(&trace, &targets, &PwrzvConfig::default()).unwrap();
+/// println!("{}", calibration.to_toml());
+/// ```
+pub fn calibrate(
+    records: &[TraceRecord],
+    targets: &[CalibrationTarget],
+    base: &PwrzvConfig,
+) -> PwrzvResult<Calibration> {
+    let platform = records
+        .first()
+        .map_or(std::env::consts::OS, |r| r.platform.as_str());
+    if let Some(record) = records.iter().find(|r| r.platform != platform) {
+        return Err(PwrzvError::invalid_value(&format!(
+            "trace mixes samples recorded on {platform} and {}",
+            record.platform
+        )));
+    }
+    let specs = metrics_for(platform).ok_or_else(|| PwrzvError::unsupported_platform(platform))?;
+    if targets.is_empty() {
+        return Err(PwrzvError::invalid_value(
+            "at least one calibration target is required",
+        ));
+    }
+
+    let labelled = |label| records.iter().filter(move |r| r.label == Some(label));
+    let healthy_samples = labelled(SampleLabel::Healthy).count();
+    let degraded_samples = labelled(SampleLabel::Degraded).count();
+    if targets
+        .iter()
+        .all(|target| labelled(target.label).next().is_none())
+    {
+        return Err(PwrzvError::invalid_value(
+            "trace has no samples labelled for the calibration targets \
+             (record them with `pwrzv record --label healthy|degraded`)",
+        ));
+    }
+
+    let mut config = base.clone();
+    let mut fits = Vec::new();
+    let mut skipped = Vec::new();
+
+    for spec in specs {
+        let values = |label| {
+            labelled(label)
+                .filter_map(|r| r.inputs.get(spec.id).copied())
+                .collect::<Vec<_>>()
+        };
+        let anchors = targets
+            .iter()
+            .filter_map(|&target| {
+                let value = percentile(&values(target.label), target.percentile)?;
+                Some(Anchor { target, value })
+            })
+            .collect::<Vec<_>>();
+
+        let Some(first) = anchors.first() else {
+            skipped.push((spec.id.to_string(), "no samples".to_string()));
+            continue;
+        };
+        let observed = anchors
+            .iter()
+            .flat_map(|anchor| values(anchor.target.label))
+            .collect::<Vec<_>>();
+        if is_constant(&observed) {
+            skipped.push((
+                spec.id.to_string(),
+                format!("constant value {} in trace", first.value),
+            ));
+            continue;
+        }
+
+        let steepness = spec
+            .curve(platform, base)
+            .steepness()
+            .unwrap_or(spec.steepness);
+        let (midpoint, steepness, note) = match fit_line(&anchors) {
+            Some((midpoint, steepness)) => (midpoint, steepness, None),
+            None if anchors.len() > 1 => {
+                let (midpoint, _) = fit_midpoint(first, steepness);
+                let note = "samples do not separate the targets; \
+                            fitted the midpoint to the first target only";
+                (midpoint, steepness, Some(note.to_string()))
+            }
+            None => {
+                let (midpoint, steepness) = fit_midpoint(first, steepness);
+                (midpoint, steepness, None)
+            }
+        };
+
+        let (midpoint, steepness) = (round4(midpoint), round4(steepness));
+        let section = match platform {
+            "linux" => &mut config.linux,
+            _ => &mut config.macos,
+        };
+        section.insert(
+            spec.id.to_string(),
+            MetricConfig {
+                midpoint: Some(midpoint),
+                steepness: Some(steepness),
+                ..Default::default()
+            },
+        );
+        fits.push(MetricFit {
+            id: spec.id.to_string(),
+            midpoint,
+            steepness,
+            anchors,
+            note,
+        });
+    }
+
+    Ok(Calibration {
+        config,
+        fits,
+        skipped,
+        healthy_samples,
+        degraded_samples,
+    })
+}
+
+/// Linear-interpolated percentile, `None` for no values
+fn percentile(values: &[f32], percentile: f32) -> Option<f32> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let last = sorted.len().checked_sub(1)?;
+
+    let rank = percentile / 100.0 * last as f32;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f32))
+}
+
+fn is_constant(values: &[f32]) -> bool {
+    values.windows(2).all(|pair| pair[0] == pair[1])
+}
+
+/// Sigmoid logit of the value that maps to `score` on the 5-point scale
+fn score_logit(score: f32) -> f32 {
+    // score = 5 * (1 - sigmoid), so sigmoid = 1 - score / 5
+    let p = 1.0 - score / 5.0;
+    (p / (1.0 - p)).ln()
+}
+
+/// Fit only the midpoint so that the anchor maps to its target score
+fn fit_midpoint(anchor: &Anchor, steepness: f32) -> (f32, f32) {
+    (
+        anchor.value - score_logit(anchor.target.score) / steepness,
+        steepness,
+    )
+}
+
+/// Least-squares fit of `logit = steepness * (value - midpoint)` through the anchors
+///
+/// Returns `None` for fewer than two distinct values or a non-increasing curve.
+fn fit_line(anchors: &[Anchor]) -> Option<(f32, f32)> {
+    if anchors.len() < 2 {
+        return None;
+    }
+    let n = anchors.len() as f64;
+    let points = anchors
+        .iter()
+        .map(|a| (a.value as f64, score_logit(a.target.score) as f64));
+    let mean_x = points.clone().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.clone().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx = points
+        .clone()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    let sxy = points
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+
+    if sxx <= f64::EPSILON {
+        return None;
+    }
+    let steepness = sxy / sxx;
+    if !steepness.is_finite() || steepness <= 0.0 {
+        return None;
+    }
+    let midpoint = mean_x - mean_y / steepness;
+    Some((midpoint as f32, steepness as f32))
+}
+
+fn round4(value: f32) -> f32 {
+    (value * 10_000.0).round() / 10_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sigmoid::SigmoidFn;
+    use chrono::DateTime;
+    use std::collections::BTreeMap;
+
+    fn targets() -> Vec<CalibrationTarget> {
+        DEFAULT_TARGETS.iter().map(|t| t.parse().unwrap()).collect()
+    }
+
+    fn sample(label: SampleLabel, cpu_usage: f32) -> TraceRecord {
+        TraceRecord {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            platform: std::env::consts::OS.to_string(),
+            level: 3.0,
+            inputs: BTreeMap::from([
+                ("cpu_usage".to_string(), cpu_usage),
+                ("memory_usage".to_string(), 0.5),
+            ]),
+            label: Some(label),
+        }
+    }
+
+    fn score(fit: &MetricFit, value: f32) -> f32 {
+        let curve = SigmoidFn {
+            midpoint: fit.midpoint,
+            steepness: fit.steepness,
+        };
+        5.0 * (1.0 - curve.evaluate(value))
+    }
+
+    #[test]
+    fn test_target_parsing() {
+        let target: CalibrationTarget = "p95 of healthy = 4".parse().unwrap();
+        assert_eq!(
+            target,
+            CalibrationTarget {
+                percentile: 95.0,
+                label: SampleLabel::Healthy,
+                score: 4.0
+            }
+        );
+        assert_eq!(target.to_string(), "p95 of healthy = 4");
+        assert_eq!(
+            "p50 of degraded=1.5".parse::<CalibrationTarget>().unwrap(),
+            CalibrationTarget {
+                percentile: 50.0,
+                label: SampleLabel::Degraded,
+                score: 1.5
+            }
+        );
+
+        for invalid in [
+            "p95 healthy = 4",
+            "95 of healthy = 4",
+            "p101 of healthy = 4",
+            "p95 of busy = 4",
+            "p95 of healthy = 5",
+            "p95 of healthy",
+        ] {
+            assert!(invalid.parse::<CalibrationTarget>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_percentile() {
+        let values = [4.0, 1.0, 3.0, 2.0, 5.0];
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&values, 50.0), Some(3.0));
+        assert_eq!(percentile(&values, 100.0), Some(5.0));
+        assert_eq!(percentile(&values, 62.5), Some(3.5));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_calibrate_two_targets() {
+        let mut records: Vec<_> = (0..=20)
+            .map(|i| sample(SampleLabel::Healthy, 0.2 + i as f32 * 0.01))
+            .collect();
+        records.extend((0..=10).map(|i| sample(SampleLabel::Degraded, 0.85 + i as f32 * 0.01)));
+
+        let calibration = calibrate(&records, &targets(), &PwrzvConfig::default()).unwrap();
+        assert_eq!(calibration.healthy_samples, 21);
+        assert_eq!(calibration.degraded_samples, 11);
+
+        let fit = calibration
+            .fits
+            .iter()
+            .find(|fit| fit.id == "cpu_usage")
+            .unwrap();
+        // p95 of healthy = 0.39, p50 of degraded = 0.90
+        assert!((score(fit, 0.39) - 4.0).abs() < 0.01);
+        assert!((score(fit, 0.90) - 1.5).abs() < 0.01);
+        assert!(fit.note.is_none());
+
+        // memory_usage never changes, so it keeps its default curve
+        assert!(
+            calibration
+                .skipped
+                .iter()
+                .any(|(id, _)| id == "memory_usage")
+        );
+
+        let config = PwrzvConfig::from_toml(&calibration.to_toml()).unwrap();
+        let platform = std::env::consts::OS;
+        assert_eq!(
+            config.metric(platform, "cpu_usage").midpoint,
+            Some(fit.midpoint)
+        );
+        assert_eq!(config.metric(platform, "memory_usage").midpoint, None);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_calibrate_healthy_only_keeps_steepness() {
+        let records: Vec<_> = (0..10)
+            .map(|i| sample(SampleLabel::Healthy, 0.1 + i as f32 * 0.05))
+            .collect();
+        let base = PwrzvConfig::from_toml(&format!(
+            "[{}.cpu_usage]\nsteepness = 12.0",
+            std::env::consts::OS
+        ))
+        .unwrap();
+
+        let calibration = calibrate(&records, &targets(), &base).unwrap();
+        let fit = &calibration.fits[0];
+        assert_eq!(fit.id, "cpu_usage");
+        assert_eq!(fit.steepness, 12.0);
+        assert!((score(fit, percentile(&[0.1, 0.55], 95.0).unwrap()) - 4.0).abs() < 0.01);
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_calibrate_inverted_samples() {
+        // Degraded samples with lower values cannot produce an increasing curve
+        let records = vec![
+            sample(SampleLabel::Healthy, 0.8),
+            sample(SampleLabel::Healthy, 0.9),
+            sample(SampleLabel::Degraded, 0.1),
+            sample(SampleLabel::Degraded, 0.2),
+        ];
+
+        let calibration = calibrate(&records, &targets(), &PwrzvConfig::default()).unwrap();
+        let fit = &calibration.fits[0];
+        assert!(fit.steepness > 0.0);
+        assert!(fit.note.is_some());
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn test_calibrate_requires_labels() {
+        let mut record = sample(SampleLabel::Healthy, 0.5);
+        record.label = None;
+        assert!(calibrate(&[record.clone()], &targets(), &PwrzvConfig::default()).is_err());
+
+        record.platform = "plan9".to_string();
+        record.label = Some(SampleLabel::Healthy);
+        assert!(calibrate(&[record], &targets(), &PwrzvConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_calibrate_recorded_platform() {
+        // Samples are fitted to the metrics and section of their own platform
+        let mut records = vec![
+            sample(SampleLabel::Healthy, 0.3),
+            sample(SampleLabel::Healthy, 0.4),
+        ];
+        for record in &mut records {
+            record.platform = "macos".to_string();
+        }
+        let calibration = calibrate(&records, &targets(), &PwrzvConfig::default()).unwrap();
+        assert_eq!(calibration.fits[0].id, "cpu_usage");
+        assert!(calibration.config.macos.contains_key("cpu_usage"));
+        assert!(calibration.config.linux.is_empty());
+
+        records[1].platform = "linux".to_string();
+        let error = calibrate(&records, &targets(), &PwrzvConfig::default()).unwrap_err();
+        assert!(error.to_string().contains("mixes samples"), "{error}");
+    }
+}
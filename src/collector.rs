@@ -0,0 +1,1146 @@
+//! Collector status module
+//!
+//! Metrics are gathered by collectors, each reading one source such as
+//! `/proc/pressure/memory` or the output of `vm_stat`. Every collector runs with
+//! a timeout and reports whether it succeeded, so a sample can tell "healthy"
+//! apart from "couldn't measure". A collector still running after its timeout
+//! is not started again until it returns. The `[collection]` section of the
+//! configuration file sets the timeouts and how many missing metrics turn a
+//! sample into an error:
+//!
+//! ```toml
+//! [collection]
+//! # Timeout of every collector (default: 2000)
+//! timeout_ms = 1000
+//! # Fail the sample when more than 2 metrics are missing
+//! # (default: fail only when no metric could be collected)
+//! max_missing = 2
+//!
+//! [collection.timeouts]
+//! disk_io = 3000
+//! ```
+//!
+//! Every collector also measures what it costs: wall time, CPU time including
+//! its subprocesses, and how many subprocesses and files it used. With
+//! `budget_ms` set, the collectors that cost the most CPU time in their latest
+//! run are skipped while the sample would exceed the budget. The cost of a
+//! skipped collector is halved every sample it is skipped, so it is run and
+//! measured again after a few samples:
+//!
+//! ```toml
+//! [collection]
+//! # CPU time a sample may spend in collectors (default: unlimited)
+//! budget_ms = 20
+//! ```
+//!
+//! The Linux collectors read procfs only. On other platforms some collectors
+//! run commands, which can be forbidden; those collectors then report
+//! themselves unavailable instead of spawning anything:
+//!
+//! ```toml
+//! [collection]
+//! # Let collectors run external commands (default: true)
+//! allow_commands = false
+//! ```
+
+use crate::config::CollectionConfig;
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::report::EffectiveCpus;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(any(not(target_os = "linux"), test))]
+use std::io::{self, Read};
+#[cfg(any(not(target_os = "linux"), test))]
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default timeout of every collector
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// How often a running command is checked for completion
+#[cfg(any(not(target_os = "linux"), test))]
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Factor the cost of a collector shrinks by every sample it is skipped
+const OVER_BUDGET_DECAY: u64 = 2;
+
+/// CPU time of every collector in its latest run, in microseconds
+///
+/// Shared by every sample of the process, whichever sampler takes it.
+static LATEST_CPU: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+
+/// Abandoned runs of every collector that are still running
+///
+/// A collector that outlives its timeout keeps its thread until it returns.
+/// While one of its runs is abandoned, the collector is not started again, so
+/// a hung source costs at most one thread.
+static ABANDONED: Mutex<BTreeMap<&'static str, usize>> = Mutex::new(BTreeMap::new());
+
+/// A running collector, shared with its helper threads
+#[derive(Debug)]
+struct Context {
+    /// CPU time, subprocesses and file reads so far
+    usage: Mutex<CollectorCost>,
+    /// Whether the collector may run external commands
+    #[cfg(any(not(target_os = "linux"), test))]
+    allow_commands: bool,
+}
+
+thread_local! {
+    /// Context of the collector running on this thread
+    static CONTEXT: RefCell<Option<Arc<Context>>> = const { RefCell::new(None) };
+}
+
+/// Outcome of a single collector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CollectorStatus {
+    /// All metrics of the collector were read
+    Ok,
+    /// The source could not be read (missing file, command not found, no data)
+    Unavailable {
+        /// What could not be read
+        detail: String,
+    },
+    /// The collector did not finish within its timeout
+    TimedOut {
+        /// The timeout that was exceeded, in milliseconds
+        timeout_ms: u64,
+    },
+    /// The source was read but its content could not be parsed
+    ParseError {
+        /// What could not be parsed
+        detail: String,
+    },
+    /// Every metric of the collector is disabled, so it was not run
+    Disabled,
+    /// The host does not provide the source, e.g. no thermal sensors in a VM
+    NotPresent,
+    /// The collector was not run to keep the sample within its budget
+    OverBudget {
+        /// CPU time of the collector in its latest run, in microseconds
+        cpu_us: u64,
+    },
+}
+
+impl CollectorStatus {
+    /// Check whether the collector succeeded
+    pub fn is_ok(&self) -> bool {
+        matches!(self, CollectorStatus::Ok)
+    }
+
+    /// Check whether the collector ran and failed
+    pub fn is_failed(&self) -> bool {
+        !matches!(
+            self,
+            CollectorStatus::Ok
+                | CollectorStatus::Disabled
+                | CollectorStatus::NotPresent
+                | CollectorStatus::OverBudget { .. }
+        )
+    }
+
+    /// Check whether the collector was skipped on purpose or found nothing to read
+    pub fn is_skipped(&self) -> bool {
+        matches!(
+            self,
+            CollectorStatus::Disabled
+                | CollectorStatus::NotPresent
+                | CollectorStatus::OverBudget { .. }
+        )
+    }
+}
+
+impl fmt::Display for CollectorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CollectorStatus::Ok => f.write_str("ok"),
+            CollectorStatus::Unavailable { detail } => write!(f, "unavailable: {detail}"),
+            CollectorStatus::TimedOut { timeout_ms } => write!(f, "timed out after {timeout_ms}ms"),
+            CollectorStatus::ParseError { detail } => write!(f, "parse error: {detail}"),
+            CollectorStatus::Disabled => f.write_str("disabled"),
+            CollectorStatus::NotPresent => f.write_str("not present on this host"),
+            CollectorStatus::OverBudget { cpu_us } => write!(
+                f,
+                "skipped, {:.1}ms CPU exceeds the sample budget",
+                *cpu_us as f64 / 1000.0
+            ),
+        }
+    }
+}
+
+/// Status of a collector in a sample
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorReport {
+    /// Collector name, as used in `[collection.timeouts]`
+    pub name: String,
+    /// IDs of the metrics the collector provides
+    pub metrics: Vec<String>,
+    /// Outcome of the collector
+    #[serde(flatten)]
+    pub status: CollectorStatus,
+    /// What the collector cost; unknown if it was skipped or timed out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<CollectorCost>,
+}
+
+/// Resources used by a collector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectorCost {
+    /// Wall-clock time in microseconds
+    pub wall_us: u64,
+    /// CPU time of the collector and its subprocesses in microseconds
+    pub cpu_us: u64,
+    /// Number of subprocesses started
+    pub subprocesses: u32,
+    /// Number of files read
+    pub file_reads: u32,
+}
+
+impl CollectorCost {
+    const ZERO: Self = Self {
+        wall_us: 0,
+        cpu_us: 0,
+        subprocesses: 0,
+        file_reads: 0,
+    };
+
+    /// Combine the costs of collectors that ran in parallel
+    ///
+    /// Wall time is the longest of them; everything else is summed.
+    pub fn total<'a>(costs: impl IntoIterator<Item = &'a CollectorCost>) -> Self {
+        costs.into_iter().fold(Self::ZERO, |total, cost| Self {
+            wall_us: total.wall_us.max(cost.wall_us),
+            cpu_us: total.cpu_us + cost.cpu_us,
+            subprocesses: total.subprocesses + cost.subprocesses,
+            file_reads: total.file_reads + cost.file_reads,
+        })
+    }
+}
+
+impl fmt::Display for CollectorCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1}ms wall, {:.1}ms CPU, {} subprocess(es), {} file read(s)",
+            self.wall_us as f64 / 1000.0,
+            self.cpu_us as f64 / 1000.0,
+            self.subprocesses,
+            self.file_reads
+        )
+    }
+}
+
+/// Update the usage of the collector running on this thread, if any
+fn add_usage(update: impl FnOnce(&mut CollectorCost)) {
+    CONTEXT.with_borrow(|context| {
+        if let Some(context) = context {
+            update(&mut context.usage.lock().unwrap());
+        }
+    });
+}
+
+/// Count a file read by the collector on the current thread
+pub(crate) fn count_file_read() {
+    add_usage(|cost| cost.file_reads += 1);
+}
+
+/// Count a finished subprocess of the collector on the current thread
+#[cfg(any(not(target_os = "linux"), test))]
+fn count_subprocess(cpu: Duration) {
+    add_usage(|cost| {
+        cost.subprocesses += 1;
+        cost.cpu_us += cpu.as_micros() as u64;
+    });
+}
+
+/// Check whether the collector on the current thread may run commands
+///
+/// Code running outside of a collector always may.
+#[cfg(any(not(target_os = "linux"), test))]
+fn commands_allowed() -> bool {
+    CONTEXT.with_borrow(|context| {
+        context
+            .as_ref()
+            .is_none_or(|context| context.allow_commands)
+    })
+}
+
+/// CPU time used by the current thread so far
+#[cfg(unix)]
+fn thread_cpu_time() -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid timespec for the duration of the call
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+#[cfg(not(unix))]
+fn thread_cpu_time() -> Duration {
+    Duration::ZERO
+}
+
+/// Run `f` on the current thread as part of a collector, adding its CPU time
+fn run_in<T>(context: Arc<Context>, f: impl FnOnce() -> T) -> T {
+    let previous = CONTEXT.replace(Some(Arc::clone(&context)));
+    let cpu_started = thread_cpu_time();
+
+    let result = f();
+
+    let cpu = thread_cpu_time().saturating_sub(cpu_started);
+    add_usage(|cost| cost.cpu_us += cpu.as_micros() as u64);
+    CONTEXT.set(previous);
+    result
+}
+
+/// Run a collector on the current thread within `context`, measuring what it costs
+fn measure<T>(
+    context: Context,
+    collector: impl FnOnce() -> PwrzvResult<T>,
+) -> (PwrzvResult<T>, CollectorCost) {
+    let context = Arc::new(context);
+    let started = Instant::now();
+
+    let result = run_in(Arc::clone(&context), collector);
+
+    let mut cost = *context.usage.lock().unwrap();
+    cost.wall_us = started.elapsed().as_micros() as u64;
+    (result, cost)
+}
+
+/// Start a helper thread of the collector running on the current thread
+///
+/// What the helper costs counts towards the collector, and it runs commands
+/// only if the collector may.
+#[cfg(any(not(target_os = "linux"), test))]
+pub(crate) fn spawn_scoped<'scope, T: Send + 'scope>(
+    scope: &'scope thread::Scope<'scope, '_>,
+    f: impl FnOnce() -> T + Send + 'scope,
+) -> thread::ScopedJoinHandle<'scope, T> {
+    let context = CONTEXT.with_borrow(Clone::clone);
+    scope.spawn(move || match context {
+        Some(context) => run_in(context, f),
+        None => f(),
+    })
+}
+
+/// A collector: its name and the IDs of the metrics it provides
+pub(crate) type CollectorSpec = (&'static str, &'static [&'static str]);
+
+/// Raw metric values of a sample with the status of every collector
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Collection {
+    /// Raw metric values keyed by metric ID
+    pub(crate) inputs: BTreeMap<String, f32>,
+    /// Status of every collector
+    pub(crate) collectors: Vec<CollectorReport>,
+    /// Observations about the sample worth reporting alongside the scores
+    pub(crate) notes: Vec<String>,
+    /// Number of CPUs the load was normalized to, where the platform counts them
+    pub(crate) effective_cpus: Option<EffectiveCpus>,
+}
+
+impl Collection {
+    /// Assemble a collection from raw values and collector statuses
+    ///
+    /// Collectors that succeeded without providing all of their metrics are
+    /// marked unavailable, so every missing metric is explained.
+    pub(crate) fn new(inputs: BTreeMap<String, f32>, mut collectors: Vec<CollectorReport>) -> Self {
+        for collector in &mut collectors {
+            let missing: Vec<_> = collector
+                .metrics
+                .iter()
+                .filter(|id| !inputs.contains_key(*id))
+                .map(String::as_str)
+                .collect();
+            if collector.status.is_ok() && !missing.is_empty() {
+                collector.status = CollectorStatus::Unavailable {
+                    detail: format!("no value for {}", missing.join(", ")),
+                };
+            }
+        }
+        Self {
+            inputs,
+            collectors,
+            notes: Vec::new(),
+            effective_cpus: None,
+        }
+    }
+
+    /// Get the IDs of the metrics without a value
+    ///
+    /// Metrics of collectors skipped on purpose are not missing.
+    pub(crate) fn missing(&self) -> Vec<&str> {
+        self.collectors
+            .iter()
+            .filter(|collector| !collector.status.is_skipped())
+            .flat_map(|collector| &collector.metrics)
+            .filter(|id| !self.inputs.contains_key(*id))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Apply the missing-metric policy
+    ///
+    /// # Returns
+    ///
+    /// An error naming every missing metric and why it is missing if more
+    /// metrics are missing than `max_missing` allows, or if none was collected.
+    pub(crate) fn check(&self, config: &CollectionConfig) -> PwrzvResult<()> {
+        let missing = self.missing();
+        let total = missing.len() + self.inputs.len();
+        let acceptable = match config.max_missing {
+            Some(max) => missing.len() <= max,
+            None => !self.inputs.is_empty(),
+        };
+        if acceptable || total == 0 {
+            return Ok(());
+        }
+
+        let reasons: Vec<_> = self
+            .collectors
+            .iter()
+            .filter(|collector| collector.status.is_failed())
+            .map(|collector| format!("{} ({})", collector.name, collector.status))
+            .collect();
+        let limit = match config.max_missing {
+            Some(max) => format!("at most {max} allowed"),
+            None => "at least one required".to_string(),
+        };
+        Err(PwrzvError::collection_error(&format!(
+            "{} of {total} metrics could not be collected ({limit}): {}",
+            missing.len(),
+            reasons.join("; ")
+        )))
+    }
+}
+
+/// Which collectors of a sample run, and with which timeouts
+pub(crate) struct Plan<'a> {
+    config: &'a CollectionConfig,
+    /// Collectors that are not run, with the status they report
+    skipped: BTreeMap<&'static str, CollectorStatus>,
+}
+
+impl<'a> Plan<'a> {
+    /// Plan a sample
+    ///
+    /// Collectors none of whose metrics is enabled are disabled. With a
+    /// budget, the collectors that cost the most CPU time in their latest run
+    /// are skipped until the rest fit in it. The cost of a skipped collector
+    /// is divided by [`OVER_BUDGET_DECAY`] every sample it is skipped, so it
+    /// runs again once the estimate fits and its cost is measured afresh.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Collection settings holding the timeouts and the budget
+    /// * `collectors` - Every collector of the platform
+    /// * `enabled` - Whether a metric, by ID, is enabled
+    pub(crate) fn new(
+        config: &'a CollectionConfig,
+        collectors: &[CollectorSpec],
+        enabled: &dyn Fn(&str) -> bool,
+    ) -> Self {
+        let (active, disabled): (Vec<_>, Vec<_>) = collectors
+            .iter()
+            .partition(|(_, metrics)| metrics.iter().any(|id| enabled(id)));
+        let mut skipped: BTreeMap<&'static str, _> = disabled
+            .into_iter()
+            .map(|&(name, _)| (name, CollectorStatus::Disabled))
+            .collect();
+
+        if let Some(budget_ms) = config.budget_ms {
+            let mut latest = LATEST_CPU.lock().unwrap();
+            let expected: Vec<_> = active
+                .iter()
+                .map(|&&(name, _)| (name, latest.get(name).copied().unwrap_or(0)))
+                .collect();
+            for (name, cpu_us) in over_budget(&expected, budget_ms.saturating_mul(1000)) {
+                skipped.insert(name, CollectorStatus::OverBudget { cpu_us });
+                // Decay the estimate so the collector is measured again
+                latest.insert(name, cpu_us / OVER_BUDGET_DECAY);
+            }
+        }
+        Self { config, skipped }
+    }
+
+    /// Get the timeout of a collector
+    pub(crate) fn timeout(&self, collector: &str) -> Duration {
+        self.config.timeout(collector)
+    }
+}
+
+/// Pick the collectors to skip so that the expected CPU time fits a budget
+///
+/// The most expensive collectors are dropped first.
+///
+/// # Arguments
+///
+/// * `expected` - Name and expected CPU time in microseconds of every collector
+/// * `budget_us` - CPU time the sample may take, in microseconds
+fn over_budget(expected: &[(&'static str, u64)], budget_us: u64) -> Vec<(&'static str, u64)> {
+    let mut by_cost = expected.to_vec();
+    by_cost.sort_by_key(|(_, cpu_us)| std::cmp::Reverse(*cpu_us));
+
+    let mut total: u64 = expected.iter().map(|(_, cpu_us)| cpu_us).sum();
+    let mut skipped = Vec::new();
+    for (name, cpu_us) in by_cost {
+        if total <= budget_us {
+            break;
+        }
+        total -= cpu_us;
+        skipped.push((name, cpu_us));
+    }
+    skipped
+}
+
+/// A collector running on its own thread
+pub(crate) struct Pending<T> {
+    spec: CollectorSpec,
+    timeout: Duration,
+    deadline: Instant,
+    run: Run<T>,
+}
+
+/// Whether a pending collector was started
+enum Run<T> {
+    Started(mpsc::Receiver<Outcome<T>>),
+    Skipped(CollectorStatus),
+}
+
+/// Result and cost of a collector run, or `None` if the collector panicked
+type Outcome<T> = Option<(PwrzvResult<T>, CollectorCost)>;
+
+/// Hands the outcome of a collector thread to its waiter, even on panic
+struct Delivery<T> {
+    name: &'static str,
+    sender: mpsc::Sender<Outcome<T>>,
+    outcome: Outcome<T>,
+}
+
+impl<T> Drop for Delivery<T> {
+    fn drop(&mut self) {
+        // Locked so that the waiter cannot abandon the run between the send
+        // and the bookkeeping
+        let mut abandoned = ABANDONED.lock().unwrap();
+        if self.sender.send(self.outcome.take()).is_err()
+            && let Some(count) = abandoned.get_mut(self.name)
+        {
+            *count -= 1;
+            if *count == 0 {
+                abandoned.remove(self.name);
+            }
+        }
+    }
+}
+
+/// Abandon a collector run that outlived its timeout
+///
+/// # Returns
+///
+/// The outcome of the run if it finished after all, in which case it is not
+/// abandoned
+fn abandon<T>(name: &'static str, receiver: mpsc::Receiver<Outcome<T>>) -> Option<Outcome<T>> {
+    let mut abandoned = ABANDONED.lock().unwrap();
+    if let Ok(outcome) = receiver.try_recv() {
+        return Some(outcome);
+    }
+    *abandoned.entry(name).or_default() += 1;
+    drop(receiver);
+    None
+}
+
+/// Start a collector on its own thread
+///
+/// Collectors started together run in parallel; [`Pending::wait`] collects
+/// the result of each within its timeout. Collectors the plan skips are not
+/// started.
+///
+/// # Arguments
+///
+/// * `spec` - Name of the collector and the metrics it provides
+/// * `plan` - Plan of the sample
+/// * `collector` - The collection function
+pub(crate) fn spawn<T: Send + 'static>(
+    spec: CollectorSpec,
+    plan: &Plan<'_>,
+    collector: impl FnOnce() -> PwrzvResult<T> + Send + 'static,
+) -> Pending<T> {
+    let timeout = plan.timeout(spec.0);
+    let hung = ABANDONED.lock().unwrap().contains_key(spec.0);
+    let run = match plan.skipped.get(spec.0) {
+        Some(status) => Run::Skipped(status.clone()),
+        None if hung => Run::Skipped(CollectorStatus::TimedOut {
+            timeout_ms: timeout.as_millis() as u64,
+        }),
+        None => {
+            let (sender, receiver) = mpsc::channel();
+            let context = Context {
+                usage: Mutex::new(CollectorCost::ZERO),
+                #[cfg(any(not(target_os = "linux"), test))]
+                allow_commands: plan.config.allow_commands,
+            };
+            // A collector that outlives its timeout is abandoned; its result is dropped
+            let name = spec.0;
+            thread::spawn(move || {
+                let mut delivery = Delivery {
+                    name,
+                    sender,
+                    outcome: None,
+                };
+                delivery.outcome = Some(measure(context, collector));
+            });
+            Run::Started(receiver)
+        }
+    };
+    Pending {
+        spec,
+        timeout,
+        deadline: Instant::now() + timeout,
+        run,
+    }
+}
+
+impl<T> Pending<T> {
+    /// Wait for the collector until its timeout
+    ///
+    /// # Returns
+    ///
+    /// The collected value, if any, and the status of the collector
+    pub(crate) fn wait(self) -> (Option<T>, CollectorReport) {
+        let (name, metrics) = self.spec;
+        let (value, status, cost) = match self.run {
+            Run::Skipped(status) => (None, status, None),
+            Run::Started(receiver) => {
+                let remaining = self.deadline.saturating_duration_since(Instant::now());
+                let outcome = match receiver.recv_timeout(remaining) {
+                    Ok(outcome) => Some(outcome),
+                    Err(RecvTimeoutError::Timeout) => abandon(name, receiver),
+                    Err(RecvTimeoutError::Disconnected) => Some(None),
+                };
+                match outcome {
+                    Some(Some((result, cost))) => {
+                        LATEST_CPU.lock().unwrap().insert(name, cost.cpu_us);
+                        let (value, status) = match result {
+                            Ok(value) => (Some(value), CollectorStatus::Ok),
+                            Err(e @ PwrzvError::ParseError { .. }) => {
+                                (None, CollectorStatus::ParseError { detail: e.detail() })
+                            }
+                            Err(e) => (None, CollectorStatus::Unavailable { detail: e.detail() }),
+                        };
+                        (value, status, Some(cost))
+                    }
+                    None => {
+                        let timeout_ms = self.timeout.as_millis() as u64;
+                        (None, CollectorStatus::TimedOut { timeout_ms }, None)
+                    }
+                    Some(None) => {
+                        let detail = "collector panicked".to_string();
+                        (None, CollectorStatus::Unavailable { detail }, None)
+                    }
+                }
+            }
+        };
+
+        let report = CollectorReport {
+            name: name.to_string(),
+            metrics: metrics.iter().map(|id| id.to_string()).collect(),
+            status,
+            cost,
+        };
+        (value, report)
+    }
+}
+
+impl<T> Pending<Option<T>> {
+    /// Wait for a collector whose source only some hosts provide
+    ///
+    /// A collector that returns `None` found nothing to read; it reports
+    /// [`CollectorStatus::NotPresent`] and its metrics do not count as missing.
+    pub(crate) fn wait_optional(self) -> (Option<T>, CollectorReport) {
+        let (value, mut report) = self.wait();
+        if let Some(None) = value {
+            report.status = CollectorStatus::NotPresent;
+        }
+        (value.flatten(), report)
+    }
+}
+
+/// Run a command to completion, killing it if it outlives `timeout`
+///
+/// Standard error is discarded. Standard output is read on a separate thread
+/// so that commands with large output cannot block on a full pipe.
+///
+/// # Returns
+///
+/// The output of the command, or an error if the collector may not run
+/// commands, or the command could not be started, did not finish in time or
+/// exited unsuccessfully
+#[cfg(any(not(target_os = "linux"), test))]
+pub(crate) fn command_output(command: &mut Command, timeout: Duration) -> PwrzvResult<Vec<u8>> {
+    let name = command.get_program().to_string_lossy().to_string();
+    if !commands_allowed() {
+        // Not transient: the command is refused until the configuration changes
+        let refused = io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "external commands are disabled",
+        );
+        return Err(PwrzvError::command_error(&name, refused));
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| PwrzvError::command_error(&name, e))?;
+
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some((status, cpu)) =
+            reap(&mut child, false).map_err(|e| PwrzvError::command_error(&name, e))?
+        {
+            count_subprocess(cpu);
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            if let Ok(Some((_, cpu))) = reap(&mut child, true) {
+                count_subprocess(cpu);
+            }
+            return Err(PwrzvError::timed_out(&name, timeout));
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+
+    let output = reader
+        .join()
+        .map_err(|_| PwrzvError::command_failed(&name, "output reader panicked"))?
+        .map_err(|e| PwrzvError::command_error(&name, e))?;
+    if !status.success() {
+        return Err(PwrzvError::command_failed(
+            &name,
+            &format!("exited with {status}"),
+        ));
+    }
+    Ok(output)
+}
+
+/// Reap a child process along with the CPU time it used
+///
+/// # Arguments
+///
+/// * `child` - The child process
+/// * `block` - Wait for the child to exit instead of returning `None`
+#[cfg(unix)]
+#[cfg(any(not(target_os = "linux"), test))]
+fn reap(child: &mut Child, block: bool) -> io::Result<Option<(ExitStatus, Duration)>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    // SAFETY: rusage is plain data, valid when zeroed
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let flags = if block { 0 } else { libc::WNOHANG };
+    // SAFETY: the pid belongs to a child that has not been reaped yet, and
+    // `status` and `usage` outlive the call
+    let pid = unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, flags, &mut usage) };
+    match pid {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(None),
+        _ => {
+            let time = |tv: libc::timeval| {
+                Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+            };
+            let cpu = time(usage.ru_utime) + time(usage.ru_stime);
+            Ok(Some((ExitStatus::from_raw(status), cpu)))
+        }
+    }
+}
+
+/// Reap a child process; its CPU time is not available on this platform
+#[cfg(not(unix))]
+fn reap(child: &mut Child, block: bool) -> io::Result<Option<(ExitStatus, Duration)>> {
+    let status = match block {
+        true => Some(child.wait()?),
+        false => child.try_wait()?,
+    };
+    Ok(status.map(|status| (status, Duration::ZERO)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CPU: CollectorSpec = ("cpu", &["cpu_usage", "cpu_io_wait"]);
+    const PRESSURE: CollectorSpec = ("memory_pressure", &["memory_pressure"]);
+
+    /// Plan running every collector
+    fn plan(config: &CollectionConfig) -> Plan<'_> {
+        Plan::new(config, &[CPU, PRESSURE], &|_| true)
+    }
+
+    #[test]
+    fn test_collector_statuses() {
+        let config = CollectionConfig::default();
+
+        let (value, report) = spawn(CPU, &plan(&config), || Ok(1)).wait();
+        assert_eq!(value, Some(1));
+        assert_eq!(report.status, CollectorStatus::Ok);
+        assert_eq!(report.metrics, ["cpu_usage", "cpu_io_wait"]);
+
+        let (value, report) = spawn(PRESSURE, &plan(&config), || {
+            Err::<f32, _>(PwrzvError::read_error(
+                "/proc/pressure/memory",
+                std::io::ErrorKind::NotFound.into(),
+            ))
+        })
+        .wait();
+        assert_eq!(value, None);
+        assert_eq!(
+            report.status.to_string(),
+            "unavailable: /proc/pressure/memory: entity not found"
+        );
+
+        let (_, report) = spawn(PRESSURE, &plan(&config), || {
+            Err::<f32, _>(PwrzvError::parse_error("no 'some avg10=' line"))
+        })
+        .wait();
+        assert!(matches!(report.status, CollectorStatus::ParseError { .. }));
+    }
+
+    #[test]
+    fn test_abandoned_collector() {
+        const HUNG: CollectorSpec = ("hung", &["hung"]);
+        let config = CollectionConfig {
+            timeouts: BTreeMap::from([("hung".to_string(), 10)]),
+            ..Default::default()
+        };
+        let plan = Plan::new(&config, &[HUNG], &|_| true);
+        let (release, released) = mpsc::channel::<()>();
+        let (value, report) = spawn(HUNG, &plan, move || {
+            let _ = released.recv();
+            Ok(0.5)
+        })
+        .wait();
+        assert_eq!(value, None);
+        assert_eq!(report.status, CollectorStatus::TimedOut { timeout_ms: 10 });
+
+        // Not started again while the abandoned run is still running
+        let started = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&started);
+        let (_, report) = spawn(HUNG, &plan, move || {
+            *counter.lock().unwrap() += 1;
+            Ok(0.5)
+        })
+        .wait();
+        assert_eq!(report.status, CollectorStatus::TimedOut { timeout_ms: 10 });
+        assert_eq!(*started.lock().unwrap(), 0);
+
+        // Started again once the abandoned run returns
+        drop(release);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while ABANDONED.lock().unwrap().contains_key("hung") {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(1));
+        }
+        let (value, report) = spawn(HUNG, &plan, || Ok(0.5)).wait();
+        assert_eq!(value, Some(0.5));
+        assert!(report.status.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_output() {
+        let output = command_output(
+            Command::new("sh").args(["-c", "echo hello"]),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert_eq!(output, b"hello\n");
+
+        let error = command_output(
+            Command::new("sh").args(["-c", "exit 3"]),
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert!(matches!(error, PwrzvError::CommandError { .. }), "{error}");
+
+        let started = Instant::now();
+        let error = command_output(
+            Command::new("sh").args(["-c", "sleep 5"]),
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+        assert!(matches!(error, PwrzvError::TimedOut { .. }), "{error}");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let error = command_output(
+            &mut Command::new("pwrzv-no-such-command"),
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert!(!error.is_transient());
+    }
+
+    #[test]
+    fn test_collection_policy() {
+        let config = CollectionConfig::default();
+        let (_, cpu) = spawn(CPU, &plan(&config), || Ok(())).wait();
+        let (_, pressure) = spawn(PRESSURE, &plan(&config), || {
+            Err::<(), _>(PwrzvError::resource_access_error("/proc/pressure/memory"))
+        })
+        .wait();
+
+        // cpu_io_wait is missing although its collector succeeded
+        let inputs = BTreeMap::from([("cpu_usage".to_string(), 0.5)]);
+        let collection = Collection::new(inputs, vec![cpu, pressure]);
+        assert_eq!(collection.missing(), ["cpu_io_wait", "memory_pressure"]);
+        assert_eq!(
+            collection.collectors[0].status.to_string(),
+            "unavailable: no value for cpu_io_wait"
+        );
+
+        assert!(collection.check(&config).is_ok());
+        let strict = CollectionConfig {
+            max_missing: Some(1),
+            ..Default::default()
+        };
+        let error = collection.check(&strict).unwrap_err().to_string();
+        assert!(error.contains("2 of 3 metrics"), "{error}");
+        assert!(error.contains("memory_pressure (unavailable"), "{error}");
+
+        let empty = Collection::new(BTreeMap::new(), collection.collectors.clone());
+        assert!(empty.check(&config).is_err());
+    }
+
+    #[test]
+    fn test_disabled_collector() {
+        let config = CollectionConfig::default();
+        let plan = Plan::new(&config, &[CPU, PRESSURE], &|id| id != "memory_pressure");
+        let (_, cpu) = spawn(CPU, &plan, || Ok(())).wait();
+        let (value, pressure) = spawn(PRESSURE, &plan, || -> PwrzvResult<()> {
+            panic!("disabled collectors are not run")
+        })
+        .wait();
+        assert_eq!(value, None);
+        assert_eq!(pressure.status, CollectorStatus::Disabled);
+
+        // Disabled metrics are not missing
+        let inputs = BTreeMap::from([
+            ("cpu_usage".to_string(), 0.5),
+            ("cpu_io_wait".to_string(), 0.1),
+        ]);
+        let collection = Collection::new(inputs, vec![cpu, pressure]);
+        assert!(collection.missing().is_empty());
+        let strict = CollectionConfig {
+            max_missing: Some(0),
+            ..Default::default()
+        };
+        assert!(collection.check(&strict).is_ok());
+    }
+
+    #[test]
+    fn test_not_present_collector() {
+        const THERMAL: CollectorSpec = ("thermal", &["thermal_load"]);
+        let config = CollectionConfig::default();
+        let plan = Plan::new(&config, &[THERMAL], &|_| true);
+
+        let (value, report) = spawn(THERMAL, &plan, || Ok(Some(0.5))).wait_optional();
+        assert_eq!(value, Some(0.5));
+        assert!(report.status.is_ok());
+
+        let (value, report) = spawn(THERMAL, &plan, || Ok(None::<f32>)).wait_optional();
+        assert_eq!(value, None);
+        assert_eq!(report.status, CollectorStatus::NotPresent);
+        assert!(report.status.is_skipped() && !report.status.is_failed());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "not_present");
+
+        let collection = Collection::new(BTreeMap::new(), vec![report]);
+        assert!(collection.missing().is_empty());
+    }
+
+    #[test]
+    fn test_collector_report_serialization() {
+        let report = CollectorReport {
+            name: "disk_io".to_string(),
+            metrics: vec!["disk_io_utilization".to_string()],
+            status: CollectorStatus::TimedOut { timeout_ms: 2000 },
+            cost: None,
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "timed_out");
+        assert_eq!(json["timeout_ms"], 2000);
+        assert_eq!(
+            serde_json::from_value::<CollectorReport>(json).unwrap(),
+            report
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collector_cost() {
+        let config = CollectionConfig::default();
+        let (_, report) = spawn(("cost_test", &["cost_test"]), &plan(&config), || {
+            count_file_read();
+            command_output(
+                Command::new("sh").args(["-c", "i=0; while [ $i -lt 20000 ]; do i=$((i+1)); done"]),
+                Duration::from_secs(5),
+            )
+        })
+        .wait();
+        assert!(report.status.is_ok(), "{report:?}");
+        let cost = report.cost.unwrap();
+        assert_eq!(cost.subprocesses, 1);
+        assert_eq!(cost.file_reads, 1);
+        assert!(cost.cpu_us > 0, "{cost}");
+        assert!(cost.wall_us >= cost.cpu_us / 2, "{cost}");
+
+        let total = CollectorCost::total([&cost, &cost]);
+        assert_eq!(total.wall_us, cost.wall_us);
+        assert_eq!(total.cpu_us, cost.cpu_us * 2);
+        assert_eq!(total.subprocesses, 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_commands_forbidden() {
+        let config = CollectionConfig {
+            allow_commands: false,
+            ..Default::default()
+        };
+        let (value, report) = spawn(("cost_test", &["cost_test"]), &plan(&config), || {
+            let output = thread::scope(|scope| {
+                let helper = spawn_scoped(scope, || {
+                    command_output(Command::new("sh").args(["-c", "echo hi"]), DEFAULT_TIMEOUT)
+                });
+                helper.join().unwrap()
+            });
+            let error = output.unwrap_err();
+            assert!(!error.is_transient());
+            Ok(error.to_string())
+        })
+        .wait();
+        let error = value.unwrap();
+        assert!(error.contains("external commands are disabled"), "{error}");
+        assert_eq!(report.cost.unwrap().subprocesses, 0);
+
+        // Helper threads of a collector that may run commands count towards it
+        let (_, report) = spawn(
+            ("cost_test", &["cost_test"]),
+            &plan(&CollectionConfig::default()),
+            || {
+                thread::scope(|scope| {
+                    spawn_scoped(scope, || {
+                        command_output(Command::new("sh").args(["-c", "echo hi"]), DEFAULT_TIMEOUT)
+                    })
+                    .join()
+                    .unwrap()
+                })
+            },
+        )
+        .wait();
+        assert!(report.status.is_ok(), "{report:?}");
+        assert_eq!(report.cost.unwrap().subprocesses, 1);
+    }
+
+    #[test]
+    fn test_collectors_spawn_through_command_output() {
+        /// Source files of every collector, recursively
+        fn sources(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    files.extend(sources(&path));
+                } else if path.extension().is_some_and(|ext| ext == "rs") {
+                    files.push(path);
+                }
+            }
+            files
+        }
+
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        // Linux collectors never build a command; macOS ones only hand them
+        // to `command_output`, which enforces `allow_commands` and counts them
+        for (dir, forbidden) in [
+            ("linux", &["Command", ".spawn(", ".output(", ".status("][..]),
+            ("macos", &[".spawn(", ".output(", ".status("][..]),
+        ] {
+            for file in sources(&src.join(dir)) {
+                let content = std::fs::read_to_string(&file).unwrap();
+                for pattern in forbidden {
+                    assert!(
+                        !content.contains(pattern),
+                        "{} uses `{pattern}`; run commands through collector::command_output",
+                        file.display()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_budget() {
+        let expected = [("a", 500), ("b", 30_000), ("c", 2_000), ("d", 0)];
+        assert!(over_budget(&expected, 50_000).is_empty());
+        assert_eq!(over_budget(&expected, 10_000), [("b", 30_000)]);
+        assert_eq!(over_budget(&expected, 1_000), [("b", 30_000), ("c", 2_000)]);
+
+        const CHEAP: CollectorSpec = ("budget_cheap", &["budget_cheap"]);
+        const COSTLY: CollectorSpec = ("budget_costly", &["budget_costly"]);
+        LATEST_CPU
+            .lock()
+            .unwrap()
+            .extend([("budget_cheap", 100), ("budget_costly", 40_000)]);
+        let config = CollectionConfig {
+            budget_ms: Some(10),
+            ..Default::default()
+        };
+        let plan = Plan::new(&config, &[CHEAP, COSTLY], &|_| true);
+        let (_, cheap) = spawn(CHEAP, &plan, || Ok(())).wait();
+        let (_, costly) = spawn(COSTLY, &plan, || Ok(())).wait();
+        assert!(cheap.status.is_ok());
+        assert_eq!(
+            costly.status,
+            CollectorStatus::OverBudget { cpu_us: 40_000 }
+        );
+        assert_eq!(
+            costly.status.to_string(),
+            "skipped, 40.0ms CPU exceeds the sample budget"
+        );
+
+        // Skipped collectors are neither failed nor missing
+        let collection = Collection::new(BTreeMap::new(), vec![costly]);
+        assert!(collection.missing().is_empty());
+
+        // The estimate decays until the collector is measured again
+        let skips: Vec<_> = (0..3)
+            .map(|_| Plan::new(&config, &[COSTLY], &|_| true))
+            .map(|plan| spawn(COSTLY, &plan, || Ok(())).wait().1.status)
+            .collect();
+        assert_eq!(
+            skips,
+            [
+                CollectorStatus::OverBudget { cpu_us: 20_000 },
+                CollectorStatus::Ok,
+                CollectorStatus::Ok,
+            ]
+        );
+    }
+}
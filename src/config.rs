@@ -0,0 +1,1202 @@
+//! Configuration file module
+//!
+//! Loads scoring parameters from a TOML file, so that curves and aggregation
+//! can be tuned per host and replayed against recorded traces.
+//!
+//! ```toml
+//! # How metric scores are combined into the power reserve level: min, mean or median
+//! aggregation = "min"
+//!
+//! [linux.cpu_usage]
+//! midpoint = 0.75
+//! steepness = 10.0
+//!
+//! [macos.memory_usage]
+//! midpoint = 0.90
+//! ```
+//!
+//! A metric can be left out with `enabled = false`, which also skips its
+//! collector once all of the collector's metrics are off, and its influence on
+//! the level set with `weight`; see [`Aggregation::apply_weighted`].
+//!
+//! Besides the logistic sigmoid, each metric can use another response curve
+//! (`curve = "piecewise_linear"`, `"step"`, `"exponential"` or `"richards"`);
+//! see the [`crate::curve`] module.
+//!
+//! Collector timeouts, the missing-metric policy, the root `/proc` is read
+//! under and whether commands may run are set in the `[collection]` section; see the [`crate::collector`]
+//! module.
+//!
+//! Metric IDs are the snake_case metric labels (`cpu_usage`, `memory_pressure`,
+//! `file_descriptors`, ...). Parameters are resolved with the precedence
+//! built-in default < configuration file < `PWRZV_*` environment variable.
+//!
+//! The process-wide configuration is read from the file named by
+//! `PWRZV_CONFIG` on first use, or set explicitly with [`set_global`].
+
+use crate::curve::{Curve, CurveKind};
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::sigmoid::{MetricSpec, env_f32};
+use crate::{platform_collectors, platform_metrics, unit_metrics};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Environment variable naming the configuration file
+pub const CONFIG_ENV: &str = "PWRZV_CONFIG";
+
+/// Environment variables read outside of metric curves
+const OTHER_ENV_VARS: [&str; 9] = [
+    CONFIG_ENV,
+    crate::store::STORE_ENV,
+    "PWRZV_SOCKET",
+    "PWRZV_CGROUP_ROOT",
+    // Set by pwrzv for alert commands
+    "PWRZV_ALERT_RULE",
+    "PWRZV_ALERT_STATE",
+    "PWRZV_ALERT_CONDITION",
+    "PWRZV_ALERT_LEVEL",
+    "PWRZV_ALERT_VALUE",
+];
+
+/// Metrics reported in the details but kept out of the level unless given a weight
+const UNWEIGHTED_METRICS: [(&str, &str); 1] = [("linux", "cpu_core_saturation")];
+
+/// Process-wide configuration, loaded lazily
+static GLOBAL: RwLock<Option<Arc<PwrzvConfig>>> = RwLock::new(None);
+
+/// How metric scores are combined into the power reserve level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// The worst metric determines the level (bottleneck semantics)
+    #[default]
+    Min,
+    /// Arithmetic mean of all metric scores
+    Mean,
+    /// Median of all metric scores
+    Median,
+}
+
+impl Aggregation {
+    /// Combine metric scores, returning `None` when there are none
+    pub fn apply(self, scores: &[f32]) -> Option<f32> {
+        let weighted: Vec<_> = scores.iter().map(|&score| (score, 1.0)).collect();
+        self.apply_weighted(&weighted)
+    }
+
+    /// Combine `(score, weight)` pairs, returning `None` when no weight is positive
+    ///
+    /// `Mean` and `Median` are the weighted mean and median. Under `Min`, the
+    /// weight scales how far a metric can pull the level below 5.0, but never
+    /// below the lowest level, 1.0: a score of 3.0 with weight 0.5 counts as
+    /// 4.0, with weight 2.0 as 1.0 and with weight 3.0 as 1.0 too. Metrics
+    /// with weight 0 are ignored.
+    pub fn apply_weighted(self, scores: &[(f32, f32)]) -> Option<f32> {
+        let mut sorted: Vec<_> = scores
+            .iter()
+            .copied()
+            .filter(|&(_, weight)| weight > 0.0)
+            .collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f32 = sorted.iter().map(|&(_, weight)| weight).sum();
+
+        let value = match self {
+            Aggregation::Min => sorted
+                .iter()
+                .map(|&(score, weight)| {
+                    if weight == 1.0 {
+                        score
+                    } else {
+                        (5.0 - weight * (5.0 - score)).clamp(1.0, 5.0)
+                    }
+                })
+                .fold(f32::INFINITY, f32::min),
+            Aggregation::Mean => {
+                sorted
+                    .iter()
+                    .map(|&(score, weight)| score * weight)
+                    .sum::<f32>()
+                    / total
+            }
+            Aggregation::Median => {
+                // The score where the cumulative weight passes half of the total;
+                // exactly at half, the midpoint of it and the next score
+                let half = total / 2.0;
+                let mut cumulative = 0.0;
+                let mut median = sorted[sorted.len() - 1].0;
+                for (i, &(score, weight)) in sorted.iter().enumerate() {
+                    cumulative += weight;
+                    if cumulative > half {
+                        median = score;
+                        break;
+                    }
+                    if cumulative == half {
+                        median = (score + sorted[i + 1].0) / 2.0;
+                        break;
+                    }
+                }
+                median
+            }
+        };
+        // Retain 4 decimal places like individual metric scores
+        Some((value * 10_000.0).round() / 10_000.0)
+    }
+}
+
+/// Curve parameters of a single metric; unset fields keep their default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricConfig {
+    /// Response curve kind (default: logistic)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub curve: Option<CurveKind>,
+    /// Sigmoid function midpoint (x0)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_short"
+    )]
+    pub midpoint: Option<f32>,
+    /// Sigmoid function steepness (k)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_short"
+    )]
+    pub steepness: Option<f32>,
+    /// Richards curve asymmetry (default: 1.0, the logistic shape)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_short"
+    )]
+    pub asymmetry: Option<f32>,
+    /// `[value, score]` breakpoints of piecewise-linear and step curves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<Vec<[f32; 2]>>,
+    /// Whether the metric is collected and scored (default: true)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Importance of the metric when scores are aggregated (default: 1.0, or
+    /// 0 for `cpu_core_saturation`)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_short"
+    )]
+    pub weight: Option<f32>,
+}
+
+impl MetricConfig {
+    /// Build the configured curve
+    ///
+    /// `midpoint` and `steepness` are the already resolved parameters of the
+    /// metric (the file values or the defaults, overridden by the environment).
+    pub fn build_curve(&self, midpoint: f32, steepness: f32) -> PwrzvResult<Curve> {
+        let kind = self.curve.unwrap_or_default();
+        let parametric = matches!(
+            kind,
+            CurveKind::Logistic | CurveKind::Exponential | CurveKind::Richards
+        );
+        if self.points.is_some() && parametric {
+            return Err(PwrzvError::invalid_value(&format!(
+                "points only apply to piecewise_linear and step curves, not {kind}"
+            )));
+        }
+        if self.asymmetry.is_some() && kind != CurveKind::Richards {
+            return Err(PwrzvError::invalid_value(&format!(
+                "asymmetry only applies to richards curves, not {kind}"
+            )));
+        }
+        if !parametric && (self.midpoint.is_some() || self.steepness.is_some()) {
+            return Err(PwrzvError::invalid_value(&format!(
+                "{kind} curves are defined by points, not midpoint/steepness"
+            )));
+        }
+
+        let points = || {
+            self.points
+                .clone()
+                .ok_or_else(|| PwrzvError::invalid_value(&format!("{kind} curve requires points")))
+        };
+        let curve = match kind {
+            CurveKind::Logistic => Curve::Logistic {
+                midpoint,
+                steepness,
+            },
+            CurveKind::Exponential => Curve::Exponential {
+                midpoint,
+                steepness,
+            },
+            CurveKind::Richards => Curve::Richards {
+                midpoint,
+                steepness,
+                asymmetry: self.asymmetry.unwrap_or(1.0),
+            },
+            CurveKind::PiecewiseLinear => Curve::PiecewiseLinear { points: points()? },
+            CurveKind::Step => Curve::Step { points: points()? },
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+}
+
+/// Serialize an `f32` as the shortest `f64` that round-trips (0.7, not 0.699999988079071)
+fn serialize_short<S: serde::Serializer>(
+    value: &Option<f32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value
+        .map(|v| v.to_string().parse::<f64>().unwrap_or(v as f64))
+        .serialize(serializer)
+}
+
+/// Collector timeouts, the missing-metric policy and where to read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectionConfig {
+    /// Directory that `/proc` and `/sys` are read under on Linux
+    ///
+    /// Defaults to `/`. Point it at the host's root mounted into a container,
+    /// or at a fixture tree in tests. The Linux collectors read nothing else;
+    /// the CPU affinity of this process only counts towards the effective CPU
+    /// count under `/`.
+    #[serde(skip_serializing_if = "is_live_root")]
+    pub root: PathBuf,
+    /// Timeout of every collector in milliseconds
+    pub timeout_ms: u64,
+    /// Timeouts of individual collectors in milliseconds, keyed by collector name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub timeouts: BTreeMap<String, u64>,
+    /// Number of missing metrics above which a sample is an error
+    ///
+    /// Unset, a sample only fails when no metric at all could be collected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_missing: Option<usize>,
+    /// CPU time a sample may spend in collectors, subprocesses included, in milliseconds
+    ///
+    /// Unset, every enabled collector runs. Otherwise the collectors that cost
+    /// the most in their latest run are skipped while the sample would exceed it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget_ms: Option<u64>,
+    /// Whether collectors may run external commands
+    ///
+    /// The Linux collectors never do. Forbidden, the macOS collectors that
+    /// need a command report themselves unavailable instead of spawning it.
+    #[serde(skip_serializing_if = "is_true")]
+    pub allow_commands: bool,
+}
+
+impl Default for CollectionConfig {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            timeout_ms: crate::collector::DEFAULT_TIMEOUT.as_millis() as u64,
+            timeouts: BTreeMap::new(),
+            max_missing: None,
+            budget_ms: None,
+            allow_commands: true,
+        }
+    }
+}
+
+impl CollectionConfig {
+    /// Get the timeout of a collector
+    pub fn timeout(&self, collector: &str) -> Duration {
+        Duration::from_millis(*self.timeouts.get(collector).unwrap_or(&self.timeout_ms))
+    }
+
+    /// Check whether all settings are at their defaults
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Check whether a flag is at its default of `true`
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Check whether a root is the live system's
+fn is_live_root(root: &Path) -> bool {
+    root == Path::new("/")
+}
+
+/// Scoring configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PwrzvConfig {
+    /// How metric scores are combined
+    #[serde(default)]
+    pub aggregation: Aggregation,
+    /// Collector timeouts and the missing-metric policy
+    #[serde(default, skip_serializing_if = "CollectionConfig::is_default")]
+    pub collection: CollectionConfig,
+    /// Linux metric parameters keyed by metric ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub linux: BTreeMap<String, MetricConfig>,
+    /// macOS metric parameters keyed by metric ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub macos: BTreeMap<String, MetricConfig>,
+}
+
+impl PwrzvConfig {
+    /// Parse a configuration from TOML
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pwrzv::config::{Aggregation, PwrzvConfig};
+    ///
+    /// let config = PwrzvConfig::from_toml(
+    ///     "aggregation = \"mean\"\n[linux.cpu_usage]\nmidpoint = 0.75\n",
+    /// ).unwrap();
+    /// assert_eq!(config.aggregation, Aggregation::Mean);
+    /// assert_eq!(config.linux["cpu_usage"].midpoint, Some(0.75));
+    /// ```
+    pub fn from_toml(toml: &str) -> PwrzvResult<Self> {
+        let config: Self = toml::from_str(toml)
+            .map_err(|e| PwrzvError::invalid_value(&format!("configuration: {e}")))?;
+
+        let collection = &config.collection;
+        if let Some((name, _)) = std::iter::once(("timeout_ms", &collection.timeout_ms))
+            .chain(
+                collection
+                    .timeouts
+                    .iter()
+                    .map(|(name, ms)| (name.as_str(), ms)),
+            )
+            .find(|(_, ms)| **ms == 0)
+        {
+            return Err(PwrzvError::invalid_value(&format!(
+                "configuration: [collection] {name}: timeout must be positive"
+            )));
+        }
+
+        for (platform, metrics) in [("linux", &config.linux), ("macos", &config.macos)] {
+            for (id, metric) in metrics {
+                if let Some(weight) = metric.weight
+                    && !(weight.is_finite() && weight >= 0.0)
+                {
+                    return Err(PwrzvError::invalid_value(&format!(
+                        "configuration: [{platform}.{id}] weight must be a non-negative number"
+                    )));
+                }
+                // Stand-in parameters for unset fields; the built-in defaults are valid
+                metric
+                    .build_curve(
+                        metric.midpoint.unwrap_or(0.5),
+                        metric.steepness.unwrap_or(1.0),
+                    )
+                    .map_err(|e| e.context(&format!("configuration: [{platform}.{id}]")))?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Load a configuration file
+    pub fn from_file(path: impl AsRef<Path>) -> PwrzvResult<Self> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path).map_err(|e| PwrzvError::read_error(path, e))?;
+        Self::from_toml(&toml).map_err(|e| e.context(&path.display().to_string()))
+    }
+
+    /// Load the file named by `PWRZV_CONFIG`, or the defaults if it is unset
+    pub fn from_env() -> PwrzvResult<Self> {
+        match std::env::var_os(CONFIG_ENV) {
+            Some(path) => Self::from_file(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Get the parameters of a metric on a platform ("linux" or "macos")
+    pub fn metric(&self, platform: &str, id: &str) -> MetricConfig {
+        self.section(platform)
+            .and_then(|metrics| metrics.get(id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Check whether a metric is collected and scored on a platform
+    pub fn metric_enabled(&self, platform: &str, id: &str) -> bool {
+        self.section(platform)
+            .and_then(|metrics| metrics.get(id))
+            .and_then(|metric| metric.enabled)
+            .unwrap_or(true)
+    }
+
+    /// Get the aggregation weight of a metric on a platform
+    pub fn metric_weight(&self, platform: &str, id: &str) -> f32 {
+        self.section(platform)
+            .and_then(|metrics| metrics.get(id))
+            .and_then(|metric| metric.weight)
+            .unwrap_or_else(|| {
+                if UNWEIGHTED_METRICS.contains(&(platform, id)) {
+                    0.0
+                } else {
+                    1.0
+                }
+            })
+    }
+
+    /// Enable or disable metrics of the current platform
+    ///
+    /// Each name is a metric ID or the prefix of metric IDs followed by `_`,
+    /// so `cpu` selects every `cpu_*` metric: on Linux `cpu_usage`,
+    /// `cpu_io_wait`, `cpu_steal`, `cpu_load`, `cpu_throttling` and
+    /// `cpu_core_saturation`. Disabled metrics are neither collected nor
+    /// scored; a collector is skipped once all of its metrics are disabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pwrzv::config::PwrzvConfig;
+    ///
+    /// let mut config = PwrzvConfig::default();
+    /// config.set_enabled(&["process_count"], false).unwrap();
+    /// assert!(config.set_enabled(&["gpu"], false).is_err());
+    /// ```
+    pub fn set_enabled<S: AsRef<str>>(&mut self, names: &[S], enabled: bool) -> PwrzvResult<()> {
+        let ids = Self::select(names)?;
+        let section = self.current_section_mut()?;
+        for id in ids {
+            section.entry(id.to_string()).or_default().enabled = Some(enabled);
+        }
+        Ok(())
+    }
+
+    /// Disable every metric of the current platform except the named ones
+    ///
+    /// Names are matched like in [`set_enabled`](Self::set_enabled).
+    pub fn enable_only<S: AsRef<str>>(&mut self, names: &[S]) -> PwrzvResult<()> {
+        let selected = Self::select(names)?;
+        let section = self.current_section_mut()?;
+        for spec in platform_metrics() {
+            section.entry(spec.id.to_string()).or_default().enabled =
+                Some(selected.contains(&spec.id));
+        }
+        Ok(())
+    }
+
+    /// Set the aggregation weight of a metric of the current platform
+    ///
+    /// See [`Aggregation::apply_weighted`] for how weights are applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Metric ID
+    /// * `weight` - Non-negative weight; 0 keeps the metric out of the level
+    pub fn set_weight(&mut self, id: &str, weight: f32) -> PwrzvResult<()> {
+        if !(weight.is_finite() && weight >= 0.0) {
+            return Err(PwrzvError::invalid_value(&format!(
+                "{id}: weight must be a non-negative number, got {weight}"
+            )));
+        }
+        if !platform_metrics().iter().any(|spec| spec.id == id) {
+            return Err(Self::unknown_metric(id));
+        }
+        self.current_section_mut()?
+            .entry(id.to_string())
+            .or_default()
+            .weight = Some(weight);
+        Ok(())
+    }
+
+    /// Resolve metric IDs and ID prefixes to the metric IDs of the current platform
+    fn select<S: AsRef<str>>(names: &[S]) -> PwrzvResult<Vec<&'static str>> {
+        let mut ids = Vec::new();
+        for name in names {
+            let name = name.as_ref();
+            let prefix = format!("{name}_");
+            let matched: Vec<_> = platform_metrics()
+                .iter()
+                .map(|spec| spec.id)
+                .filter(|id| *id == name || id.starts_with(&prefix))
+                .collect();
+            if matched.is_empty() {
+                return Err(Self::unknown_metric(name));
+            }
+            ids.extend(matched);
+        }
+        Ok(ids)
+    }
+
+    /// Error for a name that selects no metric of the current platform
+    fn unknown_metric(name: &str) -> PwrzvError {
+        let ids: Vec<_> = platform_metrics().iter().map(|spec| spec.id).collect();
+        let hint = match closest(name, ids.iter().copied()) {
+            Some(suggestion) => format!("did you mean {suggestion}?"),
+            None => format!("available: {}", ids.join(", ")),
+        };
+        PwrzvError::invalid_value(&format!("unknown metric {name:?} ({hint})"))
+    }
+
+    /// Get the metric section of a platform
+    fn section(&self, platform: &str) -> Option<&BTreeMap<String, MetricConfig>> {
+        match platform {
+            "linux" => Some(&self.linux),
+            "macos" => Some(&self.macos),
+            _ => None,
+        }
+    }
+
+    /// Get the metric section of the current platform for editing
+    fn current_section_mut(&mut self) -> PwrzvResult<&mut BTreeMap<String, MetricConfig>> {
+        match std::env::consts::OS {
+            "linux" => Ok(&mut self.linux),
+            "macos" => Ok(&mut self.macos),
+            other => Err(PwrzvError::unsupported_platform(other)),
+        }
+    }
+
+    /// Serialize the configuration as TOML
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Get the effective curve of every metric on the current platform
+    ///
+    /// Includes the systemd unit (cgroup) metrics on Linux, which are only
+    /// configurable through the environment. Metrics whose curve is invalid
+    /// are listed with the curve they fall back to: the configured one if only
+    /// the environment overrides are invalid, otherwise the built-in one.
+    pub fn effective(&self) -> Vec<EffectiveMetric> {
+        let scoped = |scope: &'static str, specs: &'static [MetricSpec]| {
+            specs.iter().map(move |spec| (scope, spec))
+        };
+        scoped(std::env::consts::OS, platform_metrics())
+            .chain(scoped("cgroup", unit_metrics()))
+            .map(|(scope, spec)| spec.resolve_or_fallback(scope, self, &env_f32))
+            .collect()
+    }
+
+    /// Check the configuration against the metrics of the current platform
+    ///
+    /// Reports sections for unknown metric IDs and curves that fail validation.
+    /// Sections of other platforms are not checked.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let platform = std::env::consts::OS;
+        let Some(section) = self.section(platform) else {
+            return Vec::new();
+        };
+        let ids: Vec<_> = platform_metrics().iter().map(|spec| spec.id).collect();
+
+        let mut issues = Vec::new();
+        let collectors: Vec<_> = platform_collectors()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        for name in self.collection.timeouts.keys() {
+            if !collectors.contains(&name.as_str()) {
+                issues.push(ConfigIssue::UnknownCollector {
+                    collector: name.clone(),
+                    suggestion: closest(name, collectors.iter().copied()),
+                });
+            }
+        }
+        for (id, metric) in section {
+            if !ids.contains(&id.as_str()) {
+                issues.push(ConfigIssue::UnknownMetric {
+                    platform: platform.to_string(),
+                    metric: id.clone(),
+                    suggestion: closest(id, ids.iter().copied()),
+                });
+                continue;
+            }
+            let midpoint = metric.midpoint.unwrap_or(0.5);
+            let steepness = metric.steepness.unwrap_or(1.0);
+            if let Err(e) = metric.build_curve(midpoint, steepness) {
+                issues.push(ConfigIssue::InvalidCurve {
+                    platform: platform.to_string(),
+                    metric: id.clone(),
+                    reason: e.detail(),
+                });
+            }
+        }
+        issues
+    }
+}
+
+// ================================
+// Effective configuration and validation
+// ================================
+
+/// Where an effective parameter value comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueSource {
+    /// Built-in default
+    Default,
+    /// Configuration file
+    File,
+    /// Environment variable
+    Env(String),
+}
+
+impl fmt::Display for ValueSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueSource::Default => f.write_str("default"),
+            ValueSource::File => f.write_str("file"),
+            ValueSource::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
+/// A single effective curve parameter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveParam {
+    /// Parameter name (`curve`, `midpoint`, `steepness`, `asymmetry`, `points`,
+    /// `enabled` or `weight`)
+    pub name: &'static str,
+    /// Parameter value as it would be written in the configuration file
+    pub value: String,
+    /// Where the value comes from
+    pub source: ValueSource,
+}
+
+/// Effective curve of a metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveMetric {
+    /// "linux", "macos" or "cgroup" (systemd unit mode)
+    pub scope: String,
+    /// Metric ID
+    pub id: String,
+    /// Human-readable metric label
+    pub label: String,
+    /// The curve used for scoring
+    pub curve: Curve,
+    /// Curve parameters with their sources
+    pub params: Vec<EffectiveParam>,
+}
+
+/// A configuration problem found by [`check`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ConfigIssue {
+    /// A `PWRZV_*` environment variable that pwrzv does not read
+    UnknownVariable {
+        /// Variable name
+        var: String,
+        /// The closest known variable, if any is similar
+        suggestion: Option<String>,
+    },
+    /// A known environment variable whose value cannot be used
+    InvalidVariable {
+        /// Variable name
+        var: String,
+        /// Variable value
+        value: String,
+        /// Why the value is rejected
+        reason: String,
+    },
+    /// The configuration file cannot be read or parsed
+    ConfigFile {
+        /// Configuration file path
+        path: PathBuf,
+        /// Why loading failed
+        reason: String,
+    },
+    /// A configuration file section for a metric that does not exist
+    UnknownMetric {
+        /// Platform section
+        platform: String,
+        /// Metric ID
+        metric: String,
+        /// The closest known metric ID, if any is similar
+        suggestion: Option<String>,
+    },
+    /// A `[collection.timeouts]` entry for a collector that does not exist
+    UnknownCollector {
+        /// Collector name
+        collector: String,
+        /// The closest known collector name, if any is similar
+        suggestion: Option<String>,
+    },
+    /// A metric whose configured curve is invalid
+    InvalidCurve {
+        /// Platform section
+        platform: String,
+        /// Metric ID
+        metric: String,
+        /// Why the curve is rejected
+        reason: String,
+    },
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::UnknownVariable { var, suggestion } => {
+                write!(f, "{var}: unknown variable")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean {suggestion}?)")?;
+                }
+                Ok(())
+            }
+            ConfigIssue::InvalidVariable { var, value, reason } => {
+                write!(f, "{var}={value:?}: {reason}")
+            }
+            ConfigIssue::ConfigFile { path, reason } => write!(f, "{}: {reason}", path.display()),
+            ConfigIssue::UnknownMetric {
+                platform,
+                metric,
+                suggestion,
+            } => {
+                write!(f, "[{platform}.{metric}]: unknown metric")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean {suggestion}?)")?;
+                }
+                Ok(())
+            }
+            ConfigIssue::UnknownCollector {
+                collector,
+                suggestion,
+            } => {
+                write!(f, "[collection.timeouts] {collector}: unknown collector")?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " (did you mean {suggestion}?)")?;
+                }
+                Ok(())
+            }
+            ConfigIssue::InvalidCurve {
+                platform,
+                metric,
+                reason,
+            } => write!(f, "[{platform}.{metric}]: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigIssue {}
+
+/// Check the environment and configuration file for problems
+///
+/// Loads the configuration from `config_path`, or from `PWRZV_CONFIG` if no
+/// path is given, and reports every problem instead of falling back to the
+/// defaults like scoring does.
+///
+/// # Example
+///
+/// ```rust
+/// for issue in pwrzv::config::check(None) {
+///     eprintln!("{issue}");
+/// }
+/// ```
+pub fn check(config_path: Option<&Path>) -> Vec<ConfigIssue> {
+    let mut issues = check_env_vars(std::env::vars());
+
+    let path = config_path
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from));
+    let config = match path {
+        Some(path) => match PwrzvConfig::from_file(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                let detail = e.detail();
+                let prefix = format!("{}: ", path.display());
+                let reason = detail.strip_prefix(&prefix).unwrap_or(&detail).to_string();
+                issues.push(ConfigIssue::ConfigFile { path, reason });
+                return issues;
+            }
+        },
+        None => PwrzvConfig::default(),
+    };
+    issues.extend(config.validate());
+    issues
+}
+
+/// Check `PWRZV_*` environment variables
+///
+/// Variables of another platform (e.g. `PWRZV_MACOS_*` on Linux) are skipped.
+///
+/// # Arguments
+///
+/// * `vars` - Environment variables as name/value pairs
+pub fn check_env_vars(vars: impl IntoIterator<Item = (String, String)>) -> Vec<ConfigIssue> {
+    let specs = platform_metrics().iter().chain(unit_metrics());
+    let mut known: Vec<String> = OTHER_ENV_VARS.iter().map(|v| v.to_string()).collect();
+    let mut numeric = Vec::new();
+    for spec in specs {
+        for suffix in ["MIDPOINT", "STEEPNESS"] {
+            let var = format!("{}_{suffix}", spec.env_prefix);
+            known.push(var.clone());
+            numeric.push(var);
+        }
+    }
+    let foreign = match std::env::consts::OS {
+        "linux" => "PWRZV_MACOS_",
+        "macos" => "PWRZV_LINUX_",
+        _ => "PWRZV_",
+    };
+
+    let mut vars: Vec<_> = vars
+        .into_iter()
+        .filter(|(var, _)| var.starts_with("PWRZV_") && !var.starts_with(foreign))
+        .collect();
+    vars.sort();
+
+    let mut issues = Vec::new();
+    for (var, value) in vars {
+        if !known.contains(&var) {
+            let suggestion = closest(&var, known.iter().map(String::as_str));
+            issues.push(ConfigIssue::UnknownVariable { var, suggestion });
+            continue;
+        }
+        if !numeric.contains(&var) {
+            continue;
+        }
+
+        let reason = match value.trim().parse::<f32>() {
+            Err(_) => Some("not a number".to_string()),
+            Ok(number) if !number.is_finite() => Some("must be a finite number".to_string()),
+            Ok(number) if var.ends_with("_STEEPNESS") && number <= 0.0 => {
+                Some("steepness must be positive to keep the curve monotonic".to_string())
+            }
+            Ok(_) => None,
+        };
+        if let Some(reason) = reason {
+            issues.push(ConfigIssue::InvalidVariable { var, value, reason });
+        }
+    }
+    issues
+}
+
+/// Find the candidate closest to a misspelled name, if it is close enough
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 3.max(candidate.len() / 8))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Get the process-wide configuration
+///
+/// Loaded from `PWRZV_CONFIG` on first use. A broken configuration file is
+/// reported once on stderr and replaced by the defaults; use
+/// [`PwrzvConfig::from_env`] to handle the error instead.
+pub fn global() -> Arc<PwrzvConfig> {
+    if let Some(config) = GLOBAL.read().unwrap().as_ref() {
+        return Arc::clone(config);
+    }
+
+    let mut slot = GLOBAL.write().unwrap();
+    let config = slot.get_or_insert_with(|| {
+        Arc::new(PwrzvConfig::from_env().unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring configuration: {e}");
+            PwrzvConfig::default()
+        }))
+    });
+    Arc::clone(config)
+}
+
+/// Replace the process-wide configuration
+pub fn set_global(config: PwrzvConfig) {
+    *GLOBAL.write().unwrap() = Some(Arc::new(config));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregation() {
+        let scores = [4.0, 2.0, 3.0, 5.0];
+        assert_eq!(Aggregation::Min.apply(&scores), Some(2.0));
+        assert_eq!(Aggregation::Mean.apply(&scores), Some(3.5));
+        assert_eq!(Aggregation::Median.apply(&scores), Some(3.5));
+        assert_eq!(Aggregation::Median.apply(&[1.0, 4.0, 2.0]), Some(2.0));
+        assert_eq!(Aggregation::Min.apply(&[]), None);
+    }
+
+    #[test]
+    fn test_weighted_aggregation() {
+        let scores = [(4.0, 1.0), (2.0, 0.5), (3.0, 2.0), (5.0, 0.0)];
+        // Weights scale how far each score falls below 5.0: 4.0, 3.5, 1.0
+        assert_eq!(Aggregation::Min.apply_weighted(&scores), Some(1.0));
+        // 5.0 - 3.0 * (5.0 - 0.5) would be negative; the level stops at 1.0
+        assert_eq!(
+            Aggregation::Min.apply_weighted(&[(0.5, 3.0), (4.0, 1.0)]),
+            Some(1.0)
+        );
+        let scores = [(4.0, 1.0), (2.0, 1.0), (3.0, 2.0), (5.0, 0.0)];
+        assert_eq!(Aggregation::Mean.apply_weighted(&scores), Some(3.0));
+        assert_eq!(Aggregation::Median.apply_weighted(&scores), Some(3.0));
+        assert_eq!(
+            Aggregation::Median.apply_weighted(&[(1.0, 1.0), (2.0, 1.0), (4.0, 2.0)]),
+            Some(3.0)
+        );
+        assert_eq!(
+            Aggregation::Min.apply_weighted(&[(1.0, 0.0), (2.0, 0.0)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = PwrzvConfig::from_toml(
+            r#"
+aggregation = "median"
+
+[linux.cpu_usage]
+midpoint = 0.75
+steepness = 10.0
+
+[linux.memory_pressure]
+steepness = 6
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.aggregation, Aggregation::Median);
+        assert_eq!(
+            config.metric("linux", "cpu_usage"),
+            MetricConfig {
+                midpoint: Some(0.75),
+                steepness: Some(10.0),
+                ..Default::default()
+            }
+        );
+        assert_eq!(config.metric("linux", "memory_pressure").midpoint, None);
+        assert_eq!(config.metric("macos", "cpu_usage"), MetricConfig::default());
+
+        let roundtrip = PwrzvConfig::from_toml(&config.to_toml()).unwrap();
+        assert_eq!(roundtrip, config);
+        assert!(config.to_toml().contains("midpoint = 0.75\n"));
+
+        let precise = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.0713").unwrap();
+        assert!(precise.to_toml().contains("midpoint = 0.0713\n"));
+    }
+
+    #[test]
+    fn test_from_toml_curves() {
+        let config = PwrzvConfig::from_toml(
+            r#"
+[linux.file_descriptors]
+curve = "piecewise_linear"
+points = [[0.80, 5.0], [0.95, 1.0], [1.0, 0.0]]
+
+[linux.memory_pressure]
+curve = "richards"
+asymmetry = 0.5
+"#,
+        )
+        .unwrap();
+
+        let fd = config.metric("linux", "file_descriptors");
+        assert_eq!(fd.curve, Some(CurveKind::PiecewiseLinear));
+        assert_eq!(
+            fd.build_curve(0.9, 25.0).unwrap(),
+            Curve::PiecewiseLinear {
+                points: vec![[0.80, 5.0], [0.95, 1.0], [1.0, 0.0]]
+            }
+        );
+        assert_eq!(
+            config
+                .metric("linux", "memory_pressure")
+                .build_curve(0.3, 12.0)
+                .unwrap(),
+            Curve::Richards {
+                midpoint: 0.3,
+                steepness: 12.0,
+                asymmetry: 0.5
+            }
+        );
+
+        let roundtrip = PwrzvConfig::from_toml(&config.to_toml()).unwrap();
+        assert_eq!(roundtrip, config);
+    }
+
+    #[test]
+    fn test_from_toml_rejects_invalid() {
+        for toml in [
+            "aggregation = \"max\"",
+            "[linux.cpu_usage]\nmidpiont = 0.5",
+            "[linux.cpu_usage]\nmidpoint = \"high\"",
+            "[linux.cpu_usage]\nmidpoint = nan",
+            "[linux.cpu_usage]\nsteepness = -8",
+            "[linux.cpu_usage]\ncurve = \"spline\"",
+            "[linux.cpu_usage]\ncurve = \"step\"",
+            "[linux.cpu_usage]\ncurve = \"step\"\nmidpoint = 0.5\npoints = [[0.5, 2.0]]",
+            "[linux.cpu_usage]\npoints = [[0.5, 2.0]]",
+            "[linux.cpu_usage]\ncurve = \"exponential\"\nasymmetry = 2.0",
+            "[linux.cpu_usage]\nweight = -1",
+            "[linux.cpu_usage]\nenabled = \"no\"",
+            "[linux.file_descriptors]\ncurve = \"piecewise_linear\"\npoints = [[0.8, 1.0], [0.9, 5.0]]",
+            "[windows.cpu_usage]\nmidpoint = 0.5",
+        ] {
+            assert!(PwrzvConfig::from_toml(toml).is_err(), "{toml}");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_check_env_vars() {
+        let vars = [
+            ("PWRZV_LINUX_CPU_USAGE_MIDPOINT", "0.7"),
+            ("PWRZV_LINUX_CPU_USAGE_MIDPIONT", "0.7"),
+            ("PWRZV_LINUX_CPU_IOWAIT_STEEPNESS", "abc"),
+            ("PWRZV_LINUX_MEMORY_PRESSURE_STEEPNESS", "-2"),
+            ("PWRZV_LINUX_CGROUP_TASKS_STEEPNESS", "12"),
+            ("PWRZV_MACOS_CPU_USAGE_MIDPOINT", "oops"),
+            ("PWRZV_STORE", "/tmp/history"),
+            ("HOME", "/root"),
+        ]
+        .map(|(var, value)| (var.to_string(), value.to_string()));
+
+        let issues = check_env_vars(vars);
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(matches!(
+            &issues[0],
+            ConfigIssue::InvalidVariable { var, .. } if var == "PWRZV_LINUX_CPU_IOWAIT_STEEPNESS"
+        ));
+        assert_eq!(
+            issues[1],
+            ConfigIssue::UnknownVariable {
+                var: "PWRZV_LINUX_CPU_USAGE_MIDPIONT".to_string(),
+                suggestion: Some("PWRZV_LINUX_CPU_USAGE_MIDPOINT".to_string()),
+            }
+        );
+        assert!(issues[2].to_string().contains("steepness must be positive"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_validate_and_effective() {
+        let config = PwrzvConfig::from_toml(
+            "[linux.cpu_usge]\nmidpoint = 0.5\n\n[linux.memory_usage]\nmidpoint = 0.9",
+        )
+        .unwrap();
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "[linux.cpu_usge]: unknown metric (did you mean cpu_usage?)"
+        );
+
+        let effective = config.effective();
+        let memory = effective
+            .iter()
+            .find(|metric| metric.scope == "linux" && metric.id == "memory_usage")
+            .unwrap();
+        let midpoint = memory.params.iter().find(|p| p.name == "midpoint").unwrap();
+        assert_eq!(midpoint.value, "0.9");
+        assert!(effective.iter().any(|metric| metric.scope == "cgroup"));
+    }
+
+    #[test]
+    fn test_enabled_and_weight() {
+        let config =
+            PwrzvConfig::from_toml("[linux.process_count]\nenabled = false\nweight = 0.5").unwrap();
+        assert!(!config.metric_enabled("linux", "process_count"));
+        assert!(config.metric_enabled("linux", "cpu_usage"));
+        assert_eq!(config.metric_weight("linux", "process_count"), 0.5);
+        assert_eq!(config.metric_weight("macos", "process_count"), 1.0);
+        assert_eq!(PwrzvConfig::from_toml(&config.to_toml()).unwrap(), config);
+
+        // Core saturation is scored only when given a weight
+        assert_eq!(config.metric_weight("linux", "cpu_core_saturation"), 0.0);
+        let config = PwrzvConfig::from_toml("[linux.cpu_core_saturation]\nweight = 1").unwrap();
+        assert_eq!(config.metric_weight("linux", "cpu_core_saturation"), 1.0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_select_metrics() {
+        let mut config = PwrzvConfig::default();
+        config.enable_only(&["cpu", "memory_usage"]).unwrap();
+        let enabled: Vec<_> = platform_metrics()
+            .iter()
+            .map(|spec| spec.id)
+            .filter(|id| config.metric_enabled("linux", id))
+            .collect();
+        assert_eq!(
+            enabled,
+            [
+                "cpu_usage",
+                "cpu_io_wait",
+                "cpu_steal",
+                "cpu_load",
+                "memory_usage",
+                "cpu_throttling",
+                "cpu_core_saturation"
+            ]
+        );
+
+        config.set_enabled(&["cpu_load"], false).unwrap();
+        assert!(!config.metric_enabled("linux", "cpu_load"));
+        config.set_weight("cpu_usage", 2.0).unwrap();
+        assert_eq!(config.metric_weight("linux", "cpu_usage"), 2.0);
+
+        let error = config.set_enabled(&["proces_count"], false).unwrap_err();
+        assert!(
+            error.to_string().contains("did you mean process_count?"),
+            "{error}"
+        );
+        assert!(config.set_weight("cpu_usage", -1.0).is_err());
+        assert!(config.set_weight("cpu", 1.0).is_err());
+    }
+
+    #[test]
+    fn test_collection_section() {
+        let config = PwrzvConfig::default();
+        assert_eq!(
+            config.collection.timeout("cpu"),
+            crate::collector::DEFAULT_TIMEOUT
+        );
+        assert_eq!(config.collection.max_missing, None);
+
+        let config = PwrzvConfig::from_toml(
+            "[collection]\ntimeout_ms = 500\nmax_missing = 2\n\n[collection.timeouts]\nmemry = 3000",
+        )
+        .unwrap();
+        assert_eq!(config.collection.timeout("cpu"), Duration::from_millis(500));
+        assert_eq!(config.collection.timeout("memry"), Duration::from_secs(3));
+        assert_eq!(config.collection.max_missing, Some(2));
+        assert!(matches!(
+            &config.validate()[..],
+            [ConfigIssue::UnknownCollector { collector, .. }] if collector == "memry"
+        ));
+
+        let error = PwrzvConfig::from_toml("[collection]\ntimeout_ms = 0")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("timeout must be positive"), "{error}");
+        assert!(PwrzvConfig::from_toml("[collection]\nretries = 3").is_err());
+
+        assert!(config.collection.allow_commands);
+        let config = PwrzvConfig::from_toml("[collection]\nallow_commands = false").unwrap();
+        assert!(!config.collection.allow_commands);
+        assert!(config.to_toml().contains("allow_commands = false"));
+    }
+}
@@ -0,0 +1,448 @@
+//! Response curve module
+//!
+//! A response curve maps a raw metric value to a pressure in `[0, 1]`, which
+//! the calculators turn into a 0-5 score (`5 * (1 - pressure)`). The logistic
+//! sigmoid is the default; the other curves suit metrics whose impact is not
+//! symmetric around a midpoint, such as file descriptor usage where 0-80% is
+//! irrelevant and 95% is an emergency.
+//!
+//! Curves are selected per metric in the configuration file:
+//!
+//! ```toml
+//! [linux.file_descriptors]
+//! curve = "piecewise_linear"
+//! # [value, score] breakpoints
+//! points = [[0.80, 5.0], [0.95, 1.0], [1.0, 0.0]]
+//!
+//! [linux.memory_pressure]
+//! curve = "richards"
+//! midpoint = 0.30
+//! steepness = 12.0
+//! asymmetry = 0.5
+//! ```
+//!
+//! All curves are validated to be monotonic: a higher metric value never
+//! results in a better score.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::sigmoid::{SigmoidFn, five_point_scale_with_decimal};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Kind of response curve, as named in configuration files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveKind {
+    /// Logistic sigmoid (`midpoint`, `steepness`)
+    #[default]
+    Logistic,
+    /// Linear interpolation between `[value, score]` breakpoints (`points`)
+    PiecewiseLinear,
+    /// Score drops to the score of each `[value, score]` threshold reached (`points`)
+    Step,
+    /// Exponential growth towards the midpoint (`midpoint`, `steepness`)
+    Exponential,
+    /// Generalized logistic (`midpoint`, `steepness`, `asymmetry`)
+    Richards,
+}
+
+impl CurveKind {
+    /// Get the curve name as used in configuration files
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CurveKind::Logistic => "logistic",
+            CurveKind::PiecewiseLinear => "piecewise_linear",
+            CurveKind::Step => "step",
+            CurveKind::Exponential => "exponential",
+            CurveKind::Richards => "richards",
+        }
+    }
+}
+
+impl fmt::Display for CurveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Response curve of a metric
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "curve", rename_all = "snake_case")]
+pub enum Curve {
+    /// `1 / (1 + e^(-steepness * (x - midpoint)))`
+    Logistic {
+        /// Value scoring 2.5
+        midpoint: f32,
+        /// Slope at the midpoint; higher is steeper
+        steepness: f32,
+    },
+    /// Linear interpolation between `[value, score]` breakpoints, flat outside them
+    PiecewiseLinear {
+        /// Breakpoints with increasing values and non-increasing scores
+        points: Vec<[f32; 2]>,
+    },
+    /// Score 5 below the first threshold, then the score of the last threshold reached
+    Step {
+        /// Thresholds with increasing values and non-increasing scores
+        points: Vec<[f32; 2]>,
+    },
+    /// `0.5 * e^(steepness * (x - midpoint))`, capped at 1
+    ///
+    /// Values well below the midpoint barely matter, pressure then rises sharply.
+    Exponential {
+        /// Value scoring 2.5
+        midpoint: f32,
+        /// Growth rate; higher is steeper
+        steepness: f32,
+    },
+    /// Generalized logistic `(1 + q * e^(-steepness * (x - midpoint)))^(-1 / asymmetry)`
+    ///
+    /// `q` is chosen so that the midpoint still scores 2.5. An asymmetry of 1 is
+    /// the logistic curve; below 1 the curve bends early and saturates slowly,
+    /// above 1 it stays flat longer and then drops quickly.
+    Richards {
+        /// Value scoring 2.5
+        midpoint: f32,
+        /// Slope parameter; higher is steeper
+        steepness: f32,
+        /// Shape parameter (nu), must be positive
+        asymmetry: f32,
+    },
+}
+
+impl Curve {
+    /// Get the kind of the curve
+    pub fn kind(&self) -> CurveKind {
+        match self {
+            Curve::Logistic { .. } => CurveKind::Logistic,
+            Curve::PiecewiseLinear { .. } => CurveKind::PiecewiseLinear,
+            Curve::Step { .. } => CurveKind::Step,
+            Curve::Exponential { .. } => CurveKind::Exponential,
+            Curve::Richards { .. } => CurveKind::Richards,
+        }
+    }
+
+    /// Get the steepness of parametric curves
+    pub fn steepness(&self) -> Option<f32> {
+        match self {
+            Curve::Logistic { steepness, .. }
+            | Curve::Exponential { steepness, .. }
+            | Curve::Richards { steepness, .. } => Some(*steepness),
+            Curve::PiecewiseLinear { .. } | Curve::Step { .. } => None,
+        }
+    }
+
+    /// Evaluate the curve
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Raw metric value
+    ///
+    /// # Returns
+    ///
+    /// Pressure in range [0, 1]; 0 scores 5.0 and 1 scores 0.0
+    pub fn evaluate(&self, x: f32) -> f32 {
+        match self {
+            Curve::Logistic {
+                midpoint,
+                steepness,
+            } => SigmoidFn {
+                midpoint: *midpoint,
+                steepness: *steepness,
+            }
+            .evaluate(x),
+            Curve::PiecewiseLinear { points } => {
+                let score = match points.iter().position(|&[value, _]| x < value) {
+                    Some(0) => points[0][1],
+                    Some(i) => {
+                        let ([x0, s0], [x1, s1]) = (points[i - 1], points[i]);
+                        s0 + (s1 - s0) * (x - x0) / (x1 - x0)
+                    }
+                    None => points[points.len() - 1][1],
+                };
+                1.0 - score / 5.0
+            }
+            Curve::Step { points } => {
+                let score = points
+                    .iter()
+                    .rev()
+                    .find(|&&[value, _]| x >= value)
+                    .map_or(5.0, |&[_, score]| score);
+                1.0 - score / 5.0
+            }
+            Curve::Exponential {
+                midpoint,
+                steepness,
+            } => (0.5 * (steepness * (x - midpoint)).exp()).min(1.0),
+            Curve::Richards {
+                midpoint,
+                steepness,
+                asymmetry,
+            } => {
+                let q = 2f32.powf(*asymmetry) - 1.0;
+                (1.0 + q * (-steepness * (x - midpoint)).exp()).powf(-1.0 / asymmetry)
+            }
+        }
+    }
+
+    /// Score a raw metric value on the 0-5 scale used in reports
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Raw metric value
+    ///
+    /// # Returns
+    ///
+    /// Score in range [0.0, 5.0] with 4 decimal places
+    pub fn score(&self, x: f32) -> f32 {
+        five_point_scale_with_decimal(self.evaluate(x))
+    }
+
+    /// Check that the curve is well-formed and monotonic
+    ///
+    /// Parametric curves need finite parameters and a positive steepness (and
+    /// asymmetry); breakpoint curves need strictly increasing values and
+    /// non-increasing scores within 0-5.
+    pub fn validate(&self) -> PwrzvResult<()> {
+        let invalid = |reason: String| Err(PwrzvError::invalid_value(&reason));
+
+        match self {
+            Curve::Logistic {
+                midpoint,
+                steepness,
+            }
+            | Curve::Exponential {
+                midpoint,
+                steepness,
+            }
+            | Curve::Richards {
+                midpoint,
+                steepness,
+                ..
+            } => {
+                if !midpoint.is_finite() {
+                    return invalid(format!("{} midpoint must be finite", self.kind()));
+                }
+                if !(steepness.is_finite() && *steepness > 0.0) {
+                    return invalid(format!(
+                        "{} steepness must be positive to keep the curve monotonic, got {steepness}",
+                        self.kind()
+                    ));
+                }
+                if let Curve::Richards { asymmetry, .. } = self
+                    && !(asymmetry.is_finite() && *asymmetry > 0.0)
+                {
+                    return invalid(format!(
+                        "richards asymmetry must be positive, got {asymmetry}"
+                    ));
+                }
+            }
+            Curve::PiecewiseLinear { points } | Curve::Step { points } => {
+                let required = if self.kind() == CurveKind::Step { 1 } else { 2 };
+                if points.len() < required {
+                    return invalid(format!(
+                        "{} curve needs at least {required} point(s)",
+                        self.kind()
+                    ));
+                }
+                for &[value, score] in points {
+                    if !value.is_finite() || !(0.0..=5.0).contains(&score) {
+                        return invalid(format!(
+                            "{} point [{value}, {score}] needs a finite value and a score within 0-5",
+                            self.kind()
+                        ));
+                    }
+                }
+                for pair in points.windows(2) {
+                    let ([x0, s0], [x1, s1]) = (pair[0], pair[1]);
+                    if x1 <= x0 {
+                        return invalid(format!(
+                            "{} point values must be strictly increasing ({x0} then {x1})",
+                            self.kind()
+                        ));
+                    }
+                    if s1 > s0 {
+                        return invalid(format!(
+                            "{} scores must not increase with the value \
+                             (score {s0} at {x0} but {s1} at {x1})",
+                            self.kind()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<SigmoidFn> for Curve {
+    fn from(sigmoid: SigmoidFn) -> Self {
+        Curve::Logistic {
+            midpoint: sigmoid.midpoint,
+            steepness: sigmoid.steepness,
+        }
+    }
+}
+
+impl fmt::Display for Curve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Curve::Logistic {
+                midpoint,
+                steepness,
+            }
+            | Curve::Exponential {
+                midpoint,
+                steepness,
+            } => write!(
+                f,
+                "{}(midpoint={midpoint}, steepness={steepness})",
+                self.kind()
+            ),
+            Curve::Richards {
+                midpoint,
+                steepness,
+                asymmetry,
+            } => write!(
+                f,
+                "richards(midpoint={midpoint}, steepness={steepness}, asymmetry={asymmetry})"
+            ),
+            Curve::PiecewiseLinear { points } | Curve::Step { points } => {
+                let points = points
+                    .iter()
+                    .map(|[value, score]| format!("{value}->{score}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "{}({points})", self.kind())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(curve: &Curve, x: f32) -> f32 {
+        5.0 * (1.0 - curve.evaluate(x))
+    }
+
+    fn all_curves() -> Vec<Curve> {
+        vec![
+            Curve::Logistic {
+                midpoint: 0.5,
+                steepness: 10.0,
+            },
+            Curve::PiecewiseLinear {
+                points: vec![[0.8, 5.0], [0.95, 1.0], [1.0, 0.0]],
+            },
+            Curve::Step {
+                points: vec![[0.5, 4.0], [0.9, 1.0]],
+            },
+            Curve::Exponential {
+                midpoint: 0.8,
+                steepness: 10.0,
+            },
+            Curve::Richards {
+                midpoint: 0.5,
+                steepness: 10.0,
+                asymmetry: 0.3,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_curves_are_monotonic() {
+        for curve in all_curves() {
+            curve.validate().unwrap();
+            let mut previous = f32::NEG_INFINITY;
+            for i in -10..=30 {
+                let pressure = curve.evaluate(i as f32 * 0.05);
+                assert!((0.0..=1.0).contains(&pressure), "{curve}: {pressure}");
+                assert!(pressure >= previous, "{curve} is not monotonic");
+                previous = pressure;
+            }
+        }
+    }
+
+    #[test]
+    fn test_midpoint_scores() {
+        for curve in all_curves() {
+            if let Curve::Logistic { midpoint, .. }
+            | Curve::Exponential { midpoint, .. }
+            | Curve::Richards { midpoint, .. } = curve
+            {
+                assert!((score(&curve, midpoint) - 2.5).abs() < 1e-4, "{curve}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_richards_generalizes_logistic() {
+        let logistic = Curve::Logistic {
+            midpoint: 0.6,
+            steepness: 8.0,
+        };
+        let richards = Curve::Richards {
+            midpoint: 0.6,
+            steepness: 8.0,
+            asymmetry: 1.0,
+        };
+        for x in [0.0, 0.3, 0.6, 0.9, 1.2] {
+            assert!((logistic.evaluate(x) - richards.evaluate(x)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_breakpoint_curves() {
+        let piecewise = Curve::PiecewiseLinear {
+            points: vec![[0.8, 5.0], [0.95, 1.0], [1.0, 0.0]],
+        };
+        assert_eq!(score(&piecewise, 0.1), 5.0);
+        assert_eq!(score(&piecewise, 0.8), 5.0);
+        assert!((score(&piecewise, 0.875) - 3.0).abs() < 1e-4);
+        assert!((score(&piecewise, 0.95) - 1.0).abs() < 1e-4);
+        assert_eq!(score(&piecewise, 2.0), 0.0);
+
+        let step = Curve::Step {
+            points: vec![[0.5, 4.0], [0.9, 1.0]],
+        };
+        assert_eq!(score(&step, 0.49), 5.0);
+        assert!((score(&step, 0.5) - 4.0).abs() < 1e-4);
+        assert!((score(&step, 0.95) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_validate_rejects_non_monotonic() {
+        for curve in [
+            Curve::Logistic {
+                midpoint: 0.5,
+                steepness: -8.0,
+            },
+            Curve::Exponential {
+                midpoint: f32::NAN,
+                steepness: 8.0,
+            },
+            Curve::Richards {
+                midpoint: 0.5,
+                steepness: 8.0,
+                asymmetry: 0.0,
+            },
+            Curve::PiecewiseLinear {
+                points: vec![[0.5, 3.0]],
+            },
+            Curve::PiecewiseLinear {
+                points: vec![[0.5, 3.0], [0.4, 1.0]],
+            },
+            Curve::PiecewiseLinear {
+                points: vec![[0.5, 3.0], [0.9, 4.0]],
+            },
+            Curve::Step { points: vec![] },
+            Curve::Step {
+                points: vec![[0.5, 6.0]],
+            },
+        ] {
+            assert!(curve.validate().is_err(), "{curve}");
+        }
+    }
+}
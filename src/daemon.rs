@@ -0,0 +1,533 @@
+//! Daemon module
+//!
+//! Serves power reserve samples from a long-running [`Sampler`] over a Unix
+//! domain socket, so local agents and scripts can query one shared process
+//! instead of spawning `pwrzv` for every reading.
+//!
+//! ## Protocol
+//!
+//! The protocol is line-delimited JSON. Each request is a single JSON object
+//! on its own line, identified by its `method`:
+//!
+//! ```text
+//! {"method":"level"}
+//! {"method":"report"}
+//! {"method":"history","since":"2025-01-01T03:00:00Z"}
+//! {"method":"subscribe"}
+//! ```
+//!
+//! Each response is a single line of the form `{"ok":true,"result":...}` or
+//! `{"ok":false,"error":"..."}`. A connection may issue any number of requests;
+//! after `subscribe`, the daemon writes one response line per new sample until
+//! the client disconnects. A request line longer than 64 KiB gets an error
+//! response and the connection is closed.
+//!
+//! The socket is only accessible to the user running the daemon.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::report::{LevelSample, PowerReserveReport};
+use crate::sampler::Sampler;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::OwnedReadHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Environment variable overriding the default socket path
+pub const SOCKET_ENV: &str = "PWRZV_SOCKET";
+
+/// Longest request line the daemon reads, including the newline
+const MAX_REQUEST_LEN: usize = 64 * 1024;
+
+/// Get the default daemon socket path
+///
+/// Uses `PWRZV_SOCKET` if set, otherwise `$XDG_RUNTIME_DIR/pwrzv.sock`.
+///
+/// # Returns
+///
+/// The socket path, or an error if neither variable is set: there is no
+/// shared fallback such as `/tmp`, where other users could take the path
+pub fn default_socket_path() -> PwrzvResult<PathBuf> {
+    if let Ok(path) = std::env::var(SOCKET_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => Ok(Path::new(&dir).join("pwrzv.sock")),
+        _ => Err(PwrzvError::invalid_value(&format!(
+            "no socket path: set {SOCKET_ENV} or XDG_RUNTIME_DIR, or pass the path explicitly"
+        ))),
+    }
+}
+
+/// A request sent to the daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Request {
+    /// Current power reserve level
+    Level,
+    /// Latest detailed report
+    Report,
+    /// Buffered reports taken at or after `since` (all buffered reports if omitted)
+    History {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        since: Option<DateTime<Utc>>,
+    },
+    /// Stream every new report
+    Subscribe,
+}
+
+/// A single response line written by the daemon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    /// Whether the request succeeded
+    pub ok: bool,
+    /// Request result when `ok` is true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    /// Error message when `ok` is false
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn success<T: Serialize>(result: &T) -> Self {
+        match serde_json::to_value(result) {
+            Ok(value) => Self {
+                ok: true,
+                result: Some(value),
+                error: None,
+            },
+            Err(e) => Self::failure(&format!("Failed to serialize result: {e}")),
+        }
+    }
+
+    fn failure(error: &str) -> Self {
+        Self {
+            ok: false,
+            result: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// Convert into the result payload, turning daemon errors into `PwrzvError`
+    fn into_result<T: for<'de> Deserialize<'de>>(self) -> PwrzvResult<T> {
+        if !self.ok {
+            return Err(PwrzvError::resource_access_error(&format!(
+                "daemon error: {}",
+                self.error.unwrap_or_default()
+            )));
+        }
+        let value = self.result.unwrap_or(serde_json::Value::Null);
+        serde_json::from_value(value)
+            .map_err(|e| PwrzvError::parse_error(&e.to_string()).context("daemon response"))
+    }
+}
+
+// ================================
+// Server
+// ================================
+
+/// Serve daemon requests on a Unix domain socket until the future is dropped
+///
+/// A stale socket file left behind by a previous daemon is removed; if another
+/// daemon is still listening on `path`, an error is returned instead. The
+/// socket is created with mode 0600.
+///
+/// # Arguments
+///
+/// * `path` - Socket path to listen on
+/// * `sampler` - Sampler providing the reports served to clients
+pub async fn serve(path: &Path, sampler: Sampler) -> PwrzvResult<()> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(PwrzvError::resource_access_error(&format!(
+                "another daemon is already listening on {}",
+                path.display()
+            )));
+        }
+        std::fs::remove_file(path)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let sampler = sampler.clone();
+        tokio::spawn(async move {
+            // A client disconnecting mid-response is not a daemon error
+            let _ = handle_connection(stream, sampler).await;
+        });
+    }
+}
+
+/// Handle all requests of a single client connection
+async fn handle_connection(stream: UnixStream, sampler: Sampler) -> std::io::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(MAX_REQUEST_LEN as u64)
+            .read_line(&mut line)
+            .await?;
+        if read == 0 {
+            return Ok(());
+        }
+        if read == MAX_REQUEST_LEN && !line.ends_with('\n') {
+            let error = format!("Request line exceeds {MAX_REQUEST_LEN} bytes");
+            return write_response(&mut writer, &Response::failure(&error)).await;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                write_response(
+                    &mut writer,
+                    &Response::failure(&format!("Invalid request: {e}")),
+                )
+                .await?;
+                continue;
+            }
+        };
+
+        if request == Request::Subscribe {
+            return stream_reports(&mut writer, &sampler).await;
+        }
+
+        let response = respond(&request, &sampler);
+        write_response(&mut writer, &response).await?;
+    }
+}
+
+/// Build the response to a non-streaming request
+fn respond(request: &Request, sampler: &Sampler) -> Response {
+    let latest = || {
+        sampler.latest().ok_or_else(|| match sampler.last_error() {
+            Some(e) => Response::failure(&format!("Failed to collect metrics: {e}")),
+            None => Response::failure("No sample collected yet"),
+        })
+    };
+
+    match request {
+        Request::Level => match latest() {
+            Ok(report) => Response::success(&LevelSample::from(report.as_ref())),
+            Err(response) => response,
+        },
+        Request::Report => match latest() {
+            Ok(report) => Response::success(report.as_ref()),
+            Err(response) => response,
+        },
+        Request::History { since } => {
+            let history: Vec<PowerReserveReport> = sampler
+                .history_since(*since)
+                .iter()
+                .map(|report| report.as_ref().clone())
+                .collect();
+            Response::success(&history)
+        }
+        Request::Subscribe => Response::failure("subscribe must be handled as a stream"),
+    }
+}
+
+/// Stream every new report to the client until it disconnects
+async fn stream_reports(
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+    sampler: &Sampler,
+) -> std::io::Result<()> {
+    let mut receiver = sampler.subscribe();
+    loop {
+        match receiver.recv().await {
+            Ok(report) => write_response(writer, &Response::success(report.as_ref())).await?,
+            // Skip reports the client was too slow to receive
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Write a single response line
+async fn write_response(
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+    response: &Response,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(response).map_err(std::io::Error::other)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+// ================================
+// Client
+// ================================
+
+/// Client for a running `pwrzv daemon`
+///
+/// Each call opens a new connection, so a client can be shared freely.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::daemon::{DaemonClient, default_socket_path};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = DaemonClient::new(default_socket_path()?);
+///     println!("Power Reserve: {:.2}", client.level().await?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DaemonClient {
+    path: PathBuf,
+}
+
+impl DaemonClient {
+    /// Create a client for the daemon listening on `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Get the socket path this client connects to
+    pub fn socket_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the current power reserve level
+    pub async fn level(&self) -> PwrzvResult<f32> {
+        let sample: LevelSample = self.request(&Request::Level).await?;
+        Ok(sample.power_reserve_level)
+    }
+
+    /// Get the latest detailed report
+    pub async fn report(&self) -> PwrzvResult<PowerReserveReport> {
+        self.request(&Request::Report).await
+    }
+
+    /// Get buffered reports taken at or after `since`, oldest first
+    pub async fn history(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> PwrzvResult<Vec<PowerReserveReport>> {
+        self.request(&Request::History { since }).await
+    }
+
+    /// Subscribe to every new report
+    pub async fn subscribe(&self) -> PwrzvResult<Subscription> {
+        let (lines, _writer) = self.send(&Request::Subscribe).await?;
+        Ok(Subscription { lines, _writer })
+    }
+
+    /// Send a request and read a single response
+    async fn request<T: for<'de> Deserialize<'de>>(&self, request: &Request) -> PwrzvResult<T> {
+        let (mut lines, _writer) = self.send(request).await?;
+        read_response(&mut lines)
+            .await?
+            .ok_or_else(|| {
+                PwrzvError::resource_io_error(
+                    "daemon closed the connection",
+                    io::ErrorKind::UnexpectedEof.into(),
+                )
+            })?
+            .into_result()
+    }
+
+    /// Connect and send a request line
+    async fn send(
+        &self,
+        request: &Request,
+    ) -> PwrzvResult<(
+        Lines<BufReader<OwnedReadHalf>>,
+        tokio::net::unix::OwnedWriteHalf,
+    )> {
+        let stream = UnixStream::connect(&self.path).await.map_err(|e| {
+            PwrzvError::resource_io_error(
+                &format!("Failed to connect to daemon at {}", self.path.display()),
+                e,
+            )
+        })?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_string(request)
+            .map_err(|e| PwrzvError::parse_error(&format!("Failed to encode request: {e}")))?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+
+        Ok((BufReader::new(reader).lines(), writer))
+    }
+}
+
+/// Read one response line, returning `None` at end of stream
+async fn read_response(
+    lines: &mut Lines<BufReader<OwnedReadHalf>>,
+) -> PwrzvResult<Option<Response>> {
+    match lines.next_line().await? {
+        Some(line) => serde_json::from_str(&line).map(Some).map_err(|e| {
+            PwrzvError::parse_error(&e.to_string())
+                .context("daemon response")
+                .with_line(&line)
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Stream of reports from a daemon subscription
+#[derive(Debug)]
+pub struct Subscription {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    // Kept alive so the daemon does not see the connection as closed
+    _writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+impl Subscription {
+    /// Wait for the next report, returning `None` when the daemon closes the stream
+    pub async fn next(&mut self) -> PwrzvResult<Option<PowerReserveReport>> {
+        match read_response(&mut self.lines).await? {
+            Some(response) => response.into_result().map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn socket_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pwrzv-daemon-{name}-{}.sock", std::process::id()))
+    }
+
+    /// Start a daemon backed by a sampler with predictable levels 1.0, 2.0, ...
+    async fn start_daemon(name: &str) -> (DaemonClient, tokio::task::JoinHandle<()>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let sampler = Sampler::start_with(Duration::from_millis(10), 100, move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move { Ok(PowerReserveReport::new(n as f32, HashMap::new())) })
+        });
+
+        let path = socket_path(name);
+        let server_path = path.clone();
+        let server = tokio::spawn(async move {
+            serve(&server_path, sampler).await.unwrap();
+        });
+
+        // Wait for the socket and the first sample
+        let client = DaemonClient::new(path);
+        for _ in 0..100 {
+            if client.level().await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        (client, server)
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        assert_eq!(
+            serde_json::to_string(&Request::Level).unwrap(),
+            r#"{"method":"level"}"#
+        );
+        let request: Request =
+            serde_json::from_str(r#"{"method":"history","since":"2025-01-01T03:00:00Z"}"#).unwrap();
+        assert!(matches!(request, Request::History { since: Some(_) }));
+        let request: Request = serde_json::from_str(r#"{"method":"history"}"#).unwrap();
+        assert_eq!(request, Request::History { since: None });
+    }
+
+    #[tokio::test]
+    async fn test_daemon_queries() {
+        let (client, server) = start_daemon("queries").await;
+
+        let level = client.level().await.unwrap();
+        assert!(level >= 1.0);
+
+        let report = client.report().await.unwrap();
+        assert_eq!(report.platform, std::env::consts::OS);
+
+        let history = client.history(None).await.unwrap();
+        assert!(!history.is_empty());
+        let since = history.last().unwrap().timestamp;
+        let recent = client.history(Some(since)).await.unwrap();
+        assert!(recent.iter().all(|r| r.timestamp >= since));
+
+        server.abort();
+        let _ = std::fs::remove_file(client.socket_path());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_subscription() {
+        let (client, server) = start_daemon("subscribe").await;
+
+        let mut subscription = client.subscribe().await.unwrap();
+        let first = subscription.next().await.unwrap().unwrap();
+        let second = subscription.next().await.unwrap().unwrap();
+        assert!(second.power_reserve_level > first.power_reserve_level);
+
+        server.abort();
+        let _ = std::fs::remove_file(client.socket_path());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_rejects_invalid_request() {
+        let (client, server) = start_daemon("invalid").await;
+
+        let stream = UnixStream::connect(client.socket_path()).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(b"{\"method\":\"bogus\"}\n").await.unwrap();
+        let mut lines = BufReader::new(reader).lines();
+        let response = read_response(&mut lines).await.unwrap().unwrap();
+        assert!(!response.ok);
+        assert!(response.error.unwrap().contains("Invalid request"));
+
+        server.abort();
+        let _ = std::fs::remove_file(client.socket_path());
+    }
+
+    #[tokio::test]
+    async fn test_daemon_limits_request_length() {
+        let (client, server) = start_daemon("long").await;
+        let mode = std::fs::metadata(client.socket_path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let stream = UnixStream::connect(client.socket_path()).await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        // Padded with spaces to exactly the limit, the request is still served
+        let request = format!("{:<1$}\n", r#"{"method":"level"}"#, MAX_REQUEST_LEN - 1);
+        writer.write_all(request.as_bytes()).await.unwrap();
+        assert!(read_response(&mut lines).await.unwrap().unwrap().ok);
+
+        writer
+            .write_all(&vec![b' '; MAX_REQUEST_LEN + 1])
+            .await
+            .unwrap();
+        let response = read_response(&mut lines).await.unwrap().unwrap();
+        assert!(response.error.unwrap().contains("exceeds"));
+        // The connection is closed, possibly reset over the unread request
+        assert!(!matches!(read_response(&mut lines).await, Ok(Some(_))));
+
+        server.abort();
+        let _ = std::fs::remove_file(client.socket_path());
+    }
+
+    #[tokio::test]
+    async fn test_client_without_daemon() {
+        let client = DaemonClient::new(socket_path("missing"));
+        assert!(client.level().await.is_err());
+    }
+}
@@ -1,12 +1,23 @@
 //! Error handling module
 //!
 //! Defines all error types and result types used in the pwrzv library.
+//!
+//! Errors raised while collecting metrics carry where they came from: the
+//! metric ID, the file or command that was read, the underlying
+//! [`io::Error`] as [`std::error::Error::source`] and, for parse failures, the
+//! offending line. [`PwrzvError::is_transient`] tells failures worth retrying
+//! apart from those that will keep failing.
 
 use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Error types for the pwrzv library
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum PwrzvError {
     /// I/O error, typically occurs when reading system files
     #[error("I/O error: {0}")]
@@ -18,13 +29,76 @@ pub enum PwrzvError {
     )]
     UnsupportedPlatform { platform: String },
 
-    /// Data parsing error
-    #[error("Failed to parse system data: {detail}")]
-    ParseError { detail: String },
+    /// A file could not be read
+    #[error("Failed to read {}{}: {source}", path.display(), for_metric(metric))]
+    ReadError {
+        /// The file that could not be read
+        path: PathBuf,
+        /// ID of the metric being collected, if any
+        metric: Option<String>,
+        /// The underlying I/O error
+        source: io::Error,
+    },
+
+    /// A command could not be started or did not succeed
+    #[error("Failed to run {command}{}: {detail}", for_metric(metric))]
+    CommandError {
+        /// The command, e.g. `ps`
+        command: String,
+        /// ID of the metric being collected, if any
+        metric: Option<String>,
+        /// What went wrong, e.g. the exit status
+        detail: String,
+        /// The I/O error if the command could not be started
+        #[source]
+        source: Option<io::Error>,
+    },
 
-    /// System resource access error
+    /// Data parsing error
+    #[error(
+        "Failed to parse {}{}: {detail}{}",
+        input.as_deref().unwrap_or("system data"),
+        for_metric(metric),
+        offending_line(line)
+    )]
+    ParseError {
+        /// The file or command output that was parsed, if known
+        input: Option<String>,
+        /// ID of the metric being collected, if any
+        metric: Option<String>,
+        /// The line that could not be parsed, if known
+        line: Option<String>,
+        /// What could not be parsed
+        detail: String,
+    },
+
+    /// System resource access error, e.g. a socket or a directory
     #[error("Failed to access system resource: {resource}")]
-    ResourceAccessError { resource: String },
+    ResourceAccessError {
+        /// The resource, or what went wrong when there is no I/O error
+        resource: String,
+        /// The underlying I/O error, if any
+        #[source]
+        source: Option<io::Error>,
+    },
+
+    /// Sources were read but did not yield a usable value
+    #[error("Failed to collect {}: {detail}", metric.as_deref().unwrap_or("metrics"))]
+    CollectionError {
+        /// ID of the metric being collected, if any
+        metric: Option<String>,
+        /// Why no value could be collected
+        detail: String,
+    },
+
+    /// An operation did not finish in time
+    #[error("Timed out after {}ms: {operation}", timeout.as_millis())]
+    TimedOut {
+        /// The operation, e.g. a webhook URL or a command
+        operation: String,
+        /// The timeout that was exceeded
+        timeout: Duration,
+    },
 
     /// Calculation error
     #[error("Calculation error: {detail}")]
@@ -35,6 +109,108 @@ pub enum PwrzvError {
     InvalidValue { detail: String },
 }
 
+/// Format the metric of an error as a suffix of its subject
+fn for_metric(metric: &Option<String>) -> String {
+    match metric {
+        Some(metric) => format!(" for {metric}"),
+        None => String::new(),
+    }
+}
+
+/// Format the offending line of a parse error
+fn offending_line(line: &Option<String>) -> String {
+    match line {
+        Some(line) => format!(" in line {line:?}"),
+        None => String::new(),
+    }
+}
+
+/// Check whether an I/O error may go away when the operation is retried
+fn is_transient_io(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// Copy an I/O error, keeping its kind and message
+fn clone_io(error: &io::Error) -> io::Error {
+    io::Error::new(error.kind(), error.to_string())
+}
+
+/// Errors are cloned so one failed sample can be handed to many subscribers.
+/// `io::Error` is not `Clone`; copies keep its kind and message but not the
+/// OS error code or the inner error.
+impl Clone for PwrzvError {
+    fn clone(&self) -> Self {
+        match self {
+            PwrzvError::IoError(source) => PwrzvError::IoError(clone_io(source)),
+            PwrzvError::UnsupportedPlatform { platform } => PwrzvError::UnsupportedPlatform {
+                platform: platform.clone(),
+            },
+            PwrzvError::ReadError {
+                path,
+                metric,
+                source,
+            } => PwrzvError::ReadError {
+                path: path.clone(),
+                metric: metric.clone(),
+                source: clone_io(source),
+            },
+            PwrzvError::CommandError {
+                command,
+                metric,
+                detail,
+                source,
+            } => PwrzvError::CommandError {
+                command: command.clone(),
+                metric: metric.clone(),
+                detail: detail.clone(),
+                source: source.as_ref().map(clone_io),
+            },
+            PwrzvError::ParseError {
+                input,
+                metric,
+                line,
+                detail,
+            } => PwrzvError::ParseError {
+                input: input.clone(),
+                metric: metric.clone(),
+                line: line.clone(),
+                detail: detail.clone(),
+            },
+            PwrzvError::ResourceAccessError { resource, source } => {
+                PwrzvError::ResourceAccessError {
+                    resource: resource.clone(),
+                    source: source.as_ref().map(clone_io),
+                }
+            }
+            PwrzvError::CollectionError { metric, detail } => PwrzvError::CollectionError {
+                metric: metric.clone(),
+                detail: detail.clone(),
+            },
+            PwrzvError::TimedOut { operation, timeout } => PwrzvError::TimedOut {
+                operation: operation.clone(),
+                timeout: *timeout,
+            },
+            PwrzvError::CalculationError { detail } => PwrzvError::CalculationError {
+                detail: detail.clone(),
+            },
+            PwrzvError::InvalidValue { detail } => PwrzvError::InvalidValue {
+                detail: detail.clone(),
+            },
+        }
+    }
+}
+
 impl PwrzvError {
     /// Create unsupported platform error
     #[allow(dead_code)]
@@ -44,10 +220,45 @@ impl PwrzvError {
         }
     }
 
+    /// Create file read error
+    #[allow(dead_code)]
+    pub(crate) fn read_error(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        PwrzvError::ReadError {
+            path: path.into(),
+            metric: None,
+            source,
+        }
+    }
+
+    /// Create error for a command that could not be started
+    #[allow(dead_code)]
+    pub(crate) fn command_error(command: &str, source: io::Error) -> Self {
+        PwrzvError::CommandError {
+            command: command.to_string(),
+            metric: None,
+            detail: source.to_string(),
+            source: Some(source),
+        }
+    }
+
+    /// Create error for a command that did not succeed
+    #[allow(dead_code)]
+    pub(crate) fn command_failed(command: &str, detail: &str) -> Self {
+        PwrzvError::CommandError {
+            command: command.to_string(),
+            metric: None,
+            detail: detail.to_string(),
+            source: None,
+        }
+    }
+
     /// Create parsing error
     #[allow(dead_code)]
     pub(crate) fn parse_error(detail: &str) -> Self {
         PwrzvError::ParseError {
+            input: None,
+            metric: None,
+            line: None,
             detail: detail.to_string(),
         }
     }
@@ -57,6 +268,19 @@ impl PwrzvError {
     pub(crate) fn resource_access_error(resource: &str) -> Self {
         PwrzvError::ResourceAccessError {
             resource: resource.to_string(),
+            source: None,
+        }
+    }
+
+    /// Create resource access error caused by an I/O error
+    ///
+    /// The I/O error is the error's [`source`](std::error::Error::source), not
+    /// part of its message.
+    #[allow(dead_code)]
+    pub(crate) fn resource_io_error(resource: &str, source: io::Error) -> Self {
+        PwrzvError::ResourceAccessError {
+            resource: resource.to_string(),
+            source: Some(source),
         }
     }
 
@@ -68,11 +292,21 @@ impl PwrzvError {
         }
     }
 
-    /// Create collection error (alias for resource access error)
+    /// Create collection error
     #[allow(dead_code)]
     pub(crate) fn collection_error(detail: &str) -> Self {
-        PwrzvError::ResourceAccessError {
-            resource: detail.to_string(),
+        PwrzvError::CollectionError {
+            metric: None,
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Create timeout error
+    #[allow(dead_code)]
+    pub(crate) fn timed_out(operation: &str, timeout: Duration) -> Self {
+        PwrzvError::TimedOut {
+            operation: operation.to_string(),
+            timeout,
         }
     }
 
@@ -83,6 +317,156 @@ impl PwrzvError {
             detail: detail.to_string(),
         }
     }
+
+    /// Attach the ID of the metric being collected, keeping the variant
+    pub(crate) fn with_metric(mut self, id: &str) -> Self {
+        if let PwrzvError::ReadError { metric, .. }
+        | PwrzvError::CommandError { metric, .. }
+        | PwrzvError::ParseError { metric, .. }
+        | PwrzvError::CollectionError { metric, .. } = &mut self
+        {
+            *metric = Some(id.to_string());
+        }
+        self
+    }
+
+    /// Attach the line that could not be parsed to a parse error
+    pub(crate) fn with_line(mut self, offending: &str) -> Self {
+        if let PwrzvError::ParseError { line, .. } = &mut self {
+            *line = Some(offending.to_string());
+        }
+        self
+    }
+
+    /// Prefix the error detail with context such as a file name, keeping the variant
+    ///
+    /// A parse error without an input takes the context as its input.
+    pub(crate) fn context(self, context: &str) -> Self {
+        match self {
+            PwrzvError::ParseError {
+                input: None,
+                metric,
+                line,
+                detail,
+            } => PwrzvError::ParseError {
+                input: Some(context.to_string()),
+                metric,
+                line,
+                detail,
+            },
+            PwrzvError::ParseError {
+                input: Some(input),
+                metric,
+                line,
+                detail,
+            } => PwrzvError::ParseError {
+                input: Some(format!("{context}: {input}")),
+                metric,
+                line,
+                detail,
+            },
+            PwrzvError::ResourceAccessError { resource, source } => {
+                PwrzvError::ResourceAccessError {
+                    resource: format!("{context}: {resource}"),
+                    source,
+                }
+            }
+            PwrzvError::CollectionError { metric, detail } => PwrzvError::CollectionError {
+                metric,
+                detail: format!("{context}: {detail}"),
+            },
+            PwrzvError::CalculationError { detail } => PwrzvError::CalculationError {
+                detail: format!("{context}: {detail}"),
+            },
+            PwrzvError::InvalidValue { detail } => PwrzvError::InvalidValue {
+                detail: format!("{context}: {detail}"),
+            },
+            error => error,
+        }
+    }
+
+    /// Get the error message without its category prefix, for status lines
+    pub(crate) fn detail(&self) -> String {
+        match self {
+            PwrzvError::IoError(source) => source.to_string(),
+            PwrzvError::ReadError { path, source, .. } => format!("{}: {source}", path.display()),
+            PwrzvError::CommandError {
+                command, detail, ..
+            } => format!("{command}: {detail}"),
+            PwrzvError::ParseError {
+                input,
+                line,
+                detail,
+                ..
+            } => match input {
+                Some(input) => format!("{input}: {detail}{}", offending_line(line)),
+                None => format!("{detail}{}", offending_line(line)),
+            },
+            PwrzvError::ResourceAccessError { resource, source } => match source {
+                Some(source) => format!("{resource}: {source}"),
+                None => resource.clone(),
+            },
+            PwrzvError::CollectionError { detail, .. }
+            | PwrzvError::CalculationError { detail }
+            | PwrzvError::InvalidValue { detail } => detail.clone(),
+            error => error.to_string(),
+        }
+    }
+
+    /// Get the ID of the metric the error occurred for, if known
+    pub fn metric(&self) -> Option<&str> {
+        match self {
+            PwrzvError::ReadError { metric, .. }
+            | PwrzvError::CommandError { metric, .. }
+            | PwrzvError::ParseError { metric, .. }
+            | PwrzvError::CollectionError { metric, .. } => metric.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Check whether the operation may succeed if retried
+    ///
+    /// Timeouts, interrupted or refused I/O, failed commands and sources that
+    /// held no usable value yet are transient. Missing files, permission
+    /// problems, unparseable data, invalid configuration and unsupported
+    /// platforms are not: retrying will fail the same way.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use pwrzv::blocking::get_power_reserve_level;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let level = loop {
+    ///         match get_power_reserve_level() {
+    ///             Err(e) if e.is_transient() => continue,
+    ///             result => break result?,
+    ///         }
+    ///     };
+    ///     println!("Power Reserve Level: {level:.2}");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn is_transient(&self) -> bool {
+        match self {
+            PwrzvError::IoError(source) | PwrzvError::ReadError { source, .. } => {
+                is_transient_io(source)
+            }
+            PwrzvError::CommandError { source, .. } => {
+                // A command that ran but failed may succeed next time; one that
+                // cannot be started (e.g. not installed) will not
+                source.as_ref().is_none_or(is_transient_io)
+            }
+            PwrzvError::ResourceAccessError { source, .. } => {
+                source.as_ref().is_some_and(is_transient_io)
+            }
+            PwrzvError::CollectionError { .. } | PwrzvError::TimedOut { .. } => true,
+            PwrzvError::UnsupportedPlatform { .. }
+            | PwrzvError::ParseError { .. }
+            | PwrzvError::CalculationError { .. }
+            | PwrzvError::InvalidValue { .. } => false,
+        }
+    }
 }
 
 /// Result type for the pwrzv library
@@ -136,7 +520,7 @@ mod tests {
     #[test]
     fn test_collection_error() {
         let error = PwrzvError::collection_error("unable to read CPU stats");
-        assert!(matches!(error, PwrzvError::ResourceAccessError { .. }));
+        assert!(matches!(error, PwrzvError::CollectionError { .. }));
 
         let error_str = error.to_string();
         assert!(error_str.contains("unable to read CPU stats"));
@@ -152,6 +536,99 @@ mod tests {
         assert!(error_str.contains("Invalid value"));
     }
 
+    #[test]
+    fn test_error_context() {
+        let error = PwrzvError::invalid_value("steepness must be positive").context("pwrzv.toml");
+        assert!(matches!(error, PwrzvError::InvalidValue { .. }));
+        assert_eq!(
+            error.to_string(),
+            "Invalid value: pwrzv.toml: steepness must be positive"
+        );
+    }
+
+    #[test]
+    fn test_read_error() {
+        let io_error = io::Error::new(io::ErrorKind::NotFound, "No such file or directory");
+        let error = PwrzvError::read_error("/proc/pressure/memory", io_error)
+            .with_metric("memory_pressure");
+        assert_eq!(error.metric(), Some("memory_pressure"));
+        assert_eq!(
+            error.to_string(),
+            "Failed to read /proc/pressure/memory for memory_pressure: No such file or directory"
+        );
+
+        let source = std::error::Error::source(&error).unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(source.kind(), io::ErrorKind::NotFound);
+        assert!(!error.is_transient());
+    }
+
+    #[test]
+    fn test_parse_error_context() {
+        let error = PwrzvError::parse_error("no load average")
+            .context("/proc/loadavg")
+            .with_metric("cpu_load")
+            .with_line("garbage");
+        let PwrzvError::ParseError {
+            input,
+            metric,
+            line,
+            ..
+        } = &error
+        else {
+            panic!("expected a parse error, got {error:?}");
+        };
+        assert_eq!(input.as_deref(), Some("/proc/loadavg"));
+        assert_eq!(metric.as_deref(), Some("cpu_load"));
+        assert_eq!(line.as_deref(), Some("garbage"));
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse /proc/loadavg for cpu_load: no load average in line \"garbage\""
+        );
+        assert_eq!(
+            error.detail(),
+            "/proc/loadavg: no load average in line \"garbage\""
+        );
+    }
+
+    #[test]
+    fn test_is_transient() {
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        let error = PwrzvError::resource_io_error("daemon", refused);
+        assert!(error.is_transient());
+        // The I/O error is the source, not repeated in the message
+        assert_eq!(
+            error.to_string(),
+            "Failed to access system resource: daemon"
+        );
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "refused"
+        );
+        assert_eq!(error.detail(), "daemon: refused");
+        let missing = io::Error::new(io::ErrorKind::NotFound, "not found");
+        assert!(!PwrzvError::command_error("iostat", missing).is_transient());
+        assert!(PwrzvError::command_failed("ps", "exited with 1").is_transient());
+        assert!(PwrzvError::timed_out("webhook", Duration::from_secs(10)).is_transient());
+        assert!(PwrzvError::collection_error("no traffic yet").is_transient());
+
+        assert!(!PwrzvError::parse_error("malformed").is_transient());
+        assert!(!PwrzvError::invalid_value("negative").is_transient());
+        assert!(!PwrzvError::unsupported_platform("windows").is_transient());
+        assert!(!PwrzvError::resource_access_error("unit not found").is_transient());
+    }
+
+    #[test]
+    fn test_error_clone() {
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        let error = PwrzvError::read_error("/proc/stat", refused).with_metric("cpu_usage");
+        let copy = error.clone();
+
+        assert_eq!(copy.to_string(), error.to_string());
+        assert_eq!(copy.metric(), Some("cpu_usage"));
+        assert!(copy.is_transient());
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_error = io::Error::new(io::ErrorKind::PermissionDenied, "Access denied");
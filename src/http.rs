@@ -0,0 +1,520 @@
+//! HTTP API module
+//!
+//! A small read-only REST API over a shared [`Sampler`], served with a minimal
+//! HTTP/1.1 implementation to keep the dependency footprint small.
+//!
+//! ## Endpoints
+//!
+//! - `GET /v1/reserve`: Current level and description
+//! - `GET /v1/reserve/details`: Full report, same JSON as `pwrzv --detailed json`
+//! - `GET /v1/history?since=<time>`: Buffered reports since an RFC 3339 timestamp or age (e.g. `15m`)
+//! - `GET /healthz`: `200` while samples are fresh, `503` otherwise
+//!
+//! Sample endpoints carry an `ETag` derived from the latest sample timestamp and
+//! answer `304 Not Modified` when it matches the request's `If-None-Match`.
+
+use crate::error::PwrzvResult;
+use crate::report::{LevelSample, PowerReserveReport};
+use crate::sampler::Sampler;
+use crate::timespec;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+/// Maximum accepted size of a request head
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+
+/// Time allowed for a client to send its request head
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Number of sampling intervals after which the latest sample is considered stale
+const STALE_INTERVALS: u32 = 3;
+
+/// Bind to `addr` and serve the HTTP API until the future is dropped
+///
+/// # Arguments
+///
+/// * `addr` - Address to bind, e.g. `127.0.0.1:9191`
+/// * `sampler` - Sampler providing the reports served to clients
+pub async fn serve(addr: impl ToSocketAddrs, sampler: Sampler) -> PwrzvResult<()> {
+    let listener = TcpListener::bind(addr).await?;
+    serve_listener(listener, sampler).await
+}
+
+/// Serve the HTTP API on an already bound listener
+pub async fn serve_listener(listener: TcpListener, sampler: Sampler) -> PwrzvResult<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let sampler = sampler.clone();
+        tokio::spawn(async move {
+            // A client disconnecting mid-response is not a server error
+            let _ = handle_connection(stream, sampler).await;
+        });
+    }
+}
+
+/// A parsed HTTP request head
+#[derive(Debug, Clone, PartialEq)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+/// An HTTP response ready to be written
+#[derive(Debug, Clone, PartialEq)]
+struct HttpResponse {
+    status: u16,
+    etag: Option<String>,
+    body: Option<String>,
+}
+
+impl HttpResponse {
+    fn json<T: serde::Serialize>(status: u16, body: &T) -> Self {
+        Self {
+            status,
+            etag: None,
+            body: Some(serde_json::to_string_pretty(body).unwrap_or_default()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    fn with_etag(mut self, etag: String) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+}
+
+/// Read one request, answer it and close the connection
+async fn handle_connection(mut stream: TcpStream, sampler: Sampler) -> std::io::Result<()> {
+    let head = match tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut stream)).await {
+        Ok(Ok(Some(head))) => head,
+        Ok(Ok(None)) | Err(_) => return Ok(()),
+        Ok(Err(e)) => return Err(e),
+    };
+
+    let (response, head_only) = match parse_request(&head) {
+        Some(request) => (route(&request, &sampler), request.method == "HEAD"),
+        None => (HttpResponse::error(400, "Malformed request"), false),
+    };
+    write_response(&mut stream, &response, head_only).await
+}
+
+/// Read bytes until the end of the request head, returning `None` on early EOF
+async fn read_request_head(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buffer = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            buffer.truncate(end);
+            return Ok(Some(String::from_utf8_lossy(&buffer).into_owned()));
+        }
+        if buffer.len() > MAX_REQUEST_HEAD {
+            return Ok(None);
+        }
+    }
+}
+
+/// Parse the request line and headers
+fn parse_request(head: &str) -> Option<HttpRequest> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    if !request_line.next()?.starts_with("HTTP/1.") {
+        return None;
+    }
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, parse_query(query)),
+        None => (target, HashMap::new()),
+    };
+
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    Some(HttpRequest {
+        method,
+        path: path.to_string(),
+        query,
+        headers,
+    })
+}
+
+/// Parse a URL query string into decoded key/value pairs
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (percent_decode(key), percent_decode(value)),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` (as space) in a query component
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        decoded.push(byte);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Dispatch a request to its endpoint
+fn route(request: &HttpRequest, sampler: &Sampler) -> HttpResponse {
+    if request.method != "GET" && request.method != "HEAD" {
+        return HttpResponse::error(405, "Only GET and HEAD are supported");
+    }
+
+    match request.path.as_str() {
+        "/healthz" => health(sampler),
+        "/v1/reserve" => with_latest(sampler, |report| {
+            HttpResponse::json(200, &LevelSample::from(report))
+        }),
+        "/v1/reserve/details" => with_latest(sampler, |report| HttpResponse::json(200, report)),
+        "/v1/history" => history(request, sampler),
+        _ => return HttpResponse::error(404, "Not found"),
+    }
+    .conditional(request)
+}
+
+/// Build a response from the latest sample, tagged with its timestamp
+fn with_latest(
+    sampler: &Sampler,
+    respond: impl FnOnce(&PowerReserveReport) -> HttpResponse,
+) -> HttpResponse {
+    match sampler.latest() {
+        Some(report) => respond(&report).with_etag(etag_for(&report)),
+        None => no_sample(sampler),
+    }
+}
+
+/// `GET /v1/history?since=`
+fn history(request: &HttpRequest, sampler: &Sampler) -> HttpResponse {
+    let since = match request.query.get("since") {
+        Some(value) => match timespec::parse_since(value, chrono::Utc::now()) {
+            Ok(since) => Some(since),
+            Err(e) => return HttpResponse::error(400, &e.to_string()),
+        },
+        None => None,
+    };
+
+    let reports: Vec<PowerReserveReport> = sampler
+        .history_since(since)
+        .iter()
+        .map(|report| report.as_ref().clone())
+        .collect();
+
+    let response = HttpResponse::json(200, &reports);
+    match sampler.latest() {
+        Some(latest) => response.with_etag(etag_for(&latest)),
+        None => response,
+    }
+}
+
+/// `GET /healthz`
+fn health(sampler: &Sampler) -> HttpResponse {
+    let Some(report) = sampler.latest() else {
+        return no_sample(sampler);
+    };
+
+    let age = (chrono::Utc::now() - report.timestamp)
+        .to_std()
+        .unwrap_or_default();
+    let max_age = sampler.interval() * STALE_INTERVALS;
+
+    if age > max_age {
+        HttpResponse::json(
+            503,
+            &serde_json::json!({
+                "status": "stale",
+                "last_sample": report.timestamp,
+                "error": sampler.last_error(),
+            }),
+        )
+    } else {
+        HttpResponse::json(
+            200,
+            &serde_json::json!({ "status": "ok", "last_sample": report.timestamp }),
+        )
+    }
+}
+
+/// Response used before the first successful sample
+fn no_sample(sampler: &Sampler) -> HttpResponse {
+    let message = match sampler.last_error() {
+        Some(e) => format!("Failed to collect metrics: {e}"),
+        None => "No sample collected yet".to_string(),
+    };
+    HttpResponse::error(503, &message)
+}
+
+/// Strong entity tag keyed on the sample timestamp
+fn etag_for(report: &PowerReserveReport) -> String {
+    format!("\"{}\"", report.timestamp.timestamp_micros())
+}
+
+impl HttpResponse {
+    /// Turn a successful response into `304 Not Modified` if `If-None-Match` matches
+    fn conditional(self, request: &HttpRequest) -> Self {
+        let (Some(etag), Some(if_none_match)) = (&self.etag, request.headers.get("if-none-match"))
+        else {
+            return self;
+        };
+        if self.status != 200 {
+            return self;
+        }
+
+        let matches = if_none_match.split(',').map(str::trim).any(|candidate| {
+            candidate == "*" || candidate.trim_start_matches("W/") == etag.as_str()
+        });
+
+        if matches {
+            Self {
+                status: 304,
+                etag: self.etag,
+                body: None,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+/// Get the reason phrase of a status code
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// Serialize a response head (and body unless `head_only`)
+fn encode_response(response: &HttpResponse, head_only: bool) -> String {
+    let body = response.body.as_deref().unwrap_or("");
+    let mut out = format!(
+        "HTTP/1.1 {} {}\r\nConnection: close\r\nCache-Control: no-cache\r\n",
+        response.status,
+        reason_phrase(response.status)
+    );
+    if let Some(etag) = &response.etag {
+        out.push_str(&format!("ETag: {etag}\r\n"));
+    }
+    if response.status == 405 {
+        out.push_str("Allow: GET, HEAD\r\n");
+    }
+    if response.body.is_some() {
+        out.push_str("Content-Type: application/json\r\n");
+        out.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    out.push_str("\r\n");
+    if !head_only {
+        out.push_str(body);
+    }
+    out
+}
+
+/// Write a response to the client
+async fn write_response(
+    stream: &mut TcpStream,
+    response: &HttpResponse,
+    head_only: bool,
+) -> std::io::Result<()> {
+    stream
+        .write_all(encode_response(response, head_only).as_bytes())
+        .await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Start a server on an ephemeral port backed by a predictable sampler
+    async fn start_server() -> (std::net::SocketAddr, Sampler, tokio::task::JoinHandle<()>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let sampler = Sampler::start_with(Duration::from_millis(20), 100, move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move { Ok(PowerReserveReport::new(n as f32, HashMap::new())) })
+        });
+        let mut rx = sampler.subscribe();
+        rx.recv().await.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server_sampler = sampler.clone();
+        let server = tokio::spawn(async move {
+            serve_listener(listener, server_sampler).await.unwrap();
+        });
+        (addr, sampler, server)
+    }
+
+    /// Send a raw request and return (status, headers, body)
+    async fn get(
+        addr: std::net::SocketAddr,
+        target: &str,
+        extra_headers: &str,
+    ) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {target} HTTP/1.1\r\nHost: test\r\n{extra_headers}\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, head.to_string(), body.to_string())
+    }
+
+    fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+        head.lines()
+            .filter_map(|line| line.split_once(": "))
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let head = "GET /v1/history?since=2025-01-01T03%3A00%3A00%2B00%3A00 HTTP/1.1\r\n\
+                    Host: localhost\r\nIf-None-Match: \"123\"";
+        let request = parse_request(head).unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/v1/history");
+        assert_eq!(request.query["since"], "2025-01-01T03:00:00+00:00");
+        assert_eq!(request.headers["if-none-match"], "\"123\"");
+
+        assert!(parse_request("garbage").is_none());
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%20b+c"), "a b c");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[tokio::test]
+    async fn test_reserve_endpoints() {
+        let (addr, _sampler, server) = start_server().await;
+
+        let (status, head, body) = get(addr, "/v1/reserve", "").await;
+        assert_eq!(status, 200);
+        assert!(header(&head, "ETag").is_some());
+        let sample: LevelSample = serde_json::from_str(&body).unwrap();
+        assert!(sample.power_reserve_level >= 1.0);
+
+        let (status, _, body) = get(addr, "/v1/reserve/details", "").await;
+        assert_eq!(status, 200);
+        let report: PowerReserveReport = serde_json::from_str(&body).unwrap();
+        assert_eq!(report.platform, std::env::consts::OS);
+
+        let (status, _, _) = get(addr, "/v1/unknown", "").await;
+        assert_eq!(status, 404);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_if_none_match() {
+        let (addr, sampler, server) = start_server().await;
+        // Pause until a fresh sample so the ETag stays stable for both requests
+        sampler.subscribe().recv().await.unwrap();
+
+        let (_, head, _) = get(addr, "/v1/reserve", "").await;
+        let etag = header(&head, "ETag").unwrap().to_string();
+
+        let (status, _, body) =
+            get(addr, "/v1/reserve", &format!("If-None-Match: {etag}\r\n")).await;
+        assert_eq!(status, 304);
+        assert!(body.is_empty());
+
+        let (status, _, _) = get(addr, "/v1/reserve", "If-None-Match: \"0\"\r\n").await;
+        assert_eq!(status, 200);
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_history_and_health() {
+        let (addr, sampler, server) = start_server().await;
+        sampler.subscribe().recv().await.unwrap();
+
+        let (status, _, body) = get(addr, "/v1/history", "").await;
+        assert_eq!(status, 200);
+        let reports: Vec<PowerReserveReport> = serde_json::from_str(&body).unwrap();
+        assert!(reports.len() >= 2);
+
+        let (status, _, body) = get(addr, "/v1/history?since=1h", "").await;
+        assert_eq!(status, 200);
+        let recent: Vec<PowerReserveReport> = serde_json::from_str(&body).unwrap();
+        assert!(!recent.is_empty());
+
+        let (status, _, _) = get(addr, "/v1/history?since=whenever", "").await;
+        assert_eq!(status, 400);
+
+        let (status, _, body) = get(addr, "/healthz", "").await;
+        assert_eq!(status, 200);
+        assert!(body.contains("\"ok\""));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_method_not_allowed() {
+        let (addr, _sampler, server) = start_server().await;
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"POST /v1/reserve HTTP/1.1\r\nHost: test\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 405"));
+        assert!(response.contains("Allow: GET, HEAD"));
+
+        server.abort();
+    }
+}
@@ -8,24 +8,33 @@
 //!
 //! ## Quick Start
 //!
+//! The async API needs the `async` feature, which the default `cli` feature
+//! enables; the [`blocking`] module works without it.
+//!
 //! ### Basic Usage
 //!
 //! ```rust
+//! # #[cfg(feature = "async")]
 //! use pwrzv::get_power_reserve_level_direct;
 //!
+//! # #[cfg(feature = "async")]
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let level = get_power_reserve_level_direct().await?;
 //!     println!("Power Reserve Level: {:.2}", level);
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "async"))]
+//! # fn main() {}
 //! ```
 //!
 //! ### Detailed Analysis
 //!
 //! ```rust
+//! # #[cfg(feature = "async")]
 //! use pwrzv::get_power_reserve_level_with_details_direct;
 //!
+//! # #[cfg(feature = "async")]
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let (level, details) = get_power_reserve_level_with_details_direct().await?;
@@ -37,6 +46,8 @@
 //!     }
 //!     Ok(())
 //! }
+//! # #[cfg(not(feature = "async"))]
+//! # fn main() {}
 //! ```
 //!
 //! ## Architecture
@@ -60,6 +71,9 @@
 //! export PWRZV_LINUX_CPU_USAGE_STEEPNESS=10.0
 //! ```
 //!
+//! The same parameters and the score aggregation can also be loaded from a
+//! TOML file named by `PWRZV_CONFIG`; see the [`config`] module.
+//!
 //! ## Supported Platforms
 //!
 //! - **Linux**: Uses `/proc` filesystem for direct system access
@@ -70,8 +84,10 @@
 //! All functions return `PwrzvResult<T>` which can be easily handled:
 //!
 //! ```rust
+//! # #[cfg(feature = "async")]
 //! use pwrzv::{get_power_reserve_level_direct, PwrzvError};
 //!
+//! # #[cfg(feature = "async")]
 //! #[tokio::main]
 //! async fn main() {
 //!     match get_power_reserve_level_direct().await {
@@ -82,29 +98,97 @@
 //!         Err(e) => eprintln!("Error: {}", e),
 //!     }
 //! }
+//! # #[cfg(not(feature = "async"))]
+//! # fn main() {}
 //! ```
 
-use std::collections::HashMap;
+use crate::collector::{Collection, CollectorSpec};
+use crate::config::PwrzvConfig;
+use crate::sigmoid::MetricSpec;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 
 #[cfg(target_os = "linux")]
 use crate::linux::calculator::LinuxProvider;
 #[cfg(target_os = "macos")]
 use crate::macos::calculator::MacProvider;
 
+#[cfg(all(feature = "async", feature = "yaml"))]
+pub mod alert;
+#[cfg(all(target_os = "linux", feature = "bench"))]
+#[doc(hidden)]
+pub mod bench;
+pub mod blocking;
+pub mod calibrate;
+pub mod collector;
+pub mod config;
+pub mod curve;
+#[cfg(all(unix, feature = "async"))]
+pub mod daemon;
 pub mod error;
+#[cfg(feature = "async")]
+pub mod http;
+#[cfg(feature = "async")]
+pub mod limiter;
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
+mod platform;
+pub mod plot;
+mod report;
+#[cfg(feature = "async")]
+mod sampler;
+#[cfg(feature = "tower")]
+pub mod shed;
 mod sigmoid;
+pub mod store;
+pub mod timespec;
+pub mod trace;
 
 pub use error::{PwrzvError, PwrzvResult};
+pub use report::{
+    CpuCountSource, EffectiveCpus, LevelSample, MetricReading, PowerReserveReport,
+    level_description,
+};
+#[cfg(feature = "async")]
+pub use sampler::{DEFAULT_SAMPLE_INTERVAL, LatestSample, Sampler, watch, watch_latest};
 
 trait PowerReserveMeterProvider {
-    async fn get_power_reserve_level(&self) -> PwrzvResult<f32>;
-    async fn get_power_reserve_level_with_details(
+    /// Collect raw metric values with the status of every collector
+    ///
+    /// Collectors whose metrics are all disabled are skipped.
+    fn collect(&self, config: &PwrzvConfig) -> Collection;
+    /// Score raw metric values with the given configuration
+    fn calculate_inputs(
         &self,
-    ) -> PwrzvResult<(f32, HashMap<String, f32>)>;
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>);
+
+    /// Collect a sample, failing if too many metrics are missing
+    fn collect_checked(&self, config: &PwrzvConfig) -> PwrzvResult<Collection> {
+        let collection = self.collect(config);
+        collection.check(&config.collection)?;
+        Ok(collection)
+    }
+
+    fn get_power_reserve_level(&self) -> PwrzvResult<f32> {
+        let (level, _) = self.get_power_reserve_level_with_details()?;
+        Ok(level)
+    }
+
+    fn get_power_reserve_level_with_details(&self) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+        let config = config::global();
+        let collection = self.collect_checked(&config)?;
+        Ok(self.calculate_inputs(&collection.inputs, &config))
+    }
+
+    /// Collect raw metric values before scoring, keyed by metric ID
+    fn collect_inputs(&self) -> PwrzvResult<BTreeMap<String, f32>> {
+        let config = config::global();
+        Ok(self.collect_checked(&config)?.inputs)
+    }
 }
 
 // ================================
@@ -141,38 +225,94 @@ impl Calculator {
     }
 
     /// Get current power reserve level
-    async fn get_power_reserve_level(&self) -> PwrzvResult<f32> {
+    fn get_power_reserve_level(&self) -> PwrzvResult<f32> {
         #[cfg(target_os = "linux")]
         {
             let Calculator::Linux(calc) = self;
-            return calc.get_power_reserve_level().await;
+            calc.get_power_reserve_level()
         }
         #[cfg(target_os = "macos")]
         {
             let Calculator::MacOS(calc) = self;
-            return calc.get_power_reserve_level().await;
+            calc.get_power_reserve_level()
         }
         #[cfg(not(any(target_os = "linux", target_os = "macos")))]
         unreachable!("Calculator should only be created on supported platforms")
     }
 
     /// Get current power reserve level with detailed information
-    async fn get_power_reserve_level_with_details(
-        &self,
-    ) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+    fn get_power_reserve_level_with_details(&self) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+        #[cfg(target_os = "linux")]
+        {
+            let Calculator::Linux(calc) = self;
+            calc.get_power_reserve_level_with_details()
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let Calculator::MacOS(calc) = self;
+            calc.get_power_reserve_level_with_details()
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        unreachable!("Calculator should only be created on supported platforms")
+    }
+
+    /// Collect a sample, failing if too many metrics are missing
+    fn collect_checked(&self, config: &PwrzvConfig) -> PwrzvResult<Collection> {
+        #[cfg(target_os = "linux")]
+        {
+            let Calculator::Linux(calc) = self;
+            calc.collect_checked(config)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let Calculator::MacOS(calc) = self;
+            calc.collect_checked(config)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            let _ = config;
+            unreachable!("Calculator should only be created on supported platforms")
+        }
+    }
+
+    /// Collect raw metric values before scoring
+    fn collect_inputs(&self) -> PwrzvResult<BTreeMap<String, f32>> {
         #[cfg(target_os = "linux")]
         {
             let Calculator::Linux(calc) = self;
-            return calc.get_power_reserve_level_with_details().await;
+            calc.collect_inputs()
         }
         #[cfg(target_os = "macos")]
         {
             let Calculator::MacOS(calc) = self;
-            return calc.get_power_reserve_level_with_details().await;
+            calc.collect_inputs()
         }
         #[cfg(not(any(target_os = "linux", target_os = "macos")))]
         unreachable!("Calculator should only be created on supported platforms")
     }
+
+    /// Score raw metric values with the given configuration
+    fn calculate_inputs(
+        &self,
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        #[cfg(target_os = "linux")]
+        {
+            let Calculator::Linux(calc) = self;
+            calc.calculate_inputs(inputs, config)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            let Calculator::MacOS(calc) = self;
+            calc.calculate_inputs(inputs, config)
+        }
+        #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+        {
+            let _ = (inputs, config);
+            unreachable!("Calculator should only be created on supported platforms")
+        }
+    }
 }
 
 /// Get the current platform name
@@ -184,6 +324,39 @@ pub fn get_platform_name() -> &'static str {
     std::env::consts::OS
 }
 
+/// Get the scored metrics of the current platform (empty if unsupported)
+pub(crate) fn platform_metrics() -> &'static [MetricSpec] {
+    platform::metrics_for(std::env::consts::OS).unwrap_or(&[])
+}
+
+/// Get the collectors of the current platform (empty if unsupported)
+pub(crate) fn platform_collectors() -> &'static [CollectorSpec] {
+    #[cfg(target_os = "linux")]
+    {
+        &crate::linux::metrics::COLLECTORS
+    }
+    #[cfg(target_os = "macos")]
+    {
+        &crate::macos::metrics::COLLECTORS
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        &[]
+    }
+}
+
+/// Get the scored metrics of systemd unit (cgroup) mode (empty if unsupported)
+pub(crate) fn unit_metrics() -> &'static [MetricSpec] {
+    #[cfg(target_os = "linux")]
+    {
+        &crate::linux::cgroup::calculator::METRICS
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        &[]
+    }
+}
+
 /// Get power reserve level directly without any intermediate storage
 ///
 /// This function collects system metrics in real-time and calculates
@@ -212,9 +385,9 @@ pub fn get_platform_name() -> &'static str {
 ///     Ok(())
 /// }
 /// ```
+#[cfg(feature = "async")]
 pub async fn get_power_reserve_level_direct() -> PwrzvResult<f32> {
-    let calculator = Calculator::new()?;
-    calculator.get_power_reserve_level().await
+    spawn_blocking(blocking::get_power_reserve_level).await
 }
 
 /// Get power reserve level with detailed metrics directly
@@ -245,10 +418,129 @@ pub async fn get_power_reserve_level_direct() -> PwrzvResult<f32> {
 ///     Ok(())
 /// }
 /// ```
+#[cfg(feature = "async")]
 pub async fn get_power_reserve_level_with_details_direct()
 -> PwrzvResult<(f32, HashMap<String, f32>)> {
-    let calculator = Calculator::new()?;
-    calculator.get_power_reserve_level_with_details().await
+    spawn_blocking(blocking::get_power_reserve_level_with_details).await
+}
+
+/// Get a detailed power reserve report
+///
+/// Like [`get_power_reserve_level_with_details_direct`], but returns a
+/// self-describing [`PowerReserveReport`] stamped with the platform and
+/// collection time, suitable for serialization.
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::get_power_reserve_report;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let report = get_power_reserve_report().await?;
+///     println!("{}", serde_json::to_string_pretty(&report)?);
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn get_power_reserve_report() -> PwrzvResult<PowerReserveReport> {
+    spawn_blocking(blocking::get_power_reserve_report).await
+}
+
+/// Run a blocking collection on the runtime's blocking thread pool
+///
+/// Collectors read files and wait for commands, which must not stall the
+/// async worker threads.
+#[cfg(feature = "async")]
+async fn spawn_blocking<T: Send + 'static>(
+    collect: impl FnOnce() -> PwrzvResult<T> + Send + 'static,
+) -> PwrzvResult<T> {
+    match tokio::task::spawn_blocking(collect).await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(PwrzvError::collection_error(&format!(
+            "collection task failed: {e}"
+        ))),
+    }
+}
+
+// ================================
+// systemd unit power reserve
+// ================================
+
+/// Power reserve of a single systemd unit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnitPowerReserve {
+    /// Unit name, e.g. `nginx.service`
+    pub unit: String,
+    /// Power reserve level (1.0-5.0) of the unit's control group
+    pub level: f32,
+    /// Pressure scores for each available cgroup metric
+    pub details: HashMap<String, f32>,
+}
+
+/// Get the power reserve level of a systemd unit with detailed metrics
+///
+/// The unit is resolved to its cgroup v2 directory (usually
+/// `/sys/fs/cgroup/system.slice/<unit>`) and scored from the controller files
+/// found there, so no D-Bus connection to systemd is required. A bare name
+/// such as `nginx` is treated as `nginx.service`.
+///
+/// Set `PWRZV_CGROUP_ROOT` to use a cgroup v2 hierarchy mounted elsewhere.
+///
+/// # Arguments
+///
+/// * `unit` - The unit name, e.g. `nginx.service`
+///
+/// # Returns
+///
+/// A tuple of (level, details) like [`get_power_reserve_level_with_details_direct`],
+/// or an error if the unit cannot be found or the platform is not Linux.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::get_unit_power_reserve_level_with_details;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let (level, details) = get_unit_power_reserve_level_with_details("nginx.service").await?;
+///     println!("nginx.service Power Reserve: {:.2}", level);
+///     for (metric, score) in details {
+///         println!("  {}: {:.3}", metric, score);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn get_unit_power_reserve_level_with_details(
+    unit: &str,
+) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+    let unit = unit.to_string();
+    spawn_blocking(move || blocking::get_unit_power_reserve_level_with_details(&unit)).await
+}
+
+/// Get the power reserve level of a systemd unit
+///
+/// See [`get_unit_power_reserve_level_with_details`] for how the unit is resolved.
+#[cfg(feature = "async")]
+pub async fn get_unit_power_reserve_level(unit: &str) -> PwrzvResult<f32> {
+    let (level, _) = get_unit_power_reserve_level_with_details(unit).await?;
+    Ok(level)
+}
+
+/// Get the power reserve of every running systemd service
+///
+/// Running services are discovered by walking `system.slice` (including nested
+/// slices) for populated `*.service` control groups.
+///
+/// # Returns
+///
+/// One entry per running unit, sorted by lowest reserve first so the most
+/// constrained services come first.
+#[cfg(feature = "async")]
+pub async fn get_running_units_power_reserve() -> PwrzvResult<Vec<UnitPowerReserve>> {
+    spawn_blocking(blocking::get_running_units_power_reserve).await
 }
 
 // Legacy API compatibility functions (deprecated, but kept for backward compatibility)
@@ -259,6 +551,7 @@ pub async fn get_power_reserve_level_with_details_direct()
 ///
 /// Use `get_power_reserve_level_direct()` instead. This function is kept for
 /// backward compatibility but may be removed in future versions.
+#[cfg(feature = "async")]
 pub async fn get_power_reserve_level() -> PwrzvResult<f32> {
     get_power_reserve_level_direct().await
 }
@@ -269,6 +562,7 @@ pub async fn get_power_reserve_level() -> PwrzvResult<f32> {
 ///
 /// Use `get_power_reserve_level_with_details_direct()` instead. This function is kept for
 /// backward compatibility but may be removed in future versions.
+#[cfg(feature = "async")]
 pub async fn get_power_reserve_level_with_details() -> PwrzvResult<(f32, HashMap<String, f32>)> {
     get_power_reserve_level_with_details_direct().await
 }
@@ -329,6 +623,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_get_power_reserve_level_direct() {
         // Should return a valid level
@@ -345,6 +640,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_get_power_reserve_level_with_details_direct() {
         // Should return valid level and details
@@ -369,6 +665,7 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "async")]
     #[tokio::test]
     async fn test_legacy_compatibility_functions() {
         // Test legacy functions still work
@@ -0,0 +1,366 @@
+//! Adaptive concurrency limiter module
+//!
+//! [`ReserveLimiter`] is an async semaphore whose permit count follows the
+//! host's power reserve: workers acquire a permit before taking a job, so they
+//! back off while the host is stressed and ramp up again while it is idle.
+//!
+//! The level is read from a shared [`Sampler`], the same pipeline behind
+//! [`get_power_reserve_level_direct`](crate::get_power_reserve_level_direct),
+//! so the limiter retunes once per sample instead of collecting per permit.
+
+use crate::error::PwrzvResult;
+use crate::sampler::{DEFAULT_SAMPLE_INTERVAL, Sampler, check_interval};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How the permit count follows the power reserve level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitPolicy {
+    /// Scale linearly from the minimum at level 1.0 to the maximum at level 5.0
+    Linear,
+    /// Additive increase, multiplicative decrease
+    ///
+    /// Every sample at or above `threshold` adds `increase` permits; every
+    /// sample below it multiplies the limit by `decrease`.
+    Aimd {
+        /// Level (1.0-5.0) below which the limit is cut
+        threshold: f32,
+        /// Permits added per healthy sample
+        increase: usize,
+        /// Factor (0.0-1.0) applied to the limit per stressed sample
+        decrease: f32,
+    },
+}
+
+impl Default for LimitPolicy {
+    fn default() -> Self {
+        LimitPolicy::Aimd {
+            threshold: 2.5,
+            increase: 1,
+            decrease: 0.5,
+        }
+    }
+}
+
+impl LimitPolicy {
+    /// Compute the next limit from a sampled level
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Sampled power reserve level (1.0-5.0)
+    /// * `current` - Current limit
+    /// * `min` - Lowest allowed limit
+    /// * `max` - Highest allowed limit
+    fn next_limit(&self, level: f32, current: usize, min: usize, max: usize) -> usize {
+        let limit = match *self {
+            LimitPolicy::Linear => {
+                let fraction = ((level - 1.0) / 4.0).clamp(0.0, 1.0);
+                min + ((max - min) as f32 * fraction).round() as usize
+            }
+            LimitPolicy::Aimd {
+                threshold,
+                increase,
+                decrease,
+            } => {
+                if level >= threshold {
+                    current.saturating_add(increase)
+                } else {
+                    (current as f32 * decrease.clamp(0.0, 1.0)) as usize
+                }
+            }
+        };
+        limit.clamp(min, max)
+    }
+}
+
+/// Permit bookkeeping guarded by a mutex
+#[derive(Debug)]
+struct LimiterState {
+    /// Current permit count
+    limit: usize,
+    /// Permits to retire as they are released, after a shrink found them in use
+    debt: usize,
+}
+
+/// State shared between limiter handles, permits and the retuning task
+#[derive(Debug)]
+struct LimiterShared {
+    semaphore: Arc<Semaphore>,
+    state: Mutex<LimiterState>,
+    policy: LimitPolicy,
+    min: usize,
+    max: usize,
+}
+
+impl LimiterShared {
+    /// Retune the limit for a newly sampled level
+    fn adjust(&self, level: f32) {
+        let mut state = self.state.lock().unwrap();
+        let target = self
+            .policy
+            .next_limit(level, state.limit, self.min, self.max);
+
+        if target > state.limit {
+            let grow = target - state.limit;
+            let forgiven = grow.min(state.debt);
+            state.debt -= forgiven;
+            self.semaphore.add_permits(grow - forgiven);
+        } else if target < state.limit {
+            let shrink = state.limit - target;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            state.debt += shrink - forgotten;
+        }
+        state.limit = target;
+    }
+
+    /// Hand a released permit back, or retire it if the limit has shrunk
+    fn release(&self, permit: OwnedSemaphorePermit) {
+        let mut state = self.state.lock().unwrap();
+        if state.debt > 0 {
+            state.debt -= 1;
+            permit.forget();
+        }
+    }
+}
+
+/// Async semaphore whose permit count scales with the power reserve
+///
+/// Cloning the handle is cheap and all clones share the same permits. The
+/// limit starts at the minimum and is retuned on every sample until the last
+/// handle is dropped.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::limiter::{LimitPolicy, ReserveLimiter};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let limiter = ReserveLimiter::new(1, 16, LimitPolicy::Linear);
+///     loop {
+///         let permit = limiter.acquire().await;
+///         tokio::spawn(async move {
+///             // ... take and run a job ...
+///             drop(permit);
+///         });
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReserveLimiter {
+    shared: Arc<LimiterShared>,
+}
+
+impl ReserveLimiter {
+    /// Create a limiter fed by the shared sampler
+    ///
+    /// Samples every [`DEFAULT_SAMPLE_INTERVAL`].
+    /// Must be called from within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - Permits available however stressed the host is (at least 1)
+    /// * `max` - Permits available when the host is idle
+    /// * `policy` - How the limit follows the level
+    pub fn new(min: usize, max: usize, policy: LimitPolicy) -> Self {
+        Self::with_sampler(min, max, policy, Sampler::shared(DEFAULT_SAMPLE_INTERVAL))
+    }
+
+    /// Create a limiter fed by the shared sampler taking a sample every `interval`
+    ///
+    /// # Errors
+    ///
+    /// Returns an invalid value error if `interval` is zero.
+    pub fn with_interval(
+        min: usize,
+        max: usize,
+        policy: LimitPolicy,
+        interval: Duration,
+    ) -> PwrzvResult<Self> {
+        check_interval(interval)?;
+        Ok(Self::with_sampler(
+            min,
+            max,
+            policy,
+            Sampler::shared(interval),
+        ))
+    }
+
+    /// Create a limiter fed by an existing sampler
+    pub fn with_sampler(min: usize, max: usize, policy: LimitPolicy, sampler: Sampler) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        let shared = Arc::new(LimiterShared {
+            semaphore: Arc::new(Semaphore::new(min)),
+            state: Mutex::new(LimiterState {
+                limit: min,
+                debt: 0,
+            }),
+            policy,
+            min,
+            max,
+        });
+
+        tokio::spawn(Self::run(Arc::downgrade(&shared), sampler));
+        Self { shared }
+    }
+
+    /// Retuning loop; exits once every handle has been dropped
+    async fn run(shared: std::sync::Weak<LimiterShared>, sampler: Sampler) {
+        let mut samples = sampler.watch();
+        loop {
+            let level = match &*samples.borrow_and_update() {
+                Some(Ok(report)) => Some(report.power_reserve_level),
+                // Keep the current limit while samples fail
+                _ => None,
+            };
+            let Some(limiter) = shared.upgrade() else {
+                return;
+            };
+            if let Some(level) = level {
+                limiter.adjust(level);
+            }
+            drop(limiter);
+
+            if samples.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Wait for a permit
+    pub async fn acquire(&self) -> ReservePermit {
+        let permit = Arc::clone(&self.shared.semaphore)
+            .acquire_owned()
+            .await
+            .expect("limiter semaphore is never closed");
+        self.permit(permit)
+    }
+
+    /// Take a permit if one is available right now
+    pub fn try_acquire(&self) -> Option<ReservePermit> {
+        let permit = Arc::clone(&self.shared.semaphore)
+            .try_acquire_owned()
+            .ok()?;
+        Some(self.permit(permit))
+    }
+
+    /// Get the current permit count
+    pub fn limit(&self) -> usize {
+        self.shared.state.lock().unwrap().limit
+    }
+
+    /// Get the number of permits that can be acquired right now
+    pub fn available(&self) -> usize {
+        self.shared.semaphore.available_permits()
+    }
+
+    fn permit(&self, permit: OwnedSemaphorePermit) -> ReservePermit {
+        ReservePermit {
+            permit: Some(permit),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// Permit from a [`ReserveLimiter`], released when dropped
+#[derive(Debug)]
+pub struct ReservePermit {
+    permit: Option<OwnedSemaphorePermit>,
+    shared: Arc<LimiterShared>,
+}
+
+impl Drop for ReservePermit {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            self.shared.release(permit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PwrzvResult;
+    use crate::report::PowerReserveReport;
+    use std::collections::HashMap;
+
+    /// Limiter whose sampler never produces a sample, so tests drive `adjust`
+    fn limiter(min: usize, max: usize, policy: LimitPolicy) -> (ReserveLimiter, Sampler) {
+        let sampler = Sampler::start_with(Duration::from_secs(3600), 1, || {
+            Box::pin(std::future::pending::<PwrzvResult<PowerReserveReport>>())
+        });
+        let limiter = ReserveLimiter::with_sampler(min, max, policy, sampler.clone());
+        (limiter, sampler)
+    }
+
+    #[test]
+    fn test_linear_limit() {
+        let policy = LimitPolicy::Linear;
+        assert_eq!(policy.next_limit(1.0, 5, 2, 10), 2);
+        assert_eq!(policy.next_limit(3.0, 5, 2, 10), 6);
+        assert_eq!(policy.next_limit(5.0, 5, 2, 10), 10);
+        assert_eq!(policy.next_limit(0.0, 5, 2, 10), 2);
+    }
+
+    #[test]
+    fn test_aimd_limit() {
+        let policy = LimitPolicy::default();
+        assert_eq!(policy.next_limit(4.0, 5, 2, 10), 6);
+        assert_eq!(policy.next_limit(4.0, 10, 2, 10), 10);
+        assert_eq!(policy.next_limit(2.0, 8, 2, 10), 4);
+        assert_eq!(policy.next_limit(2.0, 3, 2, 10), 2);
+    }
+
+    #[tokio::test]
+    async fn test_limiter_follows_level() {
+        let (limiter, _sampler) = limiter(1, 8, LimitPolicy::Linear);
+        assert_eq!(limiter.limit(), 1);
+
+        limiter.shared.adjust(5.0);
+        assert_eq!(limiter.limit(), 8);
+        assert_eq!(limiter.available(), 8);
+
+        limiter.shared.adjust(1.0);
+        assert_eq!(limiter.limit(), 1);
+        assert_eq!(limiter.available(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_limiter_retires_permits_in_use() {
+        let (limiter, _sampler) = limiter(1, 4, LimitPolicy::Linear);
+        limiter.shared.adjust(5.0);
+        let mut permits: Vec<_> = (0..4).map(|_| limiter.try_acquire().unwrap()).collect();
+        assert!(limiter.try_acquire().is_none());
+
+        // Shrinking while every permit is out retires them as they come back
+        limiter.shared.adjust(1.0);
+        permits.truncate(1);
+        assert_eq!(limiter.available(), 0);
+        permits.clear();
+        assert_eq!(limiter.available(), 1);
+
+        // Growing again first cancels outstanding debt
+        let permit = limiter.acquire().await;
+        limiter.shared.adjust(5.0);
+        drop(permit);
+        assert_eq!(limiter.available(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_limiter_samples() {
+        let sampler = Sampler::start_with(Duration::from_millis(5), 1, || {
+            Box::pin(async { Ok(PowerReserveReport::new(5.0, HashMap::new())) })
+        });
+        let limiter = ReserveLimiter::with_sampler(1, 3, LimitPolicy::default(), sampler);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while limiter.limit() < 3 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("limit should ramp up on healthy samples");
+    }
+}
@@ -1,57 +1,17 @@
 use super::metrics::LinuxSystemMetrics;
 use crate::PowerReserveMeterProvider;
-use crate::error::PwrzvResult;
-use crate::sigmoid::{SigmoidFn, get_sigmoid_config};
-use std::collections::HashMap;
+use crate::collector::Collection;
+use crate::config::PwrzvConfig;
+use crate::platform::LINUX_METRICS as METRICS;
+use crate::report::score_inputs;
+use std::collections::{BTreeMap, HashMap};
 
 // ================================
 // The core parameters of the Linux power reserve calculator
 // ================================
 
-/// Get CPU usage configuration (env: PWRZV_LINUX_CPU_USAGE_MIDPOINT, PWRZV_LINUX_CPU_USAGE_STEEPNESS)
-fn get_cpu_usage_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_CPU_USAGE", 0.65, 8.0)
-}
-
-/// Get CPU I/O wait configuration (env: PWRZV_LINUX_CPU_IOWAIT_MIDPOINT, PWRZV_LINUX_CPU_IOWAIT_STEEPNESS)
-fn get_cpu_iowait_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_CPU_IOWAIT", 0.20, 20.0)
-}
-
-/// Get CPU load configuration (env: PWRZV_LINUX_CPU_LOAD_MIDPOINT, PWRZV_LINUX_CPU_LOAD_STEEPNESS)
-fn get_cpu_load_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_CPU_LOAD", 1.2, 5.0)
-}
-
-/// Get memory usage configuration (env: PWRZV_LINUX_MEMORY_USAGE_MIDPOINT, PWRZV_LINUX_MEMORY_USAGE_STEEPNESS)
-fn get_memory_usage_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_MEMORY_USAGE", 0.85, 18.0)
-}
-
-/// Get memory pressure configuration (env: PWRZV_LINUX_MEMORY_PRESSURE_MIDPOINT, PWRZV_LINUX_MEMORY_PRESSURE_STEEPNESS)
-fn get_memory_pressure_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_MEMORY_PRESSURE", 0.30, 12.0)
-}
-
-/// Get disk I/O configuration (env: PWRZV_LINUX_DISK_IO_MIDPOINT, PWRZV_LINUX_DISK_IO_STEEPNESS)
-fn get_disk_io_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_DISK_IO", 0.70, 10.0)
-}
-
-/// Get network dropped packets configuration (env: PWRZV_LINUX_NETWORK_DROPPED_MIDPOINT, PWRZV_LINUX_NETWORK_DROPPED_STEEPNESS)
-fn get_network_dropped_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_NETWORK_DROPPED", 0.02, 100.0)
-}
-
-/// Get file descriptor configuration (env: PWRZV_LINUX_FD_MIDPOINT, PWRZV_LINUX_FD_STEEPNESS)
-fn get_fd_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_FD", 0.90, 25.0)
-}
-
-/// Get process count configuration (env: PWRZV_LINUX_PROCESS_MIDPOINT, PWRZV_LINUX_PROCESS_STEEPNESS)
-fn get_process_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_LINUX_PROCESS", 0.80, 12.0)
-}
+/// Steal ratio from which a sample notes that the hypervisor takes a significant share
+const STEAL_NOTE_RATIO: f32 = 0.05;
 
 // ================================
 
@@ -60,25 +20,26 @@ fn get_process_config() -> SigmoidFn {
 pub(crate) struct LinuxProvider;
 
 impl PowerReserveMeterProvider for LinuxProvider {
-    async fn get_power_reserve_level(&self) -> PwrzvResult<f32> {
-        let metrics = LinuxSystemMetrics::collect_system_metrics().await?;
-
-        let (level, _) = Self::calculate(&metrics)?;
-        Ok(level)
+    fn collect(&self, config: &PwrzvConfig) -> Collection {
+        let enabled = |id: &str| config.metric_enabled("linux", id);
+        let (metrics, collectors) = LinuxSystemMetrics::collect(&config.collection, &enabled);
+        let mut collection = Collection::new(Self::inputs(&metrics), collectors);
+        collection.notes = Self::notes(&metrics);
+        collection.effective_cpus = metrics.effective_cpus;
+        collection
     }
 
-    async fn get_power_reserve_level_with_details(
+    fn calculate_inputs(
         &self,
-    ) -> PwrzvResult<(f32, HashMap<String, f32>)> {
-        let metrics = LinuxSystemMetrics::collect_system_metrics().await?;
-
-        let (level, details) = Self::calculate(&metrics)?;
-        Ok((level, details))
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        Self::calculate_with(inputs, config)
     }
 }
 
 impl LinuxProvider {
-    /// Calculate the power reserve level and details
+    /// Calculate the power reserve level and details with the process-wide configuration
     ///
     /// # Arguments
     ///
@@ -88,99 +49,78 @@ impl LinuxProvider {
     ///
     /// * `level` - The power reserve level as f32 (1.0-5.0)
     /// * `details` - The details of the power reserve level
-    fn calculate(metrics: &LinuxSystemMetrics) -> PwrzvResult<(f32, HashMap<String, f32>)> {
-        let mut details = HashMap::new();
-        let mut available_scores = Vec::new();
-
-        // Process each metric if available
-        if let Some(value) = metrics.cpu_usage_ratio {
-            let score = get_cpu_usage_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("CPU Usage: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.cpu_io_wait_ratio {
-            let score = get_cpu_iowait_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("CPU IO Wait: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.cpu_load_ratio {
-            let score = get_cpu_load_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("CPU Load: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.memory_usage_ratio {
-            let score = get_memory_usage_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Memory Usage: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.memory_pressure_ratio {
-            let score = get_memory_pressure_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Memory Pressure: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.disk_io_utilization {
-            let score = get_disk_io_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(
-                format!("Disk IO Utilization: {value:.3} (Score: {n:.3})"),
-                n,
-            );
-            available_scores.push(n);
-        }
+    #[cfg(test)]
+    fn calculate(
+        metrics: &LinuxSystemMetrics,
+    ) -> crate::error::PwrzvResult<(f32, HashMap<String, f32>)> {
+        Ok(Self::calculate_with(
+            &Self::inputs(metrics),
+            &crate::config::global(),
+        ))
+    }
 
-        if let Some(value) = metrics.network_dropped_packets_ratio {
-            let score = get_network_dropped_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(
-                format!("Network Dropped Packets: {value:.3} (Score: {n:.3})"),
-                n,
-            );
-            available_scores.push(n);
-        }
+    /// Calculate the power reserve level from raw inputs keyed by metric ID
+    ///
+    /// Metrics missing from `inputs` or disabled in `config` are skipped, and
+    /// scores are combined with the configured weights. If no metrics are
+    /// available, a default level of 3.0 is returned.
+    pub(crate) fn calculate_with(
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        score_inputs("linux", &METRICS, inputs, config)
+    }
 
-        if let Some(value) = metrics.fd_usage_ratio {
-            let score = get_fd_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("File Descriptors: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
+    /// Explain readings that the scores alone do not
+    pub(crate) fn notes(metrics: &LinuxSystemMetrics) -> Vec<String> {
+        let mut notes = Vec::new();
+        if let Some(steal) = metrics.cpu_steal_ratio
+            && steal >= STEAL_NOTE_RATIO
+        {
+            notes.push(format!(
+                "Since boot, the hypervisor has taken {:.1}% of CPU time (steal); on average this guest could use at most {:.1}%",
+                steal * 100.0,
+                (1.0 - steal) * 100.0
+            ));
         }
-
-        if let Some(value) = metrics.process_count_ratio {
-            let score = get_process_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Process Count: {value:.3} (Score: {n:.3})"), n);
-            available_scores.push(n);
+        if let Some(cpus) = &metrics.effective_cpus {
+            notes.push(cpus.note());
         }
-
-        // Find the minimum score from available metrics (bottleneck determines power reserve)
-        if let Some(&final_score) = available_scores
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-        {
-            return Ok((final_score, details));
+        if let Some(cores) = &metrics.cpu_cores {
+            notes.extend(cores.notes());
         }
-
-        // If no metrics are available, return a default level
-        Ok((3.0, details))
+        notes
     }
 
-    /// Convert sigmoid score to 5-point scale with decimal precision
-    /// [0, 1.0] -> [5.0, 0.0]
-    fn five_point_scale_with_decimal(score: f32) -> f32 {
-        let score = 5.0 * (1.0 - score);
-        // Retain 4 decimal places for precision
-        let factor = 10_000f32;
-        (score * factor).round() / factor
+    /// Get the available raw metric values keyed by metric ID
+    pub(crate) fn inputs(metrics: &LinuxSystemMetrics) -> BTreeMap<String, f32> {
+        [
+            ("cpu_usage", metrics.cpu_usage_ratio),
+            ("cpu_io_wait", metrics.cpu_io_wait_ratio),
+            ("cpu_steal", metrics.cpu_steal_ratio),
+            ("cpu_load", metrics.cpu_load_ratio),
+            ("memory_usage", metrics.memory_usage_ratio),
+            ("memory_pressure", metrics.memory_pressure_ratio),
+            ("disk_io_utilization", metrics.disk_io_utilization),
+            (
+                "network_dropped_packets",
+                metrics.network_dropped_packets_ratio,
+            ),
+            ("file_descriptors", metrics.fd_usage_ratio),
+            ("process_count", metrics.process_count_ratio),
+            ("cpu_throttling", metrics.cpu_throttling_ratio),
+            ("thermal_load", metrics.thermal_load_ratio),
+            (
+                "cpu_core_saturation",
+                metrics
+                    .cpu_cores
+                    .as_ref()
+                    .and_then(|cores| Some(cores.hottest()?.1)),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| Some((id.to_string(), value?)))
+        .collect()
     }
 }
 
@@ -188,34 +128,23 @@ impl LinuxProvider {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_five_point_scale_with_decimal() {
-        // Test the five_point_scale_with_decimal function
-        // [0, 1] -> [5.0, 1.0] linear mapping with 4 decimal precision
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(0.0), 5.0000); // 5 * (1 - 0) = 5
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(0.2), 4.0000); // 5 * (1 - 0.2) = 4 
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(0.4), 3.0000); // 5 * (1 - 0.4) = 3
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(0.6), 2.0000); // 5 * (1 - 0.6) = 2
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(0.8), 1.0000); // 5 * (1 - 0.8) = 1
-        assert_eq!(LinuxProvider::five_point_scale_with_decimal(1.0), 0.0000); // 5 * (1 - 1) = 0
-
-        // Test decimal precision
-        let score = LinuxProvider::five_point_scale_with_decimal(0.1234);
-        assert!((score - 4.3830).abs() < 0.0001); // 5 * (1 - 0.1234) = 4.383
-    }
-
     #[test]
     fn test_calculate_with_full_metrics() {
         let metrics = LinuxSystemMetrics {
             cpu_usage_ratio: Some(0.5),
             cpu_io_wait_ratio: Some(0.1),
+            cpu_steal_ratio: Some(0.05),
             cpu_load_ratio: Some(0.8),
+            effective_cpus: None,
             memory_usage_ratio: Some(0.7),
             memory_pressure_ratio: Some(0.2),
             disk_io_utilization: Some(0.6),
             network_dropped_packets_ratio: Some(0.01),
             fd_usage_ratio: Some(0.3),
             process_count_ratio: Some(0.4),
+            cpu_throttling_ratio: Some(0.1),
+            thermal_load_ratio: Some(0.5),
+            cpu_cores: None,
         };
 
         let result = LinuxProvider::calculate(&metrics);
@@ -238,18 +167,115 @@ mod tests {
         assert!((0.0..=5.0).contains(&level));
     }
 
+    #[test]
+    fn test_steal_note() {
+        let mut metrics = LinuxSystemMetrics {
+            cpu_usage_ratio: Some(0.5),
+            cpu_io_wait_ratio: None,
+            cpu_steal_ratio: Some(0.02),
+            cpu_load_ratio: None,
+            effective_cpus: None,
+            memory_usage_ratio: None,
+            memory_pressure_ratio: None,
+            disk_io_utilization: None,
+            network_dropped_packets_ratio: None,
+            fd_usage_ratio: None,
+            process_count_ratio: None,
+            cpu_throttling_ratio: None,
+            thermal_load_ratio: None,
+            cpu_cores: None,
+        };
+        assert!(LinuxProvider::notes(&metrics).is_empty());
+
+        metrics.cpu_steal_ratio = Some(0.25);
+        assert_eq!(
+            LinuxProvider::notes(&metrics),
+            [
+                "Since boot, the hypervisor has taken 25.0% of CPU time (steal); on average this guest could use at most 75.0%"
+            ]
+        );
+        let (level, details) = LinuxProvider::calculate(&metrics).unwrap();
+        assert!(
+            details.contains_key("CPU Steal: 0.250 (Score: 0.055)"),
+            "{details:?}"
+        );
+        assert!(level < 1.0);
+
+        // The fixture's hypervisor takes 10%
+        let mut config = PwrzvConfig::default();
+        config.collection.root =
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/linux").into();
+        let collection = LinuxProvider.collect(&config);
+        // Its cores are neither saturated nor far apart across nodes
+        assert_eq!(
+            collection.notes,
+            [
+                "Since boot, the hypervisor has taken 10.0% of CPU time (steal); on average this guest could use at most 90.0%",
+                "CPU load is normalized to 4 CPUs (cpuset.cpus.effective)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_metric_ids_match_labels() {
+        for spec in &METRICS {
+            assert_eq!(crate::report::metric_id(spec.label), spec.id);
+        }
+    }
+
+    #[test]
+    fn test_calculate_with_config() {
+        let inputs = BTreeMap::from([
+            ("cpu_usage".to_string(), 0.65),
+            ("memory_usage".to_string(), 0.1),
+        ]);
+
+        let (level, details) = LinuxProvider::calculate_with(&inputs, &PwrzvConfig::default());
+        assert_eq!(level, 2.5);
+        assert!(details.contains_key("CPU Usage: 0.650 (Score: 2.500)"));
+
+        let config = PwrzvConfig::from_toml(
+            "aggregation = \"mean\"\n[linux.cpu_usage]\nmidpoint = 0.9\nsteepness = 50",
+        )
+        .unwrap();
+        let (level, _) = LinuxProvider::calculate_with(&inputs, &config);
+        assert!(level > 4.5, "Mean of two relaxed metrics, got {level}");
+    }
+
+    #[test]
+    fn test_calculate_with_enabled_and_weight() {
+        let inputs = BTreeMap::from([
+            ("cpu_usage".to_string(), 0.65),
+            ("memory_usage".to_string(), 0.1),
+        ]);
+
+        let config = PwrzvConfig::from_toml("[linux.cpu_usage]\nweight = 0.5").unwrap();
+        let (level, _) = LinuxProvider::calculate_with(&inputs, &config);
+        assert_eq!(level, 3.75);
+
+        let config = PwrzvConfig::from_toml("[linux.cpu_usage]\nenabled = false").unwrap();
+        let (level, details) = LinuxProvider::calculate_with(&inputs, &config);
+        assert!(level > 4.5, "Only memory usage is scored, got {level}");
+        assert!(!details.keys().any(|key| key.starts_with("CPU Usage")));
+    }
+
     #[test]
     fn test_calculate_with_no_metrics() {
         let metrics = LinuxSystemMetrics {
             cpu_usage_ratio: None,
             cpu_io_wait_ratio: None,
+            cpu_steal_ratio: None,
             cpu_load_ratio: None,
+            effective_cpus: None,
             memory_usage_ratio: None,
             memory_pressure_ratio: None,
             disk_io_utilization: None,
             network_dropped_packets_ratio: None,
             fd_usage_ratio: None,
             process_count_ratio: None,
+            cpu_throttling_ratio: None,
+            thermal_load_ratio: None,
+            cpu_cores: None,
         };
 
         let result = LinuxProvider::calculate(&metrics);
@@ -269,13 +295,18 @@ mod tests {
         let metrics = LinuxSystemMetrics {
             cpu_usage_ratio: Some(0.3),
             cpu_io_wait_ratio: None,
+            cpu_steal_ratio: None,
             cpu_load_ratio: Some(0.5),
+            effective_cpus: None,
             memory_usage_ratio: None,
             memory_pressure_ratio: Some(0.1),
             disk_io_utilization: None,
             network_dropped_packets_ratio: None,
             fd_usage_ratio: None,
             process_count_ratio: None,
+            cpu_throttling_ratio: None,
+            thermal_load_ratio: None,
+            cpu_cores: None,
         };
 
         let result = LinuxProvider::calculate(&metrics);
@@ -302,15 +333,20 @@ mod tests {
     fn test_calculate_extreme_values() {
         // Test with high load (should result in low scores)
         let metrics = LinuxSystemMetrics {
-            cpu_usage_ratio: Some(0.95),              // Very high CPU usage
-            cpu_io_wait_ratio: Some(0.8),             // High I/O wait
-            cpu_load_ratio: Some(3.0),                // Very high load
-            memory_usage_ratio: Some(0.98),           // Very high memory usage
-            memory_pressure_ratio: Some(0.9),         // High memory pressure
-            disk_io_utilization: Some(0.99),          // Very high disk I/O
+            cpu_usage_ratio: Some(0.95),  // Very high CPU usage
+            cpu_io_wait_ratio: Some(0.8), // High I/O wait
+            cpu_steal_ratio: Some(0.4),   // Heavy steal
+            cpu_load_ratio: Some(3.0),    // Very high load
+            effective_cpus: None,
+            memory_usage_ratio: Some(0.98),   // Very high memory usage
+            memory_pressure_ratio: Some(0.9), // High memory pressure
+            disk_io_utilization: Some(0.99),  // Very high disk I/O
             network_dropped_packets_ratio: Some(0.1), // High dropped packets
-            fd_usage_ratio: Some(0.95),               // High FD usage
-            process_count_ratio: Some(0.9),           // High process count
+            fd_usage_ratio: Some(0.95),       // High FD usage
+            process_count_ratio: Some(0.9),   // High process count
+            cpu_throttling_ratio: Some(0.8),  // Heavily throttled
+            thermal_load_ratio: Some(0.97),   // Near critical temperature
+            cpu_cores: None,
         };
 
         let result = LinuxProvider::calculate(&metrics);
@@ -336,15 +372,20 @@ mod tests {
     fn test_calculate_low_values() {
         // Test with low system stress (should result in high scores)
         let metrics = LinuxSystemMetrics {
-            cpu_usage_ratio: Some(0.05),                // Very low CPU usage
-            cpu_io_wait_ratio: Some(0.01),              // Very low I/O wait
-            cpu_load_ratio: Some(0.1),                  // Very low load
-            memory_usage_ratio: Some(0.1),              // Low memory usage
-            memory_pressure_ratio: Some(0.01),          // Very low memory pressure
-            disk_io_utilization: Some(0.05),            // Very low disk I/O
+            cpu_usage_ratio: Some(0.05),   // Very low CPU usage
+            cpu_io_wait_ratio: Some(0.01), // Very low I/O wait
+            cpu_steal_ratio: Some(0.0),    // No steal
+            cpu_load_ratio: Some(0.1),     // Very low load
+            effective_cpus: None,
+            memory_usage_ratio: Some(0.1),     // Low memory usage
+            memory_pressure_ratio: Some(0.01), // Very low memory pressure
+            disk_io_utilization: Some(0.05),   // Very low disk I/O
             network_dropped_packets_ratio: Some(0.001), // Very low dropped packets
-            fd_usage_ratio: Some(0.1),                  // Low FD usage
-            process_count_ratio: Some(0.1),             // Low process count
+            fd_usage_ratio: Some(0.1),         // Low FD usage
+            process_count_ratio: Some(0.1),    // Low process count
+            cpu_throttling_ratio: Some(0.0),   // Full speed
+            thermal_load_ratio: Some(0.4),     // Cool
+            cpu_cores: None,
         };
 
         let result = LinuxProvider::calculate(&metrics);
@@ -0,0 +1,208 @@
+use super::metrics::CgroupMetrics;
+use crate::config::{self, PwrzvConfig};
+use crate::error::PwrzvResult;
+use crate::sigmoid::MetricSpec;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// ================================
+// The core parameters of the cgroup power reserve calculator
+// ================================
+
+/// Scored cgroup metrics and their default curves
+///
+/// Configuration file sections do not apply to units, but the file's
+/// aggregation does; each curve can be overridden with
+/// `<env_prefix>_MIDPOINT` / `<env_prefix>_STEEPNESS`.
+pub(crate) const METRICS: [MetricSpec; 6] = [
+    MetricSpec {
+        id: "cpu_pressure",
+        label: "CPU Pressure",
+        env_prefix: "PWRZV_LINUX_CGROUP_CPU_PRESSURE",
+        midpoint: 0.30,
+        steepness: 12.0,
+    },
+    MetricSpec {
+        id: "cpu_throttled",
+        label: "CPU Throttled",
+        env_prefix: "PWRZV_LINUX_CGROUP_CPU_THROTTLED",
+        midpoint: 0.25,
+        steepness: 15.0,
+    },
+    MetricSpec {
+        id: "memory_usage",
+        label: "Memory Usage",
+        env_prefix: "PWRZV_LINUX_CGROUP_MEMORY_USAGE",
+        midpoint: 0.85,
+        steepness: 18.0,
+    },
+    MetricSpec {
+        id: "memory_pressure",
+        label: "Memory Pressure",
+        env_prefix: "PWRZV_LINUX_CGROUP_MEMORY_PRESSURE",
+        midpoint: 0.30,
+        steepness: 12.0,
+    },
+    MetricSpec {
+        id: "io_pressure",
+        label: "IO Pressure",
+        env_prefix: "PWRZV_LINUX_CGROUP_IO_PRESSURE",
+        midpoint: 0.30,
+        steepness: 12.0,
+    },
+    MetricSpec {
+        id: "tasks",
+        label: "Tasks",
+        env_prefix: "PWRZV_LINUX_CGROUP_TASKS",
+        midpoint: 0.80,
+        steepness: 12.0,
+    },
+];
+
+// ================================
+
+/// cgroup v2 power reserve provider
+///
+/// Unlike [`LinuxProvider`], which measures the whole host, this provider
+/// measures a single control group such as a systemd unit.
+#[derive(Debug, Clone)]
+pub(crate) struct CgroupProvider {
+    /// Absolute path of the control group directory
+    path: PathBuf,
+}
+
+impl CgroupProvider {
+    /// Create a provider for the control group at `path`
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Get power reserve level with detailed information for the control group
+    pub(crate) fn get_power_reserve_level_with_details(
+        &self,
+    ) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+        let metrics = CgroupMetrics::collect(&self.path)?;
+        Self::calculate(&metrics)
+    }
+
+    /// Calculate the power reserve level and details with the process-wide configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics` - The control group metrics
+    ///
+    /// # Returns
+    ///
+    /// * `level` - The power reserve level as f32 (1.0-5.0)
+    /// * `details` - The details of the power reserve level
+    fn calculate(metrics: &CgroupMetrics) -> PwrzvResult<(f32, HashMap<String, f32>)> {
+        Ok(Self::calculate_with(metrics, &config::global()))
+    }
+
+    /// Calculate the power reserve level and details with a configuration
+    ///
+    /// Metrics that could not be read are skipped. If none are available, a
+    /// default level of 3.0 is returned.
+    fn calculate_with(
+        metrics: &CgroupMetrics,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        let mut details = HashMap::new();
+        let mut available_scores = Vec::new();
+
+        let values = [
+            metrics.cpu_pressure_ratio,
+            metrics.cpu_throttled_ratio,
+            metrics.memory_usage_ratio,
+            metrics.memory_pressure_ratio,
+            metrics.io_pressure_ratio,
+            metrics.pids_usage_ratio,
+        ];
+
+        for (spec, value) in METRICS.iter().zip(values) {
+            if let Some(value) = value {
+                let n = spec.curve("cgroup", config).score(value);
+                details.insert(format!("{}: {value:.3} (Score: {n:.3})", spec.label), n);
+                available_scores.push(n);
+            }
+        }
+
+        // Combine the scores (by default the bottleneck determines power reserve)
+        let level = config.aggregation.apply(&available_scores).unwrap_or(3.0);
+        (level, details)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_with_full_metrics() {
+        let metrics = CgroupMetrics {
+            cpu_pressure_ratio: Some(0.05),
+            cpu_throttled_ratio: Some(0.01),
+            memory_usage_ratio: Some(0.4),
+            memory_pressure_ratio: Some(0.0),
+            io_pressure_ratio: Some(0.02),
+            pids_usage_ratio: Some(0.1),
+        };
+
+        let (level, details) = CgroupProvider::calculate(&metrics).unwrap();
+        assert_eq!(details.len(), 6);
+        assert!(
+            level >= 4.0,
+            "Idle unit should have high reserve, got: {level}"
+        );
+    }
+
+    #[test]
+    fn test_calculate_bottleneck() {
+        let metrics = CgroupMetrics {
+            cpu_pressure_ratio: Some(0.05),
+            cpu_throttled_ratio: None,
+            memory_usage_ratio: Some(0.98),
+            memory_pressure_ratio: Some(0.6),
+            io_pressure_ratio: None,
+            pids_usage_ratio: None,
+        };
+
+        let (level, details) = CgroupProvider::calculate(&metrics).unwrap();
+        assert_eq!(details.len(), 3);
+        assert!(
+            level <= 1.0,
+            "Unit at its memory limit should be critical, got: {level}"
+        );
+
+        // The configured aggregation applies to units too
+        let config = PwrzvConfig::from_toml("aggregation = \"mean\"").unwrap();
+        let (mean, _) = CgroupProvider::calculate_with(&metrics, &config);
+        assert!(
+            mean > level,
+            "Mean {mean} should exceed the minimum {level}"
+        );
+    }
+
+    #[test]
+    fn test_metric_ids_match_labels() {
+        for spec in &METRICS {
+            assert_eq!(crate::report::metric_id(spec.label), spec.id);
+        }
+    }
+
+    #[test]
+    fn test_calculate_with_no_metrics() {
+        let metrics = CgroupMetrics {
+            cpu_pressure_ratio: None,
+            cpu_throttled_ratio: None,
+            memory_usage_ratio: None,
+            memory_pressure_ratio: None,
+            io_pressure_ratio: None,
+            pids_usage_ratio: None,
+        };
+
+        let (level, details) = CgroupProvider::calculate(&metrics).unwrap();
+        assert_eq!(level, 3.0);
+        assert!(details.is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::error::PwrzvResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// cgroup v2 metrics for a single control group
+///
+/// All metrics are optional to handle missing controllers gracefully.
+/// When a controller is not enabled for the group, its metrics will be `None`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CgroupMetrics {
+    /// CPU pressure ratio: PSI cpu.pressure "some" average (10s) / 100
+    /// Range: [0.0, 1.0] where higher values indicate tasks waiting for CPU
+    pub cpu_pressure_ratio: Option<f32>,
+
+    /// CPU throttling ratio: nr_throttled / nr_periods from cpu.stat
+    /// Range: [0.0, 1.0] where higher values indicate the CPU quota is exhausted
+    pub cpu_throttled_ratio: Option<f32>,
+
+    /// Memory usage ratio: (memory.current - inactive_file) / memory.max
+    /// Falls back to the host's MemTotal when the group has no memory limit
+    /// Range: [0.0, 1.0] where 1.0 means the group is at its memory limit
+    pub memory_usage_ratio: Option<f32>,
+
+    /// Memory pressure ratio: PSI memory.pressure "some" average (10s) / 100
+    /// Range: [0.0, 1.0] where higher values indicate memory stalls
+    pub memory_pressure_ratio: Option<f32>,
+
+    /// I/O pressure ratio: PSI io.pressure "some" average (10s) / 100
+    /// Range: [0.0, 1.0] where higher values indicate I/O stalls
+    pub io_pressure_ratio: Option<f32>,
+
+    /// Task count ratio: pids.current / pids.max
+    /// `None` when the group has no task limit
+    /// Range: [0.0, 1.0] where 1.0 means no more tasks can be forked
+    pub pids_usage_ratio: Option<f32>,
+}
+
+impl CgroupMetrics {
+    /// Collect all metrics of the control group at `path`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Absolute path of the control group directory
+    ///
+    /// # Returns
+    ///
+    /// A `CgroupMetrics` struct where each field may be `None` if the
+    /// corresponding controller file is missing or could not be parsed.
+    pub fn collect(path: &Path) -> PwrzvResult<Self> {
+        let read = |name: &str| fs::read_to_string(path.join(name)).ok();
+
+        let cpu_pressure = read("cpu.pressure");
+        let cpu_stat = read("cpu.stat");
+        let memory = (
+            read("memory.current"),
+            read("memory.max"),
+            read("memory.stat"),
+        );
+        let memory_pressure = read("memory.pressure");
+        let io_pressure = read("io.pressure");
+        let pids = (read("pids.current"), read("pids.max"));
+
+        let (memory_current, memory_max, memory_stat) = memory;
+        let memory_usage_ratio = match (memory_current, memory_max) {
+            (Some(current), Some(max)) => {
+                let limit = match Self::parse_limit(&max) {
+                    Some(limit) => Some(limit),
+                    // Unlimited group: compare against the host's physical memory
+                    None => fs::read_to_string("/proc/meminfo")
+                        .ok()
+                        .and_then(|content| Self::parse_mem_total(&content)),
+                };
+                Self::calculate_memory_usage(&current, limit, memory_stat.as_deref())
+            }
+            _ => None,
+        };
+
+        let (pids_current, pids_max) = pids;
+        let pids_usage_ratio = match (pids_current, pids_max) {
+            (Some(current), Some(max)) => Self::calculate_pids_usage(&current, &max),
+            _ => None,
+        };
+
+        Ok(CgroupMetrics {
+            cpu_pressure_ratio: cpu_pressure.as_deref().and_then(Self::parse_pressure),
+            cpu_throttled_ratio: cpu_stat.as_deref().and_then(Self::parse_cpu_throttled),
+            memory_usage_ratio,
+            memory_pressure_ratio: memory_pressure.as_deref().and_then(Self::parse_pressure),
+            io_pressure_ratio: io_pressure.as_deref().and_then(Self::parse_pressure),
+            pids_usage_ratio,
+        })
+    }
+
+    // Private parsing methods
+
+    /// Parse the "some avg10" value from a PSI pressure file
+    fn parse_pressure(content: &str) -> Option<f32> {
+        let line = content.lines().find(|line| line.starts_with("some "))?;
+        let avg10 = line.split("avg10=").nth(1)?.split_whitespace().next()?;
+        let avg10 = avg10.parse::<f32>().ok()?;
+        Some((avg10 / 100.0).clamp(0.0, 1.0))
+    }
+
+    /// Parse the throttled period ratio from cpu.stat content
+    ///
+    /// Returns `None` when no CPU quota is configured (nr_periods is absent or zero).
+    fn parse_cpu_throttled(content: &str) -> Option<f32> {
+        let mut nr_periods = None;
+        let mut nr_throttled = None;
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("nr_periods"), Some(value)) => nr_periods = value.parse::<u64>().ok(),
+                (Some("nr_throttled"), Some(value)) => nr_throttled = value.parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+
+        match (nr_periods, nr_throttled) {
+            (Some(periods), Some(throttled)) if periods > 0 => {
+                Some((throttled as f32 / periods as f32).clamp(0.0, 1.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a cgroup limit file, returning `None` for the literal "max"
+    fn parse_limit(content: &str) -> Option<u64> {
+        let value = content.trim();
+        if value == "max" {
+            return None;
+        }
+        value.parse::<u64>().ok().filter(|&limit| limit > 0)
+    }
+
+    /// Parse MemTotal (in bytes) from /proc/meminfo content
+    fn parse_mem_total(content: &str) -> Option<u64> {
+        let line = content.lines().find(|line| line.starts_with("MemTotal:"))?;
+        let kb = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+        Some(kb * 1024)
+    }
+
+    /// Calculate memory usage ratio, excluding reclaimable page cache
+    fn calculate_memory_usage(
+        current: &str,
+        limit: Option<u64>,
+        stat: Option<&str>,
+    ) -> Option<f32> {
+        let current = current.trim().parse::<u64>().ok()?;
+        let limit = limit?;
+
+        let inactive_file = stat
+            .and_then(|stat| {
+                stat.lines()
+                    .find(|line| line.starts_with("inactive_file "))
+                    .and_then(|line| line.split_whitespace().nth(1))
+                    .and_then(|value| value.parse::<u64>().ok())
+            })
+            .unwrap_or(0);
+
+        let working_set = current.saturating_sub(inactive_file);
+        Some((working_set as f32 / limit as f32).clamp(0.0, 1.0))
+    }
+
+    /// Calculate task count ratio, returning `None` for unlimited groups
+    fn calculate_pids_usage(current: &str, max: &str) -> Option<f32> {
+        let current = current.trim().parse::<u64>().ok()?;
+        let max = Self::parse_limit(max)?;
+        Some((current as f32 / max as f32).clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "pwrzv-cgroup-metrics-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_pressure() {
+        let content = "some avg10=12.50 avg60=5.00 avg300=1.00 total=123\n\
+                       full avg10=2.00 avg60=1.00 avg300=0.50 total=45\n";
+        let result = CgroupMetrics::parse_pressure(content);
+        assert!((result.unwrap() - 0.125).abs() < 0.001);
+
+        assert_eq!(CgroupMetrics::parse_pressure("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_throttled() {
+        let content = "usage_usec 1000\nnr_periods 200\nnr_throttled 50\nthrottled_usec 10\n";
+        let result = CgroupMetrics::parse_cpu_throttled(content);
+        assert!((result.unwrap() - 0.25).abs() < 0.001);
+
+        // No quota configured: only usage fields are present
+        let content = "usage_usec 1000\nuser_usec 600\nsystem_usec 400\n";
+        assert_eq!(CgroupMetrics::parse_cpu_throttled(content), None);
+    }
+
+    #[test]
+    fn test_calculate_memory_usage() {
+        let stat = "anon 100\ninactive_file 256\nactive_file 10\n";
+        let result = CgroupMetrics::calculate_memory_usage("768\n", Some(1024), Some(stat));
+        assert!((result.unwrap() - 0.5).abs() < 0.001);
+
+        assert_eq!(
+            CgroupMetrics::calculate_memory_usage("768", None, None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calculate_pids_usage() {
+        let result = CgroupMetrics::calculate_pids_usage("25\n", "100\n");
+        assert!((result.unwrap() - 0.25).abs() < 0.001);
+
+        assert_eq!(CgroupMetrics::calculate_pids_usage("25\n", "max\n"), None);
+    }
+
+    #[test]
+    fn test_collect_from_fixture() {
+        let dir = fixture_dir("collect");
+        fs::write(
+            dir.join("cpu.pressure"),
+            "some avg10=40.00 avg60=0 avg300=0 total=0\n",
+        )
+        .unwrap();
+        fs::write(dir.join("memory.current"), "512\n").unwrap();
+        fs::write(dir.join("memory.max"), "1024\n").unwrap();
+        fs::write(dir.join("pids.current"), "10\n").unwrap();
+        fs::write(dir.join("pids.max"), "max\n").unwrap();
+
+        let metrics = CgroupMetrics::collect(&dir).unwrap();
+        assert!((metrics.cpu_pressure_ratio.unwrap() - 0.4).abs() < 0.001);
+        assert!((metrics.memory_usage_ratio.unwrap() - 0.5).abs() < 0.001);
+        assert_eq!(metrics.cpu_throttled_ratio, None);
+        assert_eq!(metrics.memory_pressure_ratio, None);
+        assert_eq!(metrics.io_pressure_ratio, None);
+        assert_eq!(metrics.pids_usage_ratio, None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
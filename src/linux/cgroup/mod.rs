@@ -0,0 +1,12 @@
+//! cgroup v2 based power reserve calculation for systemd units
+//!
+//! systemd places every unit in its own control group, so a unit's resource
+//! headroom can be read directly from the unified hierarchy (usually
+//! `/sys/fs/cgroup/system.slice/<unit>`) without talking to systemd over D-Bus.
+
+pub mod calculator;
+pub mod metrics;
+pub mod unit;
+
+pub(crate) use calculator::CgroupProvider;
+pub(crate) use unit::{find_cgroup_mount, find_cgroup_root, list_running_units, resolve_unit};
@@ -0,0 +1,224 @@
+use crate::error::{PwrzvError, PwrzvResult};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the cgroup v2 mount point
+const CGROUP_ROOT_ENV: &str = "PWRZV_CGROUP_ROOT";
+
+/// Maximum directory depth searched when a unit is not directly under `system.slice`
+const MAX_SEARCH_DEPTH: usize = 6;
+
+/// Locate the cgroup v2 (unified) hierarchy
+///
+/// Honors `PWRZV_CGROUP_ROOT`, then checks the pure v2 mount at `/sys/fs/cgroup`
+/// and the hybrid layout at `/sys/fs/cgroup/unified`.
+pub(crate) fn find_cgroup_root() -> PwrzvResult<PathBuf> {
+    if let Ok(root) = env::var(CGROUP_ROOT_ENV) {
+        return Ok(PathBuf::from(root));
+    }
+
+    find_cgroup_mount(Path::new("/")).ok_or_else(|| {
+        PwrzvError::resource_access_error(
+            "cgroup v2 hierarchy not found (set PWRZV_CGROUP_ROOT to override)",
+        )
+    })
+}
+
+/// Locate the cgroup v2 hierarchy in a filesystem tree
+///
+/// Checks the pure v2 mount at `sys/fs/cgroup` and the hybrid layout at
+/// `sys/fs/cgroup/unified` under `root`.
+pub(crate) fn find_cgroup_mount(root: &Path) -> Option<PathBuf> {
+    ["sys/fs/cgroup", "sys/fs/cgroup/unified"]
+        .iter()
+        .map(|path| root.join(path))
+        .find(|path| path.join("cgroup.controllers").exists())
+}
+
+/// Normalize a unit name, defaulting to the `.service` suffix like `systemctl` does
+pub(crate) fn normalize_unit_name(name: &str) -> String {
+    const UNIT_SUFFIXES: [&str; 4] = [".service", ".scope", ".slice", ".socket"];
+    if UNIT_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+        name.to_string()
+    } else {
+        format!("{name}.service")
+    }
+}
+
+/// Resolve a systemd unit name to its control group directory
+///
+/// Looks in `system.slice` first, then searches the rest of the hierarchy so
+/// that units in nested slices (e.g. `system-getty.slice`) or user sessions are found.
+pub(crate) fn resolve_unit(root: &Path, name: &str) -> PwrzvResult<PathBuf> {
+    let unit = normalize_unit_name(name);
+
+    let direct = root.join("system.slice").join(&unit);
+    if direct.is_dir() {
+        return Ok(direct);
+    }
+
+    find_dir_named(root, &unit, MAX_SEARCH_DEPTH).ok_or_else(|| {
+        PwrzvError::resource_access_error(&format!(
+            "systemd unit '{unit}' not found under {}",
+            root.display()
+        ))
+    })
+}
+
+/// List running service units below `system.slice`
+///
+/// A unit is considered running when its cgroup is populated (has live processes).
+///
+/// # Returns
+///
+/// A vector of `(unit_name, cgroup_path)` pairs sorted by unit name.
+pub(crate) fn list_running_units(root: &Path) -> PwrzvResult<Vec<(String, PathBuf)>> {
+    let system_slice = root.join("system.slice");
+    if !system_slice.is_dir() {
+        return Err(PwrzvError::resource_access_error(&format!(
+            "{} does not exist",
+            system_slice.display()
+        )));
+    }
+
+    let mut units = Vec::new();
+    collect_services(&system_slice, MAX_SEARCH_DEPTH, &mut units);
+    units.sort();
+    Ok(units)
+}
+
+/// Check the `populated` flag of a cgroup's `cgroup.events` file
+fn is_populated(path: &Path) -> bool {
+    fs::read_to_string(path.join("cgroup.events"))
+        .map(|content| content.lines().any(|line| line.trim() == "populated 1"))
+        .unwrap_or(false)
+}
+
+/// Recursively collect populated `*.service` groups, descending into nested slices
+fn collect_services(dir: &Path, depth: usize, units: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name.ends_with(".service") {
+            if is_populated(&path) {
+                units.push((name, path));
+            }
+        } else if name.ends_with(".slice") && depth > 0 {
+            collect_services(&path, depth - 1, units);
+        }
+    }
+}
+
+/// Breadth-first search for a directory named `name` below `dir`
+fn find_dir_named(dir: &Path, name: &str, max_depth: usize) -> Option<PathBuf> {
+    let mut level = vec![dir.to_path_buf()];
+
+    for _ in 0..=max_depth {
+        let mut next = Vec::new();
+        for current in level {
+            let Ok(entries) = fs::read_dir(&current) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if !path.is_dir() {
+                    continue;
+                }
+                if entry.file_name() == name {
+                    return Some(path);
+                }
+                next.push(path);
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        level = next;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_root(name: &str) -> PathBuf {
+        let root = env::temp_dir().join(format!("pwrzv-cgroup-unit-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn make_unit(dir: &Path, populated: bool) {
+        fs::create_dir_all(dir).unwrap();
+        let flag = if populated { 1 } else { 0 };
+        fs::write(
+            dir.join("cgroup.events"),
+            format!("populated {flag}\nfrozen 0\n"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_normalize_unit_name() {
+        assert_eq!(normalize_unit_name("nginx"), "nginx.service");
+        assert_eq!(normalize_unit_name("nginx.service"), "nginx.service");
+        assert_eq!(normalize_unit_name("session-1.scope"), "session-1.scope");
+    }
+
+    #[test]
+    fn test_resolve_unit() {
+        let root = fixture_root("resolve");
+        make_unit(&root.join("system.slice/nginx.service"), true);
+        make_unit(
+            &root.join("system.slice/system-getty.slice/getty@tty1.service"),
+            true,
+        );
+
+        assert_eq!(
+            resolve_unit(&root, "nginx").unwrap(),
+            root.join("system.slice/nginx.service")
+        );
+        assert_eq!(
+            resolve_unit(&root, "getty@tty1.service").unwrap(),
+            root.join("system.slice/system-getty.slice/getty@tty1.service")
+        );
+        assert!(resolve_unit(&root, "missing.service").is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_list_running_units() {
+        let root = fixture_root("list");
+        make_unit(&root.join("system.slice/nginx.service"), true);
+        make_unit(&root.join("system.slice/stopped.service"), false);
+        make_unit(
+            &root.join("system.slice/system-getty.slice/getty@tty1.service"),
+            true,
+        );
+
+        let units = list_running_units(&root).unwrap();
+        let names: Vec<&str> = units.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["getty@tty1.service", "nginx.service"]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_list_running_units_without_system_slice() {
+        let root = fixture_root("empty");
+        assert!(list_running_units(&root).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
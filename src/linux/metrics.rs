@@ -1,7 +1,41 @@
-use crate::error::PwrzvResult;
+use crate::collector::{self, CollectorReport, CollectorSpec, Plan};
+use crate::config::CollectionConfig;
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::linux::cgroup;
+use crate::linux::topology::{self, CoreAnalysis};
+use crate::report::{CpuCountSource, EffectiveCpus};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Shortest interval disk activity is measured over
+///
+/// Samples closer together are measured against the same earlier snapshot.
+const DISK_MIN_INTERVAL_MS: u64 = 100;
+
+/// Latest `/proc/diskstats` snapshot and the root it was read under
+///
+/// Shared by every sample of the process: disk activity is measured since the
+/// latest sample of any sampler, not of the one taking the sample.
+static DISK_SNAPSHOT: Mutex<Option<(PathBuf, DiskSnapshot)>> = Mutex::new(None);
+
+/// Shortest interval, in clock ticks of one core, per-core usage is measured over
+///
+/// Samples closer together are measured against the same earlier snapshot.
+const CORE_MIN_INTERVAL_TICKS: u64 = 10;
+
+/// Time between the two `/proc/stat` reads of the first per-core sample
+///
+/// Without an earlier snapshot, per-core usage would be the average since boot.
+const CORE_FIRST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Latest per-core `/proc/stat` snapshot and the root it was read under
+///
+/// Shared by every sample of the process, like [`DISK_SNAPSHOT`].
+static CORE_SNAPSHOT: Mutex<Option<(PathBuf, BTreeMap<usize, CpuStat>)>> = Mutex::new(None);
 
 /// Network statistics structure used by both platforms
 #[derive(Debug, Clone)]
@@ -22,18 +56,26 @@ pub(crate) struct NetworkStats {
 /// When a metric cannot be collected, it will be `None` rather than a fallback value.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct LinuxSystemMetrics {
-    /// CPU usage ratio: (user + nice + system) / total_time
-    /// Range: [0.0, 1.0] where 1.0 means CPU is fully utilized
+    /// CPU usage ratio: (total_time - idle) / total_time
+    /// Range: [0.0, 1.0] where 1.0 means CPU is fully utilized. Time stolen by
+    /// the hypervisor counts as used, since the guest cannot use it.
     pub cpu_usage_ratio: Option<f32>,
 
     /// CPU I/O wait ratio: iowait / total_time
     /// Range: [0.0, 1.0] where higher values indicate I/O bottlenecks
     pub cpu_io_wait_ratio: Option<f32>,
 
-    /// CPU load ratio: 1-minute load average / CPU core count
+    /// CPU steal ratio since boot: steal / total_time
+    /// Range: [0.0, 1.0] where higher values mean the hypervisor runs other guests
+    pub cpu_steal_ratio: Option<f32>,
+
+    /// CPU load ratio: 1-minute load average / effective CPU count
     /// Range: [0.0, +∞] where > 1.0 indicates task queuing
     pub cpu_load_ratio: Option<f32>,
 
+    /// CPUs the load is normalized to and where that count comes from
+    pub effective_cpus: Option<EffectiveCpus>,
+
     /// Memory usage ratio: (total - available) / total
     /// Range: [0.0, 1.0] where 1.0 means memory is fully utilized
     pub memory_usage_ratio: Option<f32>,
@@ -57,10 +99,23 @@ pub struct LinuxSystemMetrics {
     /// Process count ratio: current_processes / typical_limit
     /// Range: [0.0, +∞] where > 1.0 indicates high process count
     pub process_count_ratio: Option<f32>,
+
+    /// CPU throttling ratio: 1 - least capped CPU's allowed frequency / its maximum
+    /// Range: [0.0, 1.0] where higher values indicate a thermally or power capped CPU
+    pub cpu_throttling_ratio: Option<f32>,
+
+    /// Thermal load ratio: hottest zone's temperature / its critical trip point
+    /// Range: [0.0, 1.0] where 1.0 means a zone has reached its critical temperature
+    pub thermal_load_ratio: Option<f32>,
+
+    /// Per-core and NUMA node CPU usage
+    ///
+    /// Its hottest core's usage is scored as `cpu_core_saturation`.
+    pub cpu_cores: Option<CoreAnalysis>,
 }
 
 #[derive(Debug, Clone)]
-struct CpuStat {
+pub(crate) struct CpuStat {
     user: u64,
     nice: u64,
     system: u64,
@@ -68,13 +123,29 @@ struct CpuStat {
     iowait: u64,
     irq: u64,
     softirq: u64,
+    steal: u64,
 }
 
+/// Linux collectors and the metrics each one provides
+pub(crate) const COLLECTORS: [CollectorSpec; 11] = [
+    ("cpu", &["cpu_usage", "cpu_io_wait", "cpu_steal"]),
+    ("cpu_cores", &["cpu_core_saturation"]),
+    ("cpu_load", &["cpu_load"]),
+    ("memory", &["memory_usage"]),
+    ("memory_pressure", &["memory_pressure"]),
+    ("disk_io", &["disk_io_utilization"]),
+    ("network", &["network_dropped_packets"]),
+    ("file_descriptors", &["file_descriptors"]),
+    ("processes", &["process_count"]),
+    ("cpu_frequency", &["cpu_throttling"]),
+    ("thermal", &["thermal_load"]),
+];
+
 impl LinuxSystemMetrics {
     /// Collect all system metrics using optimized parallel execution
     ///
-    /// This method uses `tokio::join!` to collect all metrics in parallel,
-    /// maximizing performance and minimizing total collection time.
+    /// This method runs every collector on its own thread, maximizing
+    /// performance and minimizing total collection time.
     ///
     /// # Returns
     ///
@@ -85,12 +156,11 @@ impl LinuxSystemMetrics {
     /// # Example
     ///
     /// ```rust,no_run
-    /// use pwrzv::get_power_reserve_level_with_details_direct;
+    /// use pwrzv::blocking::get_power_reserve_level_with_details;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (level, details) = get_power_reserve_level_with_details()?;
     ///
-    /// #[tokio::main]
-    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ///     let (level, details) = get_power_reserve_level_with_details_direct().await?;
-    ///     
     ///     println!("Power reserve level: {}", level);
     ///     for (metric, score) in details {
     ///         println!("{}: {}", metric, score);
@@ -98,124 +168,362 @@ impl LinuxSystemMetrics {
     ///     Ok(())
     /// }
     /// ```
-    pub async fn collect_system_metrics() -> PwrzvResult<Self> {
-        // Execute all metrics collection in parallel for optimal performance
-        let (cpu_result, memory_result, network_result, disk_result, fd_result, process_result) = tokio::join!(
-            Self::get_cpu_metrics_consolidated(),
-            Self::get_memory_metrics_consolidated(),
-            Self::get_network_metrics_consolidated(),
-            Self::get_disk_io_utilization_instant(),
-            Self::get_fd_usage(),
-            Self::get_process_count()
-        );
-
-        // Extract results, using None for any failed metrics
-        let (cpu_usage_ratio, cpu_io_wait_ratio, cpu_load_ratio) =
-            cpu_result.unwrap_or((None, None, None));
-        let (memory_usage_ratio, memory_pressure_ratio) = memory_result.unwrap_or((None, None));
-        let network_dropped_packets_ratio = network_result.unwrap_or(None);
-        let disk_io_utilization = disk_result.unwrap_or(None);
-        let fd_usage_ratio = fd_result.unwrap_or(None);
-        let process_count_ratio = process_result.unwrap_or(None);
-
-        Ok(LinuxSystemMetrics {
-            cpu_usage_ratio,
-            cpu_io_wait_ratio,
-            cpu_load_ratio,
-            memory_usage_ratio,
-            memory_pressure_ratio,
-            disk_io_utilization,
-            network_dropped_packets_ratio,
-            fd_usage_ratio,
-            process_count_ratio,
-        })
+    #[cfg(test)]
+    pub fn collect_system_metrics() -> PwrzvResult<Self> {
+        let (metrics, _) = Self::collect(&CollectionConfig::default(), &|_| true);
+        Ok(metrics)
     }
 
-    /// Get CPU metrics with consolidated /proc/stat and /proc/loadavg reads
+    /// Collect all system metrics with the status of every collector
     ///
-    /// Uses parallel file reads to retrieve:
-    /// - `/proc/stat`: CPU time statistics for usage and I/O wait calculation
-    /// - `/proc/loadavg`: Load averages
-    /// - `/proc/cpuinfo`: CPU core count
+    /// Collectors run in parallel, each with its own timeout from `config`.
+    /// Collectors none of whose metrics is `enabled`, or over the budget, are
+    /// skipped.
     ///
     /// # Returns
     ///
-    /// A tuple of `(cpu_usage_ratio, cpu_io_wait_ratio, cpu_load_ratio)` where
-    /// each may be `None` if the corresponding metric could not be calculated.
+    /// The metrics, `None` where a collector failed, and one report per
+    /// collector in [`COLLECTORS`] order explaining any failure.
+    pub(crate) fn collect(
+        config: &CollectionConfig,
+        enabled: &dyn Fn(&str) -> bool,
+    ) -> (Self, Vec<CollectorReport>) {
+        let plan = Plan::new(config, &COLLECTORS, enabled);
+        let [
+            cpu,
+            cores,
+            load,
+            memory,
+            pressure,
+            disk,
+            network,
+            fds,
+            processes,
+            frequency,
+            thermal,
+        ] = COLLECTORS;
+        let root = Arc::new(config.root.clone());
+        let with_root = |collect: fn(&Path) -> PwrzvResult<f32>| {
+            let root = Arc::clone(&root);
+            move || collect(&root)
+        };
+
+        let cpu_root = Arc::clone(&root);
+        let cpu = collector::spawn(cpu, &plan, move || Self::get_cpu_usage(&cpu_root));
+        let cores_root = Arc::clone(&root);
+        let cores = collector::spawn(cores, &plan, move || Self::get_cpu_cores(&cores_root));
+        let load_root = Arc::clone(&root);
+        let load = collector::spawn(load, &plan, move || Self::get_cpu_load(&load_root));
+        let memory = collector::spawn(memory, &plan, with_root(Self::get_memory_usage));
+        let pressure = collector::spawn(pressure, &plan, with_root(Self::get_memory_pressure));
+        let disk = collector::spawn(disk, &plan, with_root(Self::get_disk_io_utilization));
+        let network = collector::spawn(
+            network,
+            &plan,
+            with_root(Self::get_network_metrics_consolidated),
+        );
+        let fds = collector::spawn(fds, &plan, with_root(Self::get_fd_usage));
+        let processes = collector::spawn(processes, &plan, with_root(Self::get_process_count));
+        let frequency_root = Arc::clone(&root);
+        let frequency = collector::spawn(frequency, &plan, move || {
+            Self::get_cpu_throttling(&frequency_root)
+        });
+        let thermal_root = Arc::clone(&root);
+        let thermal = collector::spawn(thermal, &plan, move || {
+            Self::get_thermal_load(&thermal_root)
+        });
+
+        let (cpu_result, cpu_report) = cpu.wait();
+        let (cores_result, cores_report) = cores.wait();
+        let (load_result, load_report) = load.wait();
+        let (memory_result, memory_report) = memory.wait();
+        let (pressure_result, pressure_report) = pressure.wait();
+        let (disk_result, disk_report) = disk.wait();
+        let (network_result, network_report) = network.wait();
+        let (fd_result, fd_report) = fds.wait();
+        let (process_result, process_report) = processes.wait();
+        let (throttling_result, frequency_report) = frequency.wait_optional();
+        let (thermal_result, thermal_report) = thermal.wait_optional();
+
+        let metrics = LinuxSystemMetrics {
+            cpu_usage_ratio: cpu_result.map(|(usage, _, _)| usage),
+            cpu_io_wait_ratio: cpu_result.map(|(_, io_wait, _)| io_wait),
+            cpu_steal_ratio: cpu_result.map(|(_, _, steal)| steal),
+            cpu_load_ratio: load_result.map(|(load, _)| load),
+            effective_cpus: load_result.map(|(_, cpus)| cpus),
+            memory_usage_ratio: memory_result,
+            memory_pressure_ratio: pressure_result,
+            disk_io_utilization: disk_result,
+            network_dropped_packets_ratio: network_result,
+            fd_usage_ratio: fd_result,
+            process_count_ratio: process_result,
+            cpu_throttling_ratio: throttling_result,
+            thermal_load_ratio: thermal_result,
+            cpu_cores: cores_result,
+        };
+        let reports = vec![
+            cpu_report,
+            cores_report,
+            load_report,
+            memory_report,
+            pressure_report,
+            disk_report,
+            network_report,
+            fd_report,
+            process_report,
+            frequency_report,
+            thermal_report,
+        ];
+        (metrics, reports)
+    }
+
+    /// Read a procfs file for a metric
     ///
-    /// # Performance
+    /// `path` is absolute on the live system and is resolved under `root`.
+    fn read_proc(root: &Path, metric: &str, path: &str) -> PwrzvResult<String> {
+        let path = root.join(path.trim_start_matches('/'));
+        collector::count_file_read();
+        fs::read_to_string(&path).map_err(|e| PwrzvError::read_error(path, e).with_metric(metric))
+    }
+
+    /// Create a parse error for a procfs file, quoting its first line
+    fn proc_parse_error(metric: &str, path: &str, content: &str, detail: &str) -> PwrzvError {
+        let error = PwrzvError::parse_error(detail)
+            .context(path)
+            .with_metric(metric);
+        match content.lines().next() {
+            Some(line) => error.with_line(line),
+            None => error,
+        }
+    }
+
+    /// Get CPU usage, I/O wait and steal from `/proc/stat`
     ///
-    /// This consolidated approach is significantly faster than separate reads.
-    pub(crate) async fn get_cpu_metrics_consolidated()
-    -> PwrzvResult<(Option<f32>, Option<f32>, Option<f32>)> {
-        // Execute all CPU-related reads in parallel
-        let (stat_result, loadavg_result, cpuinfo_result) = tokio::join!(
-            async { fs::read_to_string("/proc/stat") },
-            async { fs::read_to_string("/proc/loadavg") },
-            async { fs::read_to_string("/proc/cpuinfo") }
-        );
+    /// # Returns
+    ///
+    /// A tuple of `(cpu_usage_ratio, cpu_io_wait_ratio, cpu_steal_ratio)`
+    pub(crate) fn get_cpu_usage(root: &Path) -> PwrzvResult<(f32, f32, f32)> {
+        let content = Self::read_proc(root, "cpu_usage", "/proc/stat")?;
+        let stat = Self::parse_cpu_stat(&content).ok_or_else(|| {
+            Self::proc_parse_error("cpu_usage", "/proc/stat", &content, "malformed cpu line")
+        })?;
 
-        let mut cpu_usage: Option<f32> = None;
-        let mut cpu_io_wait: Option<f32> = None;
-        let mut cpu_load: Option<f32> = None;
-
-        // Parse CPU statistics
-        #[allow(clippy::collapsible_if)]
-        if let Ok(stat_content) = stat_result {
-            if let Some(stat) = Self::parse_cpu_stat(&stat_content) {
-                let total = stat.total();
-                if total > 0 {
-                    let idle_percent = stat.idle as f32 / total as f32;
-                    cpu_usage = Some((1.0f32 - idle_percent).clamp(0.0, 1.0));
-                    cpu_io_wait = Some((stat.iowait as f32 / total as f32).clamp(0.0, 1.0));
-                }
-            }
+        let total = stat.total();
+        if total == 0 {
+            return Err(Self::proc_parse_error(
+                "cpu_usage",
+                "/proc/stat",
+                &content,
+                "zero total CPU time",
+            ));
         }
+        let idle_percent = stat.idle as f32 / total as f32;
+        let cpu_usage = (1.0f32 - idle_percent).clamp(0.0, 1.0);
+        let cpu_io_wait = (stat.iowait as f32 / total as f32).clamp(0.0, 1.0);
+        let cpu_steal = (stat.steal as f32 / total as f32).clamp(0.0, 1.0);
+        Ok((cpu_usage, cpu_io_wait, cpu_steal))
+    }
 
-        // Parse load average and combine with CPU core count
-        #[allow(clippy::collapsible_if)]
-        if let (Ok(loadavg_content), Ok(cpuinfo_content)) = (loadavg_result, cpuinfo_result) {
-            if let (Some(load_avg), Some(cpu_cores)) = (
-                Self::parse_load_average(&loadavg_content),
-                Self::parse_cpu_cores(&cpuinfo_content),
-            ) {
-                cpu_load = Some((load_avg / cpu_cores as f32).min(10.0)); // Cap at reasonable maximum
+    /// Get per-core and NUMA node CPU usage from `/proc/stat` and `/sys/devices/system/node`
+    ///
+    /// Usage is measured since the previous sample under the same root. The
+    /// first sample reads `/proc/stat` twice, [`CORE_FIRST_INTERVAL`] apart,
+    /// so that one-shot callers see current usage too.
+    pub(crate) fn get_cpu_cores(root: &Path) -> PwrzvResult<CoreAnalysis> {
+        let metric = "cpu_core_saturation";
+        let previous = match &*CORE_SNAPSHOT.lock().unwrap() {
+            Some((previous_root, previous)) if previous_root == root => Some(previous.clone()),
+            _ => None,
+        };
+        let previous = match previous {
+            Some(previous) => previous,
+            None => {
+                let first = Self::read_proc(root, metric, "/proc/stat")?;
+                std::thread::sleep(CORE_FIRST_INTERVAL);
+                Self::parse_core_stats(&first)
             }
+        };
+        let content = Self::read_proc(root, metric, "/proc/stat")?;
+        let current = Self::parse_core_stats(&content);
+        let nodes = Self::read_numa_nodes(root, metric)?;
+
+        let analysis = CoreAnalysis::new(&current, Some(&previous), &nodes).ok_or_else(|| {
+            Self::proc_parse_error(metric, "/proc/stat", &content, "no per-core cpu lines")
+        })?;
+        let advanced = current.iter().any(|(cpu, stat)| {
+            previous
+                .get(cpu)
+                .is_none_or(|before| stat.total() >= before.total() + CORE_MIN_INTERVAL_TICKS)
+        });
+        if advanced {
+            *CORE_SNAPSHOT.lock().unwrap() = Some((root.to_path_buf(), current));
         }
-
-        Ok((cpu_usage, cpu_io_wait, cpu_load))
+        Ok(analysis)
     }
 
-    /// Get memory metrics with consolidated /proc/meminfo read
+    /// Read the CPU list of every NUMA node from `/sys/devices/system/node`
     ///
-    /// Uses a single `/proc/meminfo` read to retrieve memory statistics and
-    /// attempts to read PSI memory pressure if available.
+    /// Hosts without NUMA support have no node directories and get an empty map.
+    fn read_numa_nodes(root: &Path, metric: &str) -> PwrzvResult<BTreeMap<usize, BTreeSet<usize>>> {
+        let mut nodes = BTreeMap::new();
+        for node in Self::sys_entries(&root.join("sys/devices/system/node"), "node") {
+            let Some(number) = node
+                .file_name()
+                .and_then(|name| name.to_str()?.strip_prefix("node")?.parse().ok())
+            else {
+                continue;
+            };
+            let path = node.join("cpulist");
+            collector::count_file_read();
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let cpus = topology::parse_cpu_list(&content).ok_or_else(|| {
+                PwrzvError::parse_error("malformed CPU list")
+                    .context(&path.display().to_string())
+                    .with_line(content.trim())
+                    .with_metric(metric)
+            })?;
+            nodes.insert(number, cpus);
+        }
+        Ok(nodes)
+    }
+
+    /// Get the CPU load ratio from `/proc/loadavg`
     ///
     /// # Returns
     ///
-    /// A tuple of `(memory_usage_ratio, memory_pressure_ratio)` where each
-    /// may be `None` if the metric could not be calculated.
-    pub(crate) async fn get_memory_metrics_consolidated() -> PwrzvResult<(Option<f32>, Option<f32>)>
-    {
-        // Execute memory info and pressure reads in parallel
-        let (meminfo_result, pressure_result) =
-            tokio::join!(async { fs::read_to_string("/proc/meminfo") }, async {
-                fs::read_to_string("/proc/pressure/memory")
-            });
-
-        let memory_usage = if let Ok(meminfo_content) = meminfo_result {
-            Self::parse_memory_usage(&meminfo_content)
-        } else {
-            None
-        };
+    /// The 1-minute load average divided by the effective CPU count, and
+    /// that count; see [`get_effective_cpus`](Self::get_effective_cpus)
+    pub(crate) fn get_cpu_load(root: &Path) -> PwrzvResult<(f32, EffectiveCpus)> {
+        let loadavg = Self::read_proc(root, "cpu_load", "/proc/loadavg")?;
+        let load_avg = Self::parse_load_average(&loadavg).ok_or_else(|| {
+            Self::proc_parse_error("cpu_load", "/proc/loadavg", &loadavg, "no load average")
+        })?;
+        let cpus = Self::get_effective_cpus(root)?;
+
+        Ok(((load_avg / cpus.count).min(10.0), cpus)) // Cap at reasonable maximum
+    }
+
+    /// Determine how many CPUs this process can use
+    ///
+    /// `/proc/cpuinfo` lists every processor of the host, also in a container
+    /// pinned to a few of them. The count is the smallest of the processor
+    /// count, the CPUs in this process's cgroup (`cpuset.cpus.effective`), the
+    /// tightest `cpu.max` quota of the cgroup and its ancestors, and, when
+    /// reading the live system, the affinity mask of this process.
+    pub(crate) fn get_effective_cpus(root: &Path) -> PwrzvResult<EffectiveCpus> {
+        let cpuinfo = Self::read_proc(root, "cpu_load", "/proc/cpuinfo")?;
+        let processors = Self::parse_cpu_cores(&cpuinfo).ok_or_else(|| {
+            PwrzvError::parse_error("no processor entries")
+                .context("/proc/cpuinfo")
+                .with_metric("cpu_load")
+        })?;
+
+        let mut candidates = Vec::new();
+        if let Some((mount, cgroup)) = Self::own_cgroup(root) {
+            let quota = cgroup
+                .ancestors()
+                .take_while(|dir| dir.starts_with(&mount))
+                .filter_map(|dir| {
+                    collector::count_file_read();
+                    let content = fs::read_to_string(dir.join("cpu.max")).ok()?;
+                    topology::parse_cpu_max(&content)
+                })
+                .reduce(f32::min);
+            if let Some(quota) = quota {
+                candidates.push((quota, CpuCountSource::Quota));
+            }
+            if let Some(cpus) = Self::read_cpuset(&mount, &cgroup)? {
+                candidates.push((cpus as f32, CpuCountSource::Cpuset));
+            }
+        }
+        if root == Path::new("/")
+            && let Some(cpus) = Self::affinity_cpus()
+        {
+            candidates.push((cpus as f32, CpuCountSource::Affinity));
+        }
+        candidates.push((processors as f32, CpuCountSource::Cpuinfo));
+
+        Ok(EffectiveCpus::smallest(candidates).expect("the processor count is positive"))
+    }
 
-        let memory_pressure = if let Ok(pressure_content) = pressure_result {
-            Self::parse_memory_pressure(&pressure_content)
+    /// Find the cgroup v2 hierarchy and this process's cgroup directory in it
+    ///
+    /// `None` without a v2 hierarchy, as under cgroup v1.
+    fn own_cgroup(root: &Path) -> Option<(PathBuf, PathBuf)> {
+        let mount = if root == Path::new("/") {
+            cgroup::find_cgroup_root().ok()?
         } else {
-            None
+            cgroup::find_cgroup_mount(root)?
         };
+        collector::count_file_read();
+        let content = fs::read_to_string(root.join("proc/self/cgroup")).ok()?;
+        let path = content.lines().find_map(|line| line.strip_prefix("0::"))?;
+        let cgroup = mount.join(path.trim_start_matches('/'));
+        Some((mount, cgroup))
+    }
 
-        Ok((memory_usage, memory_pressure))
+    /// Count the CPUs of a cgroup from `cpuset.cpus.effective`
+    ///
+    /// Cgroups without the cpuset controller have no such file and use their
+    /// closest ancestor's.
+    fn read_cpuset(mount: &Path, cgroup: &Path) -> PwrzvResult<Option<usize>> {
+        for dir in cgroup.ancestors().take_while(|dir| dir.starts_with(mount)) {
+            let path = dir.join("cpuset.cpus.effective");
+            collector::count_file_read();
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let cpus = topology::parse_cpu_list(&content).ok_or_else(|| {
+                PwrzvError::parse_error("malformed CPU list")
+                    .context(&path.display().to_string())
+                    .with_line(content.trim())
+                    .with_metric("cpu_load")
+            })?;
+            return Ok(Some(cpus.len()));
+        }
+        Ok(None)
+    }
+
+    /// Count the CPUs in the affinity mask of this process
+    fn affinity_cpus() -> Option<usize> {
+        // SAFETY: an all-zero cpu_set_t is an empty set
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        // SAFETY: `set` is a valid cpu_set_t of the size passed
+        let result =
+            unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+        // Fails with EINVAL on hosts with more CPUs than a cpu_set_t holds
+        if result != 0 {
+            return None;
+        }
+        // SAFETY: `set` was filled in by the kernel
+        Some(unsafe { libc::CPU_COUNT(&set) } as usize)
+    }
+
+    /// Get the memory usage ratio from `/proc/meminfo`
+    pub(crate) fn get_memory_usage(root: &Path) -> PwrzvResult<f32> {
+        let content = Self::read_proc(root, "memory_usage", "/proc/meminfo")?;
+        Self::parse_memory_usage(&content).ok_or_else(|| {
+            PwrzvError::parse_error("missing MemTotal or MemAvailable")
+                .context("/proc/meminfo")
+                .with_metric("memory_usage")
+        })
+    }
+
+    /// Get the memory pressure ratio from PSI (`/proc/pressure/memory`)
+    ///
+    /// PSI requires Linux 4.20 or later with `CONFIG_PSI` enabled.
+    pub(crate) fn get_memory_pressure(root: &Path) -> PwrzvResult<f32> {
+        let content = Self::read_proc(root, "memory_pressure", "/proc/pressure/memory")?;
+        Self::parse_memory_pressure(&content).ok_or_else(|| {
+            Self::proc_parse_error(
+                "memory_pressure",
+                "/proc/pressure/memory",
+                &content,
+                "no 'some avg10=' value",
+            )
+        })
     }
 
     /// Get network metrics with consolidated /proc/net/dev read
@@ -225,116 +533,264 @@ impl LinuxSystemMetrics {
     ///
     /// # Returns
     ///
-    /// Network dropped packets ratio as `Option<f32>`, or `None` if no network
-    /// activity was detected or parsing failed.
-    pub(crate) async fn get_network_metrics_consolidated() -> PwrzvResult<Option<f32>> {
-        let network_stats = match fs::read_to_string("/proc/net/dev") {
-            Ok(content) => Self::parse_network_stats(&content),
-            Err(_) => return Ok(None),
-        };
-
-        if let Some(stats) = network_stats {
-            // Calculate dropped packets ratio only
-            let dropped_ratio = Self::calculate_instant_dropped_packets_ratio(&stats);
-            Ok(dropped_ratio)
-        } else {
-            Ok(None)
-        }
+    /// Network dropped packets ratio, or an error if no interface other than
+    /// the loopback has carried traffic.
+    pub(crate) fn get_network_metrics_consolidated(root: &Path) -> PwrzvResult<f32> {
+        let metric = "network_dropped_packets";
+        let content = Self::read_proc(root, metric, "/proc/net/dev")?;
+        let stats = Self::parse_network_stats(&content).ok_or_else(|| {
+            PwrzvError::collection_error("/proc/net/dev: no network interface besides lo")
+                .with_metric(metric)
+        })?;
+
+        Self::calculate_instant_dropped_packets_ratio(&stats).ok_or_else(|| {
+            PwrzvError::collection_error("/proc/net/dev: no interface has carried traffic")
+                .with_metric(metric)
+        })
     }
 
-    /// Get disk I/O utilization using iostat or /proc/diskstats fallback
+    /// Get disk I/O utilization from `/proc/diskstats` and `/proc/uptime`
     ///
-    /// Attempts to use `iostat -x` to get real utilization data, falling back
-    /// to `/proc/diskstats` estimation if iostat is not available.
+    /// Measured as the share of time the busiest disk spent doing I/O since
+    /// the previous sample under the same root, or since boot on the first.
     ///
     /// # Returns
     ///
-    /// Disk I/O utilization as `Option<f32>`, or `None` if no disks were found
-    /// or parsing failed.
-    pub(crate) async fn get_disk_io_utilization_instant() -> PwrzvResult<Option<f32>> {
-        // Try to use iostat -x to get real %util first
-        if let Some(iostat_util) = Self::get_disk_util_from_iostat().await {
-            return Ok(Some(iostat_util));
-        }
-
-        // Fallback to /proc/diskstats estimation
-        match fs::read_to_string("/proc/diskstats") {
-            Ok(content) => {
-                if let Some(disk_stats) = Self::parse_disk_stats(&content) {
-                    Ok(Self::estimate_disk_utilization(&disk_stats))
-                } else {
-                    Ok(None)
-                }
-            }
-            Err(_) => Ok(None),
+    /// Disk I/O utilization, or an error if no disks were found.
+    pub(crate) fn get_disk_io_utilization(root: &Path) -> PwrzvResult<f32> {
+        let metric = "disk_io_utilization";
+        let uptime = Self::read_proc(root, metric, "/proc/uptime")?;
+        let diskstats = Self::read_proc(root, metric, "/proc/diskstats")?;
+        let uptime_ms = Self::parse_uptime_ms(&uptime)
+            .ok_or_else(|| Self::proc_parse_error(metric, "/proc/uptime", &uptime, "no uptime"))?;
+        let io_ticks = Self::parse_disk_stats(&diskstats).ok_or_else(|| {
+            PwrzvError::collection_error("/proc/diskstats: no disks found").with_metric(metric)
+        })?;
+        let current = DiskSnapshot {
+            uptime_ms,
+            io_ticks,
+        };
+
+        let mut latest = DISK_SNAPSHOT.lock().unwrap();
+        let previous = match &*latest {
+            Some((previous_root, previous)) if previous_root == root => Some(previous),
+            _ => None,
+        };
+        let utilization = current.utilization_since(previous);
+        let interval = previous.map(|previous| uptime_ms.saturating_sub(previous.uptime_ms));
+        if interval.is_none_or(|interval| interval >= DISK_MIN_INTERVAL_MS) {
+            *latest = Some((root.to_path_buf(), current));
         }
+        Ok(utilization)
     }
 
     /// Get file descriptor usage ratio
     ///
     /// Reads system file descriptor limits and current usage from `/proc/sys/fs/`.
+    pub(crate) fn get_fd_usage(root: &Path) -> PwrzvResult<f32> {
+        let metric = "file_descriptors";
+        let file_nr = Self::read_proc(root, metric, "/proc/sys/fs/file-nr")?;
+        let file_max = Self::read_proc(root, metric, "/proc/sys/fs/file-max")?;
+
+        let open_fds = file_nr
+            .split_whitespace()
+            .next()
+            .and_then(|s| s.parse::<u64>().ok())
+            .ok_or_else(|| {
+                Self::proc_parse_error(metric, "/proc/sys/fs/file-nr", &file_nr, "no open count")
+            })?;
+        let max_fds = file_max
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|&max_fds| max_fds > 0)
+            .ok_or_else(|| {
+                Self::proc_parse_error(
+                    metric,
+                    "/proc/sys/fs/file-max",
+                    &file_max,
+                    "no positive limit",
+                )
+            })?;
+
+        Ok((open_fds as f32 / max_fds as f32).min(1.0))
+    }
+
+    /// Get process count ratio
+    ///
+    /// Counts the process directories in `/proc` and compares against a
+    /// typical system limit.
+    pub(crate) fn get_process_count(root: &Path) -> PwrzvResult<f32> {
+        let proc = root.join("proc");
+        collector::count_file_read();
+        let entries = fs::read_dir(&proc)
+            .map_err(|e| PwrzvError::read_error(&proc, e).with_metric("process_count"))?;
+        let process_count = entries
+            .filter_map(Result::ok)
+            .filter(|entry| Self::is_number(&entry.file_name().to_string_lossy()))
+            .count();
+
+        // Typical max processes is around 4096 for most systems
+        let typical_max = 4096.0;
+        Ok((process_count as f32 / typical_max).min(10.0)) // Cap at reasonable maximum
+    }
+
+    /// Get the CPU throttling ratio from cpufreq in sysfs
+    ///
+    /// Compares the frequency every CPU is allowed to reach, `scaling_max_freq`,
+    /// with its hardware maximum and takes the least capped one. Thermal and
+    /// power-limit drivers lower that ceiling.
+    ///
+    /// The current frequency, `scaling_cur_freq`, is deliberately ignored: a
+    /// governor clocks idle cores down to their minimum, so it is low whenever
+    /// the host is idle and would read as throttled exactly when there is the
+    /// most reserve.
     ///
     /// # Returns
     ///
-    /// FD usage ratio as `Option<f32>`, or `None` if the limits could not be read.
-    pub(crate) async fn get_fd_usage() -> PwrzvResult<Option<f32>> {
-        let (file_nr_result, file_max_result) = tokio::join!(
-            async { fs::read_to_string("/proc/sys/fs/file-nr") },
-            async { fs::read_to_string("/proc/sys/fs/file-max") }
-        );
-
-        if let (Ok(file_nr_content), Ok(file_max_content)) = (file_nr_result, file_max_result) {
-            let open_fds = file_nr_content
-                .split_whitespace()
-                .next()
-                .and_then(|s| s.parse::<u64>().ok());
+    /// `1 - allowed / maximum` of the least capped CPU, or `None` if the host
+    /// exposes no cpufreq data, as most virtual machines do
+    pub(crate) fn get_cpu_throttling(root: &Path) -> PwrzvResult<Option<f32>> {
+        let metric = "cpu_throttling";
+        let mut fastest: Option<f32> = None;
+        for cpu in Self::sys_entries(&root.join("sys/devices/system/cpu"), "cpu") {
+            let cpufreq = cpu.join("cpufreq");
+            let Some(allowed) = Self::read_sys_value(&cpufreq.join("scaling_max_freq"), metric)?
+            else {
+                continue;
+            };
+            let Some(max) = Self::read_sys_value(&cpufreq.join("cpuinfo_max_freq"), metric)?
+                .filter(|&max| max > 0)
+            else {
+                continue;
+            };
+            let ratio = allowed as f32 / max as f32;
+            fastest = Some(fastest.map_or(ratio, |fastest| fastest.max(ratio)));
+        }
+        Ok(fastest.map(|ratio| (1.0 - ratio).clamp(0.0, 1.0)))
+    }
 
-            let max_fds = file_max_content.trim().parse::<u64>().ok();
+    /// Get the thermal load ratio from the thermal zones in sysfs
+    ///
+    /// Every zone's temperature is compared with its `critical` trip point,
+    /// or its highest one if none is critical. Zones without trip points are
+    /// left out.
+    ///
+    /// # Returns
+    ///
+    /// The ratio of the hottest zone, or `None` if the host exposes no
+    /// thermal zone with a trip point
+    pub(crate) fn get_thermal_load(root: &Path) -> PwrzvResult<Option<f32>> {
+        let metric = "thermal_load";
+        let mut hottest: Option<f32> = None;
+        for zone in Self::sys_entries(&root.join("sys/class/thermal"), "thermal_zone") {
+            let Some(temp) = Self::read_sys_value(&zone.join("temp"), metric)? else {
+                continue;
+            };
+            let Some(critical) = Self::critical_temperature(&zone, metric)? else {
+                continue;
+            };
+            let load = (temp as f32 / critical as f32).clamp(0.0, 1.0);
+            hottest = Some(hottest.map_or(load, |hottest| hottest.max(load)));
+        }
+        Ok(hottest)
+    }
 
-            if let (Some(open_fds), Some(max_fds)) = (open_fds, max_fds) {
-                if max_fds > 0 {
-                    Ok(Some((open_fds as f32 / max_fds as f32).min(1.0)))
-                } else {
-                    Ok(None)
-                }
-            } else {
-                Ok(None)
+    /// Get the temperature a thermal zone must stay below, in millidegrees Celsius
+    ///
+    /// The `critical` trip point, or else the highest one; `None` if the zone
+    /// has no positive trip point.
+    fn critical_temperature(zone: &Path, metric: &str) -> PwrzvResult<Option<i64>> {
+        let mut critical = None;
+        let mut highest = None;
+        // Trip points are numbered from 0 without gaps
+        for n in 0.. {
+            let Some(temp) =
+                Self::read_sys_value(&zone.join(format!("trip_point_{n}_temp")), metric)?
+            else {
+                break;
+            };
+            if temp <= 0 {
+                continue;
             }
-        } else {
-            Ok(None)
+            collector::count_file_read();
+            let kind = fs::read_to_string(zone.join(format!("trip_point_{n}_type")));
+            if kind.is_ok_and(|kind| kind.trim() == "critical") {
+                critical = Some(temp);
+            }
+            highest = highest.max(Some(temp));
         }
+        Ok(critical.or(highest))
     }
 
-    /// Get process count ratio
+    /// List the entries of a sysfs directory named `prefix` followed by a number
     ///
-    /// Uses `ps aux` to count processes and compares against a typical system limit.
+    /// A missing directory has no entries.
+    fn sys_entries(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+        collector::count_file_read();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .strip_prefix(prefix)
+                    .is_some_and(Self::is_number)
+            })
+            .map(|entry| entry.path())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Read a number from a sysfs file
     ///
     /// # Returns
     ///
-    /// Process count ratio as `Option<f32>`, or `None` if process count could not be determined.
-    pub(crate) async fn get_process_count() -> PwrzvResult<Option<f32>> {
-        let output = match tokio::process::Command::new("ps")
-            .args(["aux"])
-            .output()
-            .await
-        {
-            Ok(output) if output.status.success() => output,
-            _ => return Ok(None),
+    /// The number, `None` if the file cannot be read (sysfs refuses reads of
+    /// offline CPUs and disabled sensors), or an error if it holds no number
+    fn read_sys_value(path: &Path, metric: &str) -> PwrzvResult<Option<i64>> {
+        collector::count_file_read();
+        let Ok(content) = fs::read_to_string(path) else {
+            return Ok(None);
         };
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let process_count = stdout.lines().count().saturating_sub(1); // Subtract header line
-
-        // Typical max processes is around 4096 for most systems
-        let typical_max = 4096.0;
-        Ok(Some((process_count as f32 / typical_max).min(10.0))) // Cap at reasonable maximum
+        let value = content.trim().parse::<i64>().map_err(|_| {
+            PwrzvError::parse_error("not a number")
+                .context(&path.display().to_string())
+                .with_line(content.trim())
+                .with_metric(metric)
+        })?;
+        Ok(Some(value))
     }
 
     // Private parsing methods
 
     /// Parse CPU statistics from /proc/stat content
-    fn parse_cpu_stat(content: &str) -> Option<CpuStat> {
-        let line = content.lines().next()?;
+    pub(crate) fn parse_cpu_stat(content: &str) -> Option<CpuStat> {
+        Self::parse_cpu_line(content.lines().next()?)
+    }
+
+    /// Parse the per-core `cpuN` lines of /proc/stat content, keyed by CPU number
+    ///
+    /// Malformed lines are skipped.
+    pub(crate) fn parse_core_stats(content: &str) -> BTreeMap<usize, CpuStat> {
+        content
+            .lines()
+            .filter_map(|line| {
+                let name = line.split_whitespace().next()?;
+                let cpu = name
+                    .strip_prefix("cpu")
+                    .filter(|cpu| Self::is_number(cpu))?;
+                Some((cpu.parse().ok()?, Self::parse_cpu_line(line)?))
+            })
+            .collect()
+    }
+
+    /// Parse one `cpu` or `cpuN` line of /proc/stat
+    fn parse_cpu_line(line: &str) -> Option<CpuStat> {
         let parts: Vec<&str> = line.split_whitespace().collect();
 
         if parts.len() < 8 {
@@ -348,6 +804,12 @@ impl LinuxSystemMetrics {
         let iowait = parts[5].parse::<u64>().ok()?;
         let irq = parts[6].parse::<u64>().ok()?;
         let softirq = parts[7].parse::<u64>().ok()?;
+        // Kernels before 2.6.11 have no steal field. The guest and guest_nice
+        // fields after it are already counted in user and nice.
+        let steal = match parts.get(8) {
+            Some(steal) => steal.parse::<u64>().ok()?,
+            None => 0,
+        };
 
         Some(CpuStat {
             user,
@@ -357,11 +819,12 @@ impl LinuxSystemMetrics {
             iowait,
             irq,
             softirq,
+            steal,
         })
     }
 
     /// Parse load average from /proc/loadavg content
-    fn parse_load_average(content: &str) -> Option<f32> {
+    pub(crate) fn parse_load_average(content: &str) -> Option<f32> {
         content
             .split_whitespace()
             .next()
@@ -369,7 +832,7 @@ impl LinuxSystemMetrics {
     }
 
     /// Parse CPU core count from /proc/cpuinfo content
-    fn parse_cpu_cores(content: &str) -> Option<u32> {
+    pub(crate) fn parse_cpu_cores(content: &str) -> Option<u32> {
         let core_count = content
             .lines()
             .filter(|line| line.starts_with("processor"))
@@ -383,7 +846,7 @@ impl LinuxSystemMetrics {
     }
 
     /// Parse memory usage from /proc/meminfo content
-    fn parse_memory_usage(content: &str) -> Option<f32> {
+    pub(crate) fn parse_memory_usage(content: &str) -> Option<f32> {
         let mut mem_total = 0u64;
         let mut mem_available = 0u64;
 
@@ -418,7 +881,7 @@ impl LinuxSystemMetrics {
     }
 
     /// Parse memory pressure from /proc/pressure/memory content
-    fn parse_memory_pressure(content: &str) -> Option<f32> {
+    pub(crate) fn parse_memory_pressure(content: &str) -> Option<f32> {
         for line in content.lines() {
             if line.starts_with("some avg10=") {
                 let avg10_str = line.split("avg10=").nth(1)?.split_whitespace().next()?;
@@ -430,7 +893,7 @@ impl LinuxSystemMetrics {
     }
 
     /// Parse network statistics from /proc/net/dev content
-    fn parse_network_stats(content: &str) -> Option<HashMap<String, NetworkStats>> {
+    pub(crate) fn parse_network_stats(content: &str) -> Option<HashMap<String, NetworkStats>> {
         let mut stats = HashMap::new();
 
         for line in content.lines().skip(2) {
@@ -469,8 +932,8 @@ impl LinuxSystemMetrics {
         if stats.is_empty() { None } else { Some(stats) }
     }
 
-    /// Parse disk statistics from /proc/diskstats content
-    fn parse_disk_stats(content: &str) -> Option<HashMap<String, DiskStat>> {
+    /// Parse the milliseconds every disk spent doing I/O from /proc/diskstats content
+    pub(crate) fn parse_disk_stats(content: &str) -> Option<HashMap<String, u64>> {
         let mut stats = HashMap::new();
 
         for line in content.lines() {
@@ -486,83 +949,26 @@ impl LinuxSystemMetrics {
                 continue;
             }
 
-            let sectors_read = parts[5].parse::<u64>().unwrap_or(0);
-            let sectors_written = parts[9].parse::<u64>().unwrap_or(0);
-
-            stats.insert(
-                device,
-                DiskStat {
-                    sectors_read,
-                    sectors_written,
-                },
-            );
+            let io_ticks = parts[12].parse::<u64>().unwrap_or(0);
+            stats.insert(device, io_ticks);
         }
 
         if stats.is_empty() { None } else { Some(stats) }
     }
 
-    /// Try to get real disk utilization from iostat -x
-    async fn get_disk_util_from_iostat() -> Option<f32> {
-        let output = tokio::process::Command::new("iostat")
-            .args(["-x", "1", "1"])
-            .output()
-            .await
-            .ok()?;
-
-        let output_str = std::str::from_utf8(&output.stdout).ok()?;
-
-        let mut max_util = 0.0f32;
-
-        // Parse iostat -x output to find %util column
-        for line in output_str.lines() {
-            // Skip header lines and empty lines
-            if line.contains("Device") || line.trim().is_empty() || line.contains("avg-cpu") {
-                continue;
-            }
-
-            let parts: Vec<&str> = line.split_whitespace().collect();
-
-            // iostat -x output format includes %util as the last column
-            #[allow(clippy::collapsible_if)]
-            if parts.len() >= 14 {
-                if let Some(util_str) = parts.last() {
-                    if let Ok(util_percent) = util_str.parse::<f32>() {
-                        let util_ratio = (util_percent / 100.0).min(1.0);
-                        max_util = max_util.max(util_ratio);
-                    }
-                }
-            }
-        }
-
-        Some(max_util)
+    /// Parse the uptime in milliseconds from /proc/uptime content
+    pub(crate) fn parse_uptime_ms(content: &str) -> Option<u64> {
+        let seconds = content.split_whitespace().next()?.parse::<f64>().ok()?;
+        Some((seconds * 1000.0) as u64)
     }
 
-    /// Estimate disk utilization from /proc/diskstats
-    fn estimate_disk_utilization(disk_stats: &HashMap<String, DiskStat>) -> Option<f32> {
-        if disk_stats.is_empty() {
-            return None;
-        }
-
-        let mut total_utilization = 0.0;
-        let mut count = 0;
-
-        for stat in disk_stats.values() {
-            // Approximate utilization based on sectors read/written
-            let sectors_total = stat.sectors_read + stat.sectors_written;
-            let utilization = (sectors_total as f32 / 1000000.0).min(1.0); // Rough approximation
-            total_utilization += utilization;
-            count += 1;
-        }
-
-        if count > 0 {
-            Some(total_utilization / count as f32)
-        } else {
-            None
-        }
+    /// Check whether a name is all digits, like process directories and CPU indexes
+    fn is_number(name: &str) -> bool {
+        !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
     }
 
     /// Calculate instant dropped packets ratio
-    fn calculate_instant_dropped_packets_ratio(
+    pub(crate) fn calculate_instant_dropped_packets_ratio(
         stats: &HashMap<String, NetworkStats>,
     ) -> Option<f32> {
         let mut total_packets = 0u64;
@@ -589,26 +995,72 @@ impl LinuxSystemMetrics {
 }
 
 impl CpuStat {
-    fn total(&self) -> u64 {
-        self.user + self.nice + self.system + self.idle + self.iowait + self.irq + self.softirq
+    pub(crate) fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// Time spent neither idle nor waiting for I/O
+    pub(crate) fn busy(&self) -> u64 {
+        self.total() - self.idle - self.iowait
     }
 }
 
+/// Disk activity at one point in time
 #[derive(Debug, Clone)]
-struct DiskStat {
-    sectors_read: u64,
-    sectors_written: u64,
+pub(crate) struct DiskSnapshot {
+    /// Uptime when the snapshot was taken
+    uptime_ms: u64,
+    /// Milliseconds every disk has spent doing I/O since boot
+    io_ticks: HashMap<String, u64>,
+}
+
+impl DiskSnapshot {
+    /// Utilization of the busiest disk since an earlier snapshot, or since boot
+    ///
+    /// Disks that appeared after the earlier snapshot are left out.
+    fn utilization_since(&self, previous: Option<&DiskSnapshot>) -> f32 {
+        let previous = previous.filter(|previous| previous.uptime_ms < self.uptime_ms);
+        let elapsed = match previous {
+            Some(previous) => self.uptime_ms - previous.uptime_ms,
+            None => self.uptime_ms,
+        };
+        if elapsed == 0 {
+            return 0.0;
+        }
+
+        self.io_ticks
+            .iter()
+            .filter_map(|(device, &ticks)| {
+                let since = match previous {
+                    Some(previous) => *previous.io_ticks.get(device)?,
+                    None => 0,
+                };
+                Some((ticks.saturating_sub(since) as f32 / elapsed as f32).min(1.0))
+            })
+            .fold(0.0, f32::max)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collector::{Collection, CollectorStatus};
+    use crate::linux::calculator::LinuxProvider;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
 
-    #[tokio::test]
-    async fn test_collect_system_metrics() {
+    #[test]
+    fn test_collect_system_metrics() {
         println!("Testing Linux system metrics collection...");
 
-        let result = LinuxSystemMetrics::collect_system_metrics().await;
+        let result = LinuxSystemMetrics::collect_system_metrics();
         assert!(result.is_ok(), "System metrics collection should succeed");
 
         let metrics = result.unwrap();
@@ -678,6 +1130,14 @@ mod tests {
             );
         }
 
+        // Only hosts with cpufreq and thermal sensors provide these
+        for ratio in [metrics.cpu_throttling_ratio, metrics.thermal_load_ratio]
+            .into_iter()
+            .flatten()
+        {
+            assert!((0.0..=1.0).contains(&ratio), "{ratio}");
+        }
+
         // Count available metrics
         let available_count = [
             metrics.cpu_usage_ratio.is_some(),
@@ -703,54 +1163,54 @@ mod tests {
         );
     }
 
-    #[tokio::test]
-    async fn test_individual_metric_methods() {
+    #[test]
+    fn test_individual_metric_methods() {
         println!("Testing individual metric collection methods...");
 
-        // Test CPU metrics
-        let cpu_result = LinuxSystemMetrics::get_cpu_metrics_consolidated().await;
-        assert!(cpu_result.is_ok(), "CPU metrics should be collectible");
-        let (cpu_usage, cpu_io_wait, cpu_load) = cpu_result.unwrap();
-        println!("CPU metrics: usage={cpu_usage:?}, io_wait={cpu_io_wait:?}, load={cpu_load:?}");
-
-        // Test memory metrics
-        let memory_result = LinuxSystemMetrics::get_memory_metrics_consolidated().await;
-        assert!(
-            memory_result.is_ok(),
-            "Memory metrics should be collectible"
+        let root = Path::new("/");
+
+        // /proc/stat, /proc/loadavg and /proc/meminfo exist on every Linux system
+        let (cpu_usage, cpu_io_wait, cpu_steal) = LinuxSystemMetrics::get_cpu_usage(root).unwrap();
+        assert!((0.0..=1.0).contains(&cpu_usage));
+        assert!((0.0..=1.0).contains(&cpu_io_wait));
+        assert!((0.0..=1.0).contains(&cpu_steal));
+        let (cpu_load, cpus) = LinuxSystemMetrics::get_cpu_load(root).unwrap();
+        assert!(cpus.count > 0.0);
+        println!(
+            "CPU metrics: usage={cpu_usage}, io_wait={cpu_io_wait}, load={cpu_load} over {} CPUs ({})",
+            cpus.count, cpus.source
         );
-        let (memory_usage, memory_pressure) = memory_result.unwrap();
-        println!("Memory metrics: usage={memory_usage:?}, pressure={memory_pressure:?}");
 
-        // Test network metrics
-        let network_result = LinuxSystemMetrics::get_network_metrics_consolidated().await;
-        assert!(
-            network_result.is_ok(),
-            "Network metrics should be collectible"
-        );
-        let network_drop = network_result.unwrap();
-        println!("Network metrics: drop_ratio={network_drop:?}");
-
-        // Test disk metrics
-        let disk_result = LinuxSystemMetrics::get_disk_io_utilization_instant().await;
-        assert!(disk_result.is_ok(), "Disk metrics should be collectible");
-        let disk_io = disk_result.unwrap();
-        println!("Disk metrics: io_utilization={disk_io:?}");
-
-        // Test FD usage
-        let fd_result = LinuxSystemMetrics::get_fd_usage().await;
-        assert!(fd_result.is_ok(), "FD metrics should be collectible");
-        let fd_usage = fd_result.unwrap();
-        println!("FD metrics: usage={fd_usage:?}");
-
-        // Test process count
-        let process_result = LinuxSystemMetrics::get_process_count().await;
-        assert!(
-            process_result.is_ok(),
-            "Process metrics should be collectible"
-        );
-        let process_count = process_result.unwrap();
-        println!("Process metrics: count_ratio={process_count:?}");
+        let memory_usage = LinuxSystemMetrics::get_memory_usage(root).unwrap();
+        assert!((0.0..=1.0).contains(&memory_usage));
+
+        // The others depend on the kernel configuration and the container,
+        // but must explain why they are unavailable
+        let optional = [
+            (
+                "memory_pressure",
+                LinuxSystemMetrics::get_memory_pressure(root),
+            ),
+            (
+                "network",
+                LinuxSystemMetrics::get_network_metrics_consolidated(root),
+            ),
+            ("disk_io", LinuxSystemMetrics::get_disk_io_utilization(root)),
+            ("file_descriptors", LinuxSystemMetrics::get_fd_usage(root)),
+            ("processes", LinuxSystemMetrics::get_process_count(root)),
+        ];
+        for (name, result) in optional {
+            match result {
+                Ok(value) => {
+                    assert!(value >= 0.0, "{name}: {value}");
+                    println!("{name}: {value}");
+                }
+                Err(e) => {
+                    assert!(!e.to_string().is_empty());
+                    println!("{name} unavailable: {e}");
+                }
+            }
+        }
     }
 
     #[test]
@@ -768,6 +1228,19 @@ mod tests {
 
         let total = stat.total();
         assert_eq!(total, 123456 + 789 + 234567 + 890123 + 45678 + 901 + 234);
+
+        // Steal counts towards the total; guest time is already part of user
+        let content = "cpu  600 0 100 200 0 0 0 100 300 0\n";
+        let stat = LinuxSystemMetrics::parse_cpu_stat(content).unwrap();
+        assert_eq!(stat.steal, 100);
+        assert_eq!(stat.total(), 1000);
+
+        // Old kernels stop after softirq
+        let content = "cpu  600 0 100 200 0 0 0\n";
+        assert_eq!(
+            LinuxSystemMetrics::parse_cpu_stat(content).unwrap().steal,
+            0
+        );
     }
 
     #[test]
@@ -816,18 +1289,190 @@ mod tests {
         assert!((pressure - 0.1234).abs() < 0.001);
     }
 
+    #[test]
+    fn test_disk_utilization() {
+        let snapshot = |uptime_ms, content: &str| DiskSnapshot {
+            uptime_ms,
+            io_ticks: LinuxSystemMetrics::parse_disk_stats(content).unwrap(),
+        };
+        let line = |name, io_ticks| format!("8 0 {name} 1 0 8 1 1 0 8 1 0 {io_ticks} 2 0 0 0 0\n");
+
+        let first = snapshot(10_000, &line("sda", 2_000));
+        assert_eq!(first.io_ticks["sda"], 2_000);
+        assert_eq!(first.utilization_since(None), 0.2);
+
+        // sdb appeared after the first snapshot and is left out
+        let second = snapshot(11_000, &(line("sda", 2_250) + &line("sdb", 9_000)));
+        assert_eq!(second.utilization_since(Some(&first)), 0.25);
+        // No time has passed, so fall back to the average since boot
+        assert_eq!(first.utilization_since(Some(&first)), 0.2);
+
+        assert_eq!(
+            LinuxSystemMetrics::parse_uptime_ms("12.34 40.00\n"),
+            Some(12_340)
+        );
+        assert!(LinuxSystemMetrics::parse_disk_stats(&line("sda1", 5)).is_none());
+    }
+
+    #[test]
+    fn test_cpu_throttling() {
+        // No core capped, however far an idle governor clocks it down
+        let root = std::env::temp_dir().join(format!("pwrzv-cpufreq-{}", std::process::id()));
+        for cpu in ["cpu0", "cpu1"] {
+            let cpufreq = root
+                .join("sys/devices/system/cpu")
+                .join(cpu)
+                .join("cpufreq");
+            fs::create_dir_all(&cpufreq).unwrap();
+            fs::write(cpufreq.join("scaling_max_freq"), "3600000\n").unwrap();
+            fs::write(cpufreq.join("cpuinfo_max_freq"), "3600000\n").unwrap();
+        }
+        let idle = LinuxSystemMetrics::get_cpu_throttling(&root);
+
+        // A thermal driver capping both cores at half their maximum
+        for cpu in ["cpu0", "cpu1"] {
+            let cpufreq = root
+                .join("sys/devices/system/cpu")
+                .join(cpu)
+                .join("cpufreq");
+            fs::write(cpufreq.join("scaling_max_freq"), "1800000\n").unwrap();
+        }
+        let capped = LinuxSystemMetrics::get_cpu_throttling(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(idle.unwrap().unwrap() < 0.01);
+        assert_eq!(capped.unwrap(), Some(0.5));
+    }
+
+    #[test]
+    fn test_sysfs_not_present() {
+        // A root with /proc but without /sys
+        let config = CollectionConfig {
+            root: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/linux/proc").into(),
+            ..Default::default()
+        };
+        let (metrics, reports) = LinuxSystemMetrics::collect(&config, &|_| true);
+        assert_eq!(metrics.cpu_throttling_ratio, None);
+        assert_eq!(metrics.thermal_load_ratio, None);
+        for name in ["cpu_frequency", "thermal"] {
+            let report = reports.iter().find(|r| r.name == name).unwrap();
+            assert_eq!(report.status, CollectorStatus::NotPresent);
+        }
+
+        let garbage = std::env::temp_dir().join(format!("pwrzv-thermal-{}", std::process::id()));
+        let zone = garbage.join("sys/class/thermal/thermal_zone0");
+        fs::create_dir_all(&zone).unwrap();
+        fs::write(zone.join("temp"), "warm\n").unwrap();
+        let error = LinuxSystemMetrics::get_thermal_load(&garbage).unwrap_err();
+        fs::remove_dir_all(&garbage).unwrap();
+        assert!(matches!(error, PwrzvError::ParseError { .. }), "{error}");
+        assert!(error.to_string().contains("thermal_zone0/temp"), "{error}");
+    }
+
+    #[test]
+    fn test_first_core_sample() {
+        // Mostly idle since boot, but cpu0 is pinned while the sample is taken
+        let root = std::env::temp_dir().join(format!("pwrzv-cores-{}", std::process::id()));
+        fs::create_dir_all(root.join("proc")).unwrap();
+        let stat = root.join("proc/stat");
+        fs::write(
+            &stat,
+            "cpu  0 0 0 0 0 0 0\ncpu0 100 0 0 900 0 0 0\ncpu1 100 0 0 900 0 0 0\n",
+        )
+        .unwrap();
+        let writer = std::thread::spawn({
+            let stat = stat.clone();
+            move || {
+                std::thread::sleep(CORE_FIRST_INTERVAL / 4);
+                fs::write(
+                    &stat,
+                    "cpu  0 0 0 0 0 0 0\ncpu0 200 0 0 900 0 0 0\ncpu1 105 0 0 995 0 0 0\n",
+                )
+                .unwrap();
+            }
+        });
+        let analysis = LinuxSystemMetrics::get_cpu_cores(&root);
+        writer.join().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let analysis = analysis.unwrap();
+        assert_eq!(analysis.hottest(), Some((0, 1.0)));
+        assert_eq!(analysis.saturated_cores(), 1);
+    }
+
+    #[test]
+    fn test_effective_cpus() {
+        // A pod pinned to 4 of 8 CPUs with a quota of 1.5 CPUs
+        let root = std::env::temp_dir().join(format!("pwrzv-cpus-{}", std::process::id()));
+        let cgroup = root.join("sys/fs/cgroup");
+        let pod = cgroup.join("kubepods/pod1");
+        fs::create_dir_all(root.join("proc/self")).unwrap();
+        fs::create_dir_all(&pod).unwrap();
+        fs::write(root.join("proc/cpuinfo"), "processor\t: 0\n".repeat(8)).unwrap();
+        fs::write(root.join("proc/loadavg"), "3.00 2.00 1.00 4/200 999\n").unwrap();
+        fs::write(root.join("proc/self/cgroup"), "0::/kubepods/pod1\n").unwrap();
+        fs::write(cgroup.join("cgroup.controllers"), "cpuset cpu\n").unwrap();
+        fs::write(cgroup.join("cpuset.cpus.effective"), "0-7\n").unwrap();
+        fs::write(cgroup.join("kubepods/cpuset.cpus.effective"), "2-5\n").unwrap();
+        fs::write(cgroup.join("kubepods/cpu.max"), "max 100000\n").unwrap();
+        fs::write(pod.join("cpu.max"), "150000 100000\n").unwrap();
+
+        let quota = LinuxSystemMetrics::get_cpu_load(&root);
+        fs::remove_file(pod.join("cpu.max")).unwrap();
+        let cpuset = LinuxSystemMetrics::get_cpu_load(&root);
+        fs::write(cgroup.join("kubepods/cpuset.cpus.effective"), "2-x\n").unwrap();
+        let malformed = LinuxSystemMetrics::get_cpu_load(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let (load, cpus) = quota.unwrap();
+        assert_eq!(load, 2.0);
+        assert_eq!((cpus.count, cpus.source), (1.5, CpuCountSource::Quota));
+        let (load, cpus) = cpuset.unwrap();
+        assert_eq!(load, 0.75);
+        assert_eq!((cpus.count, cpus.source), (4.0, CpuCountSource::Cpuset));
+        let error = malformed.unwrap_err();
+        assert!(
+            error.to_string().contains("cpuset.cpus.effective"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_no_subprocesses() {
+        let config = CollectionConfig {
+            allow_commands: false,
+            ..Default::default()
+        };
+        let (_, reports) = LinuxSystemMetrics::collect(&config, &|_| true);
+        for report in &reports {
+            assert!(
+                !report.status.to_string().contains("external commands"),
+                "{report:?}"
+            );
+            if let Some(cost) = report.cost {
+                assert_eq!(cost.subprocesses, 0, "{report:?}");
+            }
+        }
+        assert!(reports[0].status.is_ok());
+    }
+
     #[test]
     fn test_serialization() {
         let metrics = LinuxSystemMetrics {
             cpu_usage_ratio: Some(0.5),
             cpu_io_wait_ratio: Some(0.1),
+            cpu_steal_ratio: Some(0.05),
             cpu_load_ratio: Some(1.2),
+            effective_cpus: None,
             memory_usage_ratio: Some(0.7),
             memory_pressure_ratio: Some(0.2),
             disk_io_utilization: Some(0.3),
             network_dropped_packets_ratio: Some(0.01),
             fd_usage_ratio: Some(0.6),
             process_count_ratio: Some(0.8),
+            cpu_throttling_ratio: Some(0.1),
+            thermal_load_ratio: Some(0.5),
+            cpu_cores: None,
         };
 
         // Test JSON serialization
@@ -845,13 +1490,18 @@ mod tests {
         let metrics = LinuxSystemMetrics {
             cpu_usage_ratio: Some(0.5),
             cpu_io_wait_ratio: Some(0.1),
+            cpu_steal_ratio: Some(0.05),
             cpu_load_ratio: Some(1.2),
+            effective_cpus: None,
             memory_usage_ratio: Some(0.7),
             memory_pressure_ratio: Some(0.2),
             disk_io_utilization: Some(0.3),
             network_dropped_packets_ratio: Some(0.01),
             fd_usage_ratio: Some(0.6),
             process_count_ratio: Some(0.8),
+            cpu_throttling_ratio: Some(0.1),
+            thermal_load_ratio: Some(0.5),
+            cpu_cores: None,
         };
 
         // Test Clone
@@ -864,52 +1514,103 @@ mod tests {
         assert!(debug_str.contains("cpu_usage_ratio"));
     }
 
-    #[tokio::test]
-    async fn test_error_handling() {
-        // Test that methods handle errors gracefully and return None rather than panicking
-
-        // These tests verify the error handling paths, though they may not trigger
-        // actual errors in a normal environment
-
-        let cpu_result = LinuxSystemMetrics::get_cpu_metrics_consolidated().await;
-        assert!(
-            cpu_result.is_ok(),
-            "CPU metrics should handle errors gracefully"
-        );
-
-        let memory_result = LinuxSystemMetrics::get_memory_metrics_consolidated().await;
-        assert!(
-            memory_result.is_ok(),
-            "Memory metrics should handle errors gracefully"
-        );
-
-        let network_result = LinuxSystemMetrics::get_network_metrics_consolidated().await;
+    #[test]
+    fn test_collector_statuses() {
+        let (metrics, reports) =
+            LinuxSystemMetrics::collect(&CollectionConfig::default(), &|_| true);
+        let collection = Collection::new(LinuxProvider::inputs(&metrics), reports);
+
+        let names: Vec<_> = collection
+            .collectors
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        let expected: Vec<_> = COLLECTORS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, expected);
+
+        // Every missing metric belongs to a collector that reports why
+        for collector in &collection.collectors {
+            let complete = collector
+                .metrics
+                .iter()
+                .all(|id| collection.inputs.contains_key(id));
+            assert_eq!(collector.status.is_ok(), complete, "{collector:?}");
+        }
         assert!(
-            network_result.is_ok(),
-            "Network metrics should handle errors gracefully"
+            collection.collectors[0].status.is_ok(),
+            "/proc/stat is always readable"
         );
+    }
 
-        let disk_result = LinuxSystemMetrics::get_disk_io_utilization_instant().await;
-        assert!(
-            disk_result.is_ok(),
-            "Disk metrics should handle errors gracefully"
-        );
+    #[test]
+    fn test_collector_timeout() {
+        let config = CollectionConfig {
+            timeouts: BTreeMap::from([("disk_io".to_string(), 1)]),
+            ..Default::default()
+        };
+        let (metrics, reports) = LinuxSystemMetrics::collect(&config, &|_| true);
+        let disk = reports
+            .iter()
+            .find(|report| report.name == "disk_io")
+            .unwrap();
+        // Reading /proc/diskstats may or may not finish within a millisecond
+        if !disk.status.is_ok() {
+            assert_eq!(disk.status, CollectorStatus::TimedOut { timeout_ms: 1 });
+            assert_eq!(metrics.disk_io_utilization, None);
+        }
+    }
 
-        let fd_result = LinuxSystemMetrics::get_fd_usage().await;
-        assert!(
-            fd_result.is_ok(),
-            "FD metrics should handle errors gracefully"
-        );
+    #[test]
+    fn test_fixture_root() {
+        let config = CollectionConfig {
+            root: concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/linux").into(),
+            ..Default::default()
+        };
+        let (metrics, reports) = LinuxSystemMetrics::collect(&config, &|_| true);
+        let assert_close = |actual: Option<f32>, expected: f32| {
+            let actual = actual.unwrap();
+            assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+        };
 
-        let process_result = LinuxSystemMetrics::get_process_count().await;
-        assert!(
-            process_result.is_ok(),
-            "Process metrics should handle errors gracefully"
+        assert_close(metrics.cpu_usage_ratio, 0.5);
+        assert_close(metrics.cpu_io_wait_ratio, 0.1);
+        assert_close(metrics.cpu_steal_ratio, 0.1);
+        assert_close(metrics.cpu_load_ratio, 0.5);
+        assert_eq!(
+            metrics.effective_cpus,
+            Some(EffectiveCpus {
+                count: 4.0,
+                source: CpuCountSource::Cpuset
+            })
         );
+        assert_close(metrics.memory_usage_ratio, 0.75);
+        assert_close(metrics.memory_pressure_ratio, 0.05);
+        assert_close(metrics.disk_io_utilization, 0.5);
+        assert_close(metrics.network_dropped_packets_ratio, 0.005);
+        assert_close(metrics.fd_usage_ratio, 0.01);
+
+        assert_close(metrics.process_count_ratio, 3.0 / 4096.0);
+        assert_close(metrics.cpu_throttling_ratio, 0.25);
+        assert_close(metrics.thermal_load_ratio, 0.6);
+        let cores = metrics.cpu_cores.unwrap();
+        assert_eq!(cores.hottest(), Some((3, 0.76)));
+        assert_eq!(cores.saturated_cores(), 0);
+        assert_eq!(cores.nodes, BTreeMap::from([(0, 0.28), (1, 0.52)]));
+        assert!(reports.iter().all(|r| r.status.is_ok()), "{reports:?}");
+
+        // Collectors of disabled metrics are not run
+        let enabled = |id: &str| !matches!(id, "process_count" | "disk_io_utilization");
+        let (metrics, reports) = LinuxSystemMetrics::collect(&config, &enabled);
+        assert_eq!(metrics.disk_io_utilization, None);
+        assert_close(metrics.memory_usage_ratio, 0.75);
+        for name in ["disk_io", "processes"] {
+            let report = reports.iter().find(|r| r.name == name).unwrap();
+            assert_eq!(report.status, CollectorStatus::Disabled);
+        }
     }
 
-    #[tokio::test]
-    async fn test_integration_comprehensive() {
+    #[test]
+    fn test_integration_comprehensive() {
         println!("Running comprehensive Linux integration test...");
 
         // Test multiple collection cycles to ensure consistency
@@ -919,7 +1620,7 @@ mod tests {
         for i in 0..3 {
             println!("Collection cycle {}", i + 1);
 
-            match LinuxSystemMetrics::collect_system_metrics().await {
+            match LinuxSystemMetrics::collect_system_metrics() {
                 Ok(metrics) => {
                     if metrics.cpu_usage_ratio.is_some() {
                         metrics_availability[0] += 1;
@@ -959,7 +1660,7 @@ mod tests {
             }
 
             // Small delay between collections
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            std::thread::sleep(Duration::from_millis(100));
         }
 
         println!("Metric availability across 3 cycles:");
@@ -1,2 +1,4 @@
 pub mod calculator;
+pub mod cgroup;
 pub mod metrics;
+pub mod topology;
@@ -0,0 +1,297 @@
+//! Per-core and NUMA node CPU usage
+//!
+//! The aggregate `cpu` line of `/proc/stat` averages over every core, so a
+//! single-threaded task pinning one core of sixteen reads as 6% busy. This
+//! module works on the per-core `cpuN` lines and the CPU lists of the NUMA
+//! nodes in `/sys/devices/system/node` to find the hottest core, count the
+//! saturated ones and compare nodes.
+//!
+//! It also determines how many CPUs this process can actually use, since a
+//! container sees every processor of the host in `/proc/cpuinfo`.
+
+use super::metrics::CpuStat;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Usage from which a core counts as saturated
+pub(crate) const SATURATED_CORE_RATIO: f32 = 0.9;
+
+/// Usage gap between NUMA nodes from which the detailed report notes it
+pub(crate) const NODE_IMBALANCE_NOTE_RATIO: f32 = 0.25;
+
+/// Per-core and per-node CPU usage of a sample
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoreAnalysis {
+    /// Usage of every core keyed by CPU number: (total - idle - iowait) / total
+    pub cores: BTreeMap<usize, f32>,
+
+    /// Usage of every NUMA node keyed by node number
+    ///
+    /// Empty unless at least two nodes have CPUs.
+    pub nodes: BTreeMap<usize, f32>,
+}
+
+impl CoreAnalysis {
+    /// Analyze per-core CPU times since an earlier snapshot, or since boot
+    ///
+    /// Cores missing from the earlier snapshot or whose times did not advance
+    /// are measured since boot.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - CPU times of every core keyed by CPU number
+    /// * `previous` - An earlier snapshot of `current`
+    /// * `nodes` - The CPUs of every NUMA node keyed by node number
+    ///
+    /// # Returns
+    ///
+    /// The analysis, or `None` if no core has any CPU time
+    pub(crate) fn new(
+        current: &BTreeMap<usize, CpuStat>,
+        previous: Option<&BTreeMap<usize, CpuStat>>,
+        nodes: &BTreeMap<usize, BTreeSet<usize>>,
+    ) -> Option<Self> {
+        let intervals: BTreeMap<usize, (u64, u64)> = current
+            .iter()
+            .map(|(&cpu, stat)| {
+                let since = previous
+                    .and_then(|previous| previous.get(&cpu))
+                    .filter(|before| before.total() < stat.total());
+                let interval = match since {
+                    Some(before) => (
+                        stat.busy().saturating_sub(before.busy()),
+                        stat.total() - before.total(),
+                    ),
+                    None => (stat.busy(), stat.total()),
+                };
+                (cpu, interval)
+            })
+            .filter(|(_, (_, total))| *total > 0)
+            .collect();
+        if intervals.is_empty() {
+            return None;
+        }
+
+        let usage = |busy: u64, total: u64| (busy as f32 / total as f32).clamp(0.0, 1.0);
+        let cores = intervals
+            .iter()
+            .map(|(&cpu, &(busy, total))| (cpu, usage(busy, total)))
+            .collect();
+        let mut node_usage: BTreeMap<usize, f32> = nodes
+            .iter()
+            .filter_map(|(&node, cpus)| {
+                let (busy, total) = cpus
+                    .iter()
+                    .filter_map(|cpu| intervals.get(cpu))
+                    .fold((0, 0), |(busy, total), (b, t)| (busy + b, total + t));
+                (total > 0).then(|| (node, usage(busy, total)))
+            })
+            .collect();
+        if node_usage.len() < 2 {
+            node_usage.clear();
+        }
+
+        Some(Self {
+            cores,
+            nodes: node_usage,
+        })
+    }
+
+    /// Get the busiest core and its usage
+    ///
+    /// The lowest-numbered core wins a tie.
+    pub fn hottest(&self) -> Option<(usize, f32)> {
+        self.cores
+            .iter()
+            .map(|(&cpu, &usage)| (cpu, usage))
+            .reduce(|hottest, core| if core.1 > hottest.1 { core } else { hottest })
+    }
+
+    /// Count the cores at or above [`SATURATED_CORE_RATIO`]
+    pub fn saturated_cores(&self) -> usize {
+        self.cores
+            .values()
+            .filter(|&&usage| usage >= SATURATED_CORE_RATIO)
+            .count()
+    }
+
+    /// Get the usage gap between the busiest and the idlest NUMA node
+    ///
+    /// `None` on hosts with a single node.
+    pub fn node_imbalance(&self) -> Option<f32> {
+        let busiest = self.nodes.values().copied().reduce(f32::max)?;
+        let idlest = self.nodes.values().copied().reduce(f32::min)?;
+        Some(busiest - idlest)
+    }
+
+    /// Describe the analysis for the detailed report
+    ///
+    /// Only worth a note when a core is saturated or the NUMA nodes are at
+    /// least [`NODE_IMBALANCE_NOTE_RATIO`] apart.
+    pub(crate) fn notes(&self) -> Vec<String> {
+        let imbalance = self
+            .node_imbalance()
+            .filter(|&imbalance| imbalance >= NODE_IMBALANCE_NOTE_RATIO);
+        if self.saturated_cores() == 0 && imbalance.is_none() {
+            return Vec::new();
+        }
+
+        let mut notes = Vec::new();
+        if let Some((cpu, usage)) = self.hottest() {
+            notes.push(format!(
+                "Hottest core: cpu{cpu} at {:.1}%; saturated cores: {} of {}",
+                usage * 100.0,
+                self.saturated_cores(),
+                self.cores.len()
+            ));
+        }
+        if let Some(imbalance) = imbalance {
+            let nodes: Vec<_> = self
+                .nodes
+                .iter()
+                .map(|(node, usage)| format!("node{node} {:.1}%", usage * 100.0))
+                .collect();
+            notes.push(format!(
+                "NUMA node usage: {}; imbalance {:.1} points",
+                nodes.join(", "),
+                imbalance * 100.0
+            ));
+        }
+        notes
+    }
+}
+
+/// Parse the `cpu.max` file of a cgroup into a CPU count
+///
+/// # Returns
+///
+/// Quota divided by period, or `None` if the file is malformed or holds no
+/// limit (`max`)
+pub(crate) fn parse_cpu_max(content: &str) -> Option<f32> {
+    let mut fields = content.split_whitespace();
+    let quota: u64 = fields.next()?.parse().ok()?;
+    let period: u64 = fields
+        .next()
+        .map_or(Some(100_000), |period| period.parse().ok())?;
+    (period > 0).then(|| quota as f32 / period as f32)
+}
+
+/// Number of CPUs a CPU list may name, well above the kernel's `NR_CPUS` limit
+const MAX_CPUS: usize = 65_536;
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`
+///
+/// An empty list, as memory-only NUMA nodes have, is an empty set. Lists
+/// naming a CPU number of [`MAX_CPUS`] or more are malformed.
+pub(crate) fn parse_cpu_list(content: &str) -> Option<BTreeSet<usize>> {
+    let mut cpus = BTreeSet::new();
+    for range in content.trim().split(',').filter(|range| !range.is_empty()) {
+        match range.split_once('-') {
+            Some((first, last)) => {
+                let first: usize = first.parse().ok()?;
+                let last: usize = last.parse().ok()?;
+                if first > last || last >= MAX_CPUS {
+                    return None;
+                }
+                cpus.extend(first..=last);
+            }
+            None => {
+                cpus.insert(range.parse().ok().filter(|&cpu| cpu < MAX_CPUS)?);
+            }
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linux::metrics::LinuxSystemMetrics;
+
+    fn cores(content: &str) -> BTreeMap<usize, CpuStat> {
+        LinuxSystemMetrics::parse_core_stats(content)
+    }
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11\n"),
+            Some(BTreeSet::from([0, 1, 2, 3, 8, 10, 11]))
+        );
+        assert_eq!(parse_cpu_list("\n"), Some(BTreeSet::new()));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("0-x"), None);
+        assert_eq!(parse_cpu_list("0-4294967295"), None);
+        assert_eq!(parse_cpu_list("70000"), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_max() {
+        assert_eq!(parse_cpu_max("150000 100000\n"), Some(1.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+    }
+
+    #[test]
+    fn test_core_analysis() {
+        let before = cores(
+            "cpu  0 0 0 0 0 0 0\n\
+             cpu0 100 0 0 100 0 0 0\n\
+             cpu1 100 0 0 100 0 0 0\n\
+             cpu2 100 0 0 100 0 0 0\n\
+             cpu3 100 0 0 100 0 0 0\n",
+        );
+        let after = cores(
+            "cpu  0 0 0 0 0 0 0\n\
+             cpu0 195 0 0 105 0 0 0\n\
+             cpu1 110 0 0 190 0 0 0\n\
+             cpu2 200 0 0 100 0 0 0\n\
+             cpu3 120 0 0 170 10 0 0\n",
+        );
+        let nodes = BTreeMap::from([
+            (0, BTreeSet::from([0, 1])),
+            (1, BTreeSet::from([2, 3])),
+            (2, BTreeSet::new()),
+        ]);
+
+        let analysis = CoreAnalysis::new(&after, Some(&before), &nodes).unwrap();
+        assert_eq!(analysis.hottest(), Some((2, 1.0)));
+        assert_eq!(analysis.saturated_cores(), 2);
+        assert_eq!(analysis.nodes, BTreeMap::from([(0, 0.525), (1, 0.6)]));
+        assert!((analysis.node_imbalance().unwrap() - 0.075).abs() < 1e-6);
+        // The nodes are too close to be worth a note
+        assert_eq!(
+            analysis.notes(),
+            ["Hottest core: cpu2 at 100.0%; saturated cores: 2 of 4"]
+        );
+
+        // Without an earlier snapshot or a second node, and nothing saturated
+        let single = BTreeMap::from([(0, BTreeSet::from([0, 1, 2, 3]))]);
+        let analysis = CoreAnalysis::new(&before, None, &single).unwrap();
+        assert_eq!(analysis.hottest(), Some((0, 0.5)));
+        assert_eq!(analysis.node_imbalance(), None);
+        assert!(analysis.notes().is_empty());
+
+        // Unbalanced nodes are noted even without a saturated core
+        let unbalanced = cores(
+            "cpu  0 0 0 0 0 0 0\n\
+             cpu0 180 0 0 120 0 0 0\n\
+             cpu1 180 0 0 120 0 0 0\n\
+             cpu2 110 0 0 190 0 0 0\n\
+             cpu3 110 0 0 190 0 0 0\n",
+        );
+        let analysis = CoreAnalysis::new(&unbalanced, Some(&before), &nodes).unwrap();
+        assert_eq!(analysis.saturated_cores(), 0);
+        assert_eq!(
+            analysis.notes(),
+            [
+                "Hottest core: cpu0 at 80.0%; saturated cores: 0 of 4",
+                "NUMA node usage: node0 80.0%, node1 10.0%; imbalance 70.0 points",
+            ]
+        );
+
+        assert_eq!(
+            CoreAnalysis::new(&cores("cpu  1 0 0 0\n"), None, &nodes),
+            None
+        );
+    }
+}
@@ -1,49 +1,10 @@
 use super::metrics::MacSystemMetrics;
 use crate::PowerReserveMeterProvider;
-use crate::error::PwrzvResult;
-use crate::sigmoid::{SigmoidFn, get_sigmoid_config};
-use std::collections::HashMap;
-
-// ================================
-// The core parameters of the macOS power reserve calculator
-// ================================
-
-/// Get CPU usage configuration (env: PWRZV_MACOS_CPU_USAGE_MIDPOINT, PWRZV_MACOS_CPU_USAGE_STEEPNESS)
-fn get_cpu_usage_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_CPU_USAGE", 0.60, 8.0)
-}
-
-/// Get CPU load configuration (env: PWRZV_MACOS_CPU_LOAD_MIDPOINT, PWRZV_MACOS_CPU_LOAD_STEEPNESS)
-fn get_cpu_load_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_CPU_LOAD", 1.2, 5.0)
-}
-
-/// Get memory usage configuration (env: PWRZV_MACOS_MEMORY_USAGE_MIDPOINT, PWRZV_MACOS_MEMORY_USAGE_STEEPNESS)
-fn get_memory_usage_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_MEMORY_USAGE", 0.85, 20.0)
-}
-
-/// Get memory compressed configuration (env: PWRZV_MACOS_MEMORY_COMPRESSED_MIDPOINT, PWRZV_MACOS_MEMORY_COMPRESSED_STEEPNESS)
-fn get_memory_compressed_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_MEMORY_COMPRESSED", 0.60, 15.0)
-}
-
-/// Get network dropped packets configuration (env: PWRZV_MACOS_NETWORK_DROPPED_MIDPOINT, PWRZV_MACOS_NETWORK_DROPPED_STEEPNESS)
-fn get_network_dropped_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_NETWORK_DROPPED", 0.02, 100.0)
-}
-
-/// Get file descriptor configuration (env: PWRZV_MACOS_FD_MIDPOINT, PWRZV_MACOS_FD_STEEPNESS)
-fn get_fd_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_FD", 0.90, 30.0)
-}
-
-/// Get process count configuration (env: PWRZV_MACOS_PROCESS_MIDPOINT, PWRZV_MACOS_PROCESS_STEEPNESS)
-fn get_process_config() -> SigmoidFn {
-    get_sigmoid_config("PWRZV_MACOS_PROCESS", 0.80, 12.0)
-}
-
-// ================================
+use crate::collector::Collection;
+use crate::config::PwrzvConfig;
+use crate::platform::MACOS_METRICS as METRICS;
+use crate::report::score_inputs;
+use std::collections::{BTreeMap, HashMap};
 
 /// macOS power reserve provider
 
@@ -51,25 +12,23 @@ fn get_process_config() -> SigmoidFn {
 pub(crate) struct MacProvider;
 
 impl PowerReserveMeterProvider for MacProvider {
-    async fn get_power_reserve_level(&self) -> PwrzvResult<f32> {
-        let metrics = MacSystemMetrics::collect_system_metrics().await?;
-
-        let (level, _) = Self::calculate(&metrics)?;
-        Ok(level)
+    fn collect(&self, config: &PwrzvConfig) -> Collection {
+        let enabled = |id: &str| config.metric_enabled("macos", id);
+        let (metrics, collectors) = MacSystemMetrics::collect(&config.collection, &enabled);
+        Collection::new(Self::inputs(&metrics), collectors)
     }
 
-    async fn get_power_reserve_level_with_details(
+    fn calculate_inputs(
         &self,
-    ) -> PwrzvResult<(f32, HashMap<String, f32>)> {
-        let metrics = MacSystemMetrics::collect_system_metrics().await?;
-
-        let (level, details) = Self::calculate(&metrics)?;
-        Ok((level, details))
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        Self::calculate_with(inputs, config)
     }
 }
 
 impl MacProvider {
-    /// Calculate the power reserve level and details
+    /// Calculate the power reserve level and details with the process-wide configuration
     ///
     /// # Arguments
     ///
@@ -79,78 +38,42 @@ impl MacProvider {
     ///
     /// * `level` - The power reserve level
     /// * `details` - The details of the power reserve level
-    fn calculate(metrics: &MacSystemMetrics) -> PwrzvResult<(f32, HashMap<String, f32>)> {
-        let mut details = HashMap::new();
-        let mut available_scores = Vec::new();
-
-        // Process each metric if available
-        if let Some(value) = metrics.cpu_usage_ratio {
-            let score = get_cpu_usage_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("CPU Usage: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.cpu_load_ratio {
-            let score = get_cpu_load_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("CPU Load: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.memory_usage_ratio {
-            let score = get_memory_usage_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Memory Usage: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.memory_compressed_ratio {
-            let score = get_memory_compressed_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Memory Compressed: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.network_dropped_packets_ratio {
-            let score = get_network_dropped_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Network Dropped: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.fd_usage_ratio {
-            let score = get_fd_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("File Descriptors: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        if let Some(value) = metrics.process_count_ratio {
-            let score = get_process_config().evaluate(value);
-            let n = Self::five_point_scale_with_decimal(score);
-            details.insert(format!("Process Count: {value}, (Score: {n})"), n);
-            available_scores.push(n);
-        }
-
-        // Find the minimum score from available metrics (bottleneck determines power reserve)
-        if let Some(&final_score) = available_scores
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-        {
-            return Ok((final_score, details));
-        }
+    #[cfg(test)]
+    fn calculate(
+        metrics: &MacSystemMetrics,
+    ) -> crate::error::PwrzvResult<(f32, HashMap<String, f32>)> {
+        Ok(Self::calculate_with(
+            &Self::inputs(metrics),
+            &crate::config::global(),
+        ))
+    }
 
-        // If no metrics are available, return a default level
-        Ok((3.0, details))
+    /// Calculate the power reserve level from raw inputs keyed by metric ID
+    ///
+    /// Metrics missing from `inputs` or disabled in `config` are skipped, and
+    /// scores are combined with the configured weights. If no metrics are
+    /// available, a default level of 3.0 is returned.
+    pub(crate) fn calculate_with(
+        inputs: &BTreeMap<String, f32>,
+        config: &PwrzvConfig,
+    ) -> (f32, HashMap<String, f32>) {
+        score_inputs("macos", &METRICS, inputs, config)
     }
-    /// Convert sigmoid score to 5-point scale with decimal precision
-    /// [0, 1.0] -> [5.0, 0.0]
-    fn five_point_scale_with_decimal(score: f32) -> f32 {
-        let score = 5.0 * (1.0 - score);
-        // Retain 4 decimal places for precision
-        let factor = 10_000f32;
-        (score * factor).round() / factor
+
+    /// Get the available raw metric values keyed by metric ID
+    pub(crate) fn inputs(metrics: &MacSystemMetrics) -> BTreeMap<String, f32> {
+        [
+            ("cpu_usage", metrics.cpu_usage_ratio),
+            ("cpu_load", metrics.cpu_load_ratio),
+            ("memory_usage", metrics.memory_usage_ratio),
+            ("memory_compressed", metrics.memory_compressed_ratio),
+            ("network_dropped", metrics.network_dropped_packets_ratio),
+            ("file_descriptors", metrics.fd_usage_ratio),
+            ("process_count", metrics.process_count_ratio),
+        ]
+        .into_iter()
+        .filter_map(|(id, value)| Some((id.to_string(), value?)))
+        .collect()
     }
 }
 
@@ -193,6 +116,13 @@ mod tests {
         assert!((0.0..=5.0).contains(&level));
     }
 
+    #[test]
+    fn test_metric_ids_match_labels() {
+        for spec in &METRICS {
+            assert_eq!(crate::report::metric_id(spec.label), spec.id);
+        }
+    }
+
     #[test]
     fn test_calculate_with_no_metrics() {
         let metrics = MacSystemMetrics {
@@ -1,6 +1,11 @@
+use crate::collector::{self, CollectorReport, CollectorSpec, Plan};
+use crate::config::CollectionConfig;
 use crate::error::{PwrzvError, PwrzvResult};
 use serde::{Deserialize, Serialize};
+use std::process::Command;
 use std::str;
+use std::thread::{self, ScopedJoinHandle};
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MacSystemMetrics {
@@ -33,24 +38,68 @@ pub struct MacSystemMetrics {
     pub process_count_ratio: Option<f32>,
 }
 
-impl MacSystemMetrics {
-    /// Collect all system metrics using optimized consolidated calls
-    pub async fn collect_system_metrics() -> PwrzvResult<Self> {
-        // Execute all metrics collection in parallel using tokio::join!
-        let (cpu_result, memory_result, network_result, system_resource_result) = tokio::join!(
-            Self::get_cpu_metrics_consolidated(),
-            Self::get_memory_metrics_consolidated(),
-            Self::get_network_metrics_consolidated(),
-            Self::get_system_resource_metrics_consolidated()
-        );
+/// macOS collectors and the metrics each one provides
+pub(crate) const COLLECTORS: [CollectorSpec; 4] = [
+    ("cpu", &["cpu_usage", "cpu_load"]),
+    ("memory", &["memory_usage", "memory_compressed"]),
+    ("network", &["network_dropped"]),
+    ("system_resources", &["file_descriptors", "process_count"]),
+];
+
+/// Wait for a scoped thread, propagating its panic
+fn join<T>(handle: ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
 
-        // Extract results, using None for any failed metrics
+impl MacSystemMetrics {
+    /// Collect all system metrics with the status of every collector
+    ///
+    /// Collectors run in parallel, each with its own timeout from `config`.
+    /// Collectors none of whose metrics is `enabled`, or over the budget, are
+    /// skipped.
+    ///
+    /// # Returns
+    ///
+    /// The metrics, `None` where a collector failed, and one report per
+    /// collector in [`COLLECTORS`] order explaining any failure.
+    pub(crate) fn collect(
+        config: &CollectionConfig,
+        enabled: &dyn Fn(&str) -> bool,
+    ) -> (Self, Vec<CollectorReport>) {
+        let plan = Plan::new(config, &COLLECTORS, enabled);
+        let [cpu, memory, network, system_resources] = COLLECTORS;
+        let cpu_timeout = config.timeout(cpu.0);
+        let memory_timeout = config.timeout(memory.0);
+        let network_timeout = config.timeout(network.0);
+        let system_resource_timeout = config.timeout(system_resources.0);
+
+        let cpu = collector::spawn(cpu, &plan, move || {
+            Self::get_cpu_metrics_consolidated(cpu_timeout)
+        });
+        let memory = collector::spawn(memory, &plan, move || {
+            Self::get_memory_metrics_consolidated(memory_timeout)
+        });
+        let network = collector::spawn(network, &plan, move || {
+            Self::get_network_metrics_consolidated(network_timeout)
+        });
+        let system_resources = collector::spawn(system_resources, &plan, move || {
+            Self::get_system_resource_metrics_consolidated(system_resource_timeout)
+        });
+
+        let (cpu_result, cpu_report) = cpu.wait();
+        let (memory_result, memory_report) = memory.wait();
+        let (network_result, network_report) = network.wait();
+        let (system_resource_result, system_resource_report) = system_resources.wait();
+
+        // Metrics a collector could not parse are None and reported as missing
         let (cpu_usage_ratio, cpu_load_ratio) = cpu_result.unwrap_or((None, None));
         let (memory_usage_ratio, memory_compressed_ratio) = memory_result.unwrap_or((None, None));
-        let network_dropped_packets_ratio = network_result.unwrap_or(None);
+        let network_dropped_packets_ratio = network_result.flatten();
         let (fd_usage_ratio, process_count_ratio) = system_resource_result.unwrap_or((None, None));
 
-        Ok(MacSystemMetrics {
+        let metrics = MacSystemMetrics {
             cpu_usage_ratio,
             cpu_load_ratio,
             memory_usage_ratio,
@@ -58,7 +107,14 @@ impl MacSystemMetrics {
             network_dropped_packets_ratio,
             fd_usage_ratio,
             process_count_ratio,
-        })
+        };
+        let reports = vec![
+            cpu_report,
+            memory_report,
+            network_report,
+            system_resource_report,
+        ];
+        (metrics, reports)
     }
 
     /// Get CPU metrics with consolidated system calls
@@ -75,16 +131,20 @@ impl MacSystemMetrics {
     /// # Performance
     ///
     /// This approach is optimized for macOS system limitations.
-    pub(crate) async fn get_cpu_metrics_consolidated() -> PwrzvResult<(Option<f32>, Option<f32>)> {
+    pub(crate) fn get_cpu_metrics_consolidated(
+        timeout: Duration,
+    ) -> PwrzvResult<(Option<f32>, Option<f32>)> {
         // Execute CPU usage and load/core count in parallel
-        let (top_result, sysctl_result) = tokio::join!(
-            tokio::process::Command::new("top")
-                .args(["-l", "1", "-n", "0"])
-                .output(),
-            tokio::process::Command::new("sysctl")
-                .args(["vm.loadavg", "hw.ncpu"])
-                .output()
-        );
+        let (top_result, sysctl_result) = thread::scope(|scope| {
+            let top = collector::spawn_scoped(scope, || {
+                collector::command_output(Command::new("top").args(["-l", "1", "-n", "0"]), timeout)
+            });
+            let sysctl = collector::command_output(
+                Command::new("sysctl").args(["vm.loadavg", "hw.ncpu"]),
+                timeout,
+            );
+            (join(top), sysctl)
+        });
 
         let mut cpu_usage: Option<f32> = None;
         let mut load_ratio: Option<f32> = None;
@@ -92,7 +152,7 @@ impl MacSystemMetrics {
         // Get CPU usage from top command
         #[allow(clippy::collapsible_if)]
         if let Ok(top_output) = top_result {
-            if let Ok(top_str) = str::from_utf8(&top_output.stdout) {
+            if let Ok(top_str) = str::from_utf8(&top_output) {
                 cpu_usage = Self::parse_top_cpu_usage(top_str);
             }
         }
@@ -100,7 +160,7 @@ impl MacSystemMetrics {
         // Get CPU load from sysctl
         #[allow(clippy::collapsible_if)]
         if let Ok(sysctl_output) = sysctl_result {
-            if let Ok(sysctl_str) = str::from_utf8(&sysctl_output.stdout) {
+            if let Ok(sysctl_str) = str::from_utf8(&sysctl_output) {
                 load_ratio = Self::parse_sysctl_load_and_cores(sysctl_str);
             }
         }
@@ -109,17 +169,16 @@ impl MacSystemMetrics {
     }
 
     /// Get memory metrics with consolidated calls (usage + compressed in single vm_stat call)
-    pub(crate) async fn get_memory_metrics_consolidated() -> PwrzvResult<(Option<f32>, Option<f32>)>
-    {
-        let output = tokio::process::Command::new("vm_stat")
-            .output()
-            .await
-            .map_err(|e| {
-                PwrzvError::resource_access_error(&format!("Failed to execute vm_stat: {e}"))
-            })?;
-
-        let output_str = str::from_utf8(&output.stdout).map_err(|e| {
-            PwrzvError::parse_error(&format!("Failed to parse vm_stat output: {e}"))
+    pub(crate) fn get_memory_metrics_consolidated(
+        timeout: Duration,
+    ) -> PwrzvResult<(Option<f32>, Option<f32>)> {
+        let output = collector::command_output(&mut Command::new("vm_stat"), timeout)
+            .map_err(|e| e.with_metric("memory_usage"))?;
+
+        let output_str = str::from_utf8(&output).map_err(|e| {
+            PwrzvError::parse_error(&e.to_string())
+                .context("vm_stat output")
+                .with_metric("memory_usage")
         })?;
 
         let mut pages_active = 0u64;
@@ -176,18 +235,14 @@ impl MacSystemMetrics {
     }
 
     /// Get network metrics with consolidated calls (drop ratio in single netstat call)
-    pub(crate) async fn get_network_metrics_consolidated() -> PwrzvResult<Option<f32>> {
-        let output = tokio::process::Command::new("netstat")
-            .arg("-i")
-            .arg("-b")
-            .output()
-            .await
-            .map_err(|e| {
-                PwrzvError::resource_access_error(&format!("Failed to execute netstat: {e}"))
-            })?;
-
-        let output_str = str::from_utf8(&output.stdout).map_err(|e| {
-            PwrzvError::parse_error(&format!("Failed to parse netstat output: {e}"))
+    pub(crate) fn get_network_metrics_consolidated(timeout: Duration) -> PwrzvResult<Option<f32>> {
+        let output = collector::command_output(Command::new("netstat").args(["-i", "-b"]), timeout)
+            .map_err(|e| e.with_metric("network_dropped"))?;
+
+        let output_str = str::from_utf8(&output).map_err(|e| {
+            PwrzvError::parse_error(&e.to_string())
+                .context("netstat output")
+                .with_metric("network_dropped")
         })?;
 
         let mut total_packets = 0u64;
@@ -240,21 +295,24 @@ impl MacSystemMetrics {
     }
 
     /// Get system resource metrics with consolidated calls (fd usage + process count in optimized calls)
-    pub(crate) async fn get_system_resource_metrics_consolidated()
-    -> PwrzvResult<(Option<f32>, Option<f32>)> {
+    pub(crate) fn get_system_resource_metrics_consolidated(
+        timeout: Duration,
+    ) -> PwrzvResult<(Option<f32>, Option<f32>)> {
         // Execute all three commands in parallel for maximum efficiency
-        let (ps_result, sysctl_result, fd_limit_result) = tokio::join!(
+        let (ps_result, sysctl_result, fd_limit_result) = thread::scope(|scope| {
             // Get process count from ps
-            tokio::process::Command::new("ps").arg("ax").output(),
+            let ps = collector::spawn_scoped(scope, || {
+                collector::command_output(Command::new("ps").arg("ax"), timeout)
+            });
             // Get system process limit from sysctl
-            tokio::process::Command::new("sysctl")
-                .arg("kern.maxproc")
-                .output(),
+            let sysctl = collector::spawn_scoped(scope, || {
+                collector::command_output(Command::new("sysctl").arg("kern.maxproc"), timeout)
+            });
             // Get system-wide file descriptor limit from sysctl
-            tokio::process::Command::new("sysctl")
-                .arg("kern.maxfiles")
-                .output()
-        );
+            let fd_limit =
+                collector::command_output(Command::new("sysctl").arg("kern.maxfiles"), timeout);
+            (join(ps), join(sysctl), fd_limit)
+        });
 
         // Parse process count and calculate ratios
         let mut fd_usage_ratio: Option<f32> = None;
@@ -263,13 +321,13 @@ impl MacSystemMetrics {
         // Get process count and file descriptor metrics
         #[allow(clippy::collapsible_if)]
         if let Ok(ps_output) = ps_result {
-            if let Ok(ps_str) = str::from_utf8(&ps_output.stdout) {
+            if let Ok(ps_str) = str::from_utf8(&ps_output) {
                 let process_count = ps_str.lines().count().saturating_sub(1) as u32;
 
                 // Get file descriptor limits and usage
                 #[allow(clippy::collapsible_if)]
                 if let Ok(fd_limit_output) = fd_limit_result {
-                    if let Ok(fd_limit_str) = str::from_utf8(&fd_limit_output.stdout) {
+                    if let Ok(fd_limit_str) = str::from_utf8(&fd_limit_output) {
                         // Parse sysctl output: "kern.maxfiles: 245760"
                         #[allow(clippy::collapsible_if)]
                         if let Some(colon_pos) = fd_limit_str.find(':') {
@@ -277,21 +335,17 @@ impl MacSystemMetrics {
                                 fd_limit_str[colon_pos + 1..].trim().parse::<u32>()
                             {
                                 // Get actual open file descriptors using lsof
-                                #[allow(clippy::collapsible_if)]
-                                if let Ok(lsof_output) = tokio::process::Command::new("lsof")
-                                    .arg("-n") // Don't resolve hostnames
-                                    .arg("-P") // Don't resolve port names
-                                    .output()
-                                    .await
-                                {
-                                    if lsof_output.status.success() {
-                                        // Use lossy conversion to handle non-UTF8 characters in file paths
-                                        let lsof_str = String::from_utf8_lossy(&lsof_output.stdout);
-                                        // Subtract 1 for header line
-                                        let actual_fds = lsof_str.lines().count().saturating_sub(1);
-                                        fd_usage_ratio =
-                                            Some((actual_fds as f32 / fd_limit as f32).min(1.0));
-                                    }
+                                // (-n: don't resolve hostnames, -P: don't resolve port names)
+                                if let Ok(lsof_output) = collector::command_output(
+                                    Command::new("lsof").args(["-n", "-P"]),
+                                    timeout,
+                                ) {
+                                    // Use lossy conversion to handle non-UTF8 characters in file paths
+                                    let lsof_str = String::from_utf8_lossy(&lsof_output);
+                                    // Subtract 1 for header line
+                                    let actual_fds = lsof_str.lines().count().saturating_sub(1);
+                                    fd_usage_ratio =
+                                        Some((actual_fds as f32 / fd_limit as f32).min(1.0));
                                 }
                             }
                         }
@@ -301,7 +355,7 @@ impl MacSystemMetrics {
                 // Get process count ratio
                 #[allow(clippy::collapsible_if)]
                 if let Ok(sysctl_output) = sysctl_result {
-                    if let Ok(sysctl_str) = str::from_utf8(&sysctl_output.stdout) {
+                    if let Ok(sysctl_str) = str::from_utf8(&sysctl_output) {
                         #[allow(clippy::collapsible_if)]
                         if let Some(colon_pos) = sysctl_str.find(':') {
                             if let Ok(max_processes) =
@@ -325,14 +379,16 @@ impl MacSystemMetrics {
         if let Some(last_part) = parts.last() {
             let number_str = last_part.trim_end_matches('.');
             number_str.parse::<u64>().map_err(|e| {
-                PwrzvError::parse_error(&format!(
-                    "Failed to parse number from vm_stat line '{line}': {e}"
-                ))
+                PwrzvError::parse_error(&format!("invalid page count: {e}"))
+                    .context("vm_stat output")
+                    .with_metric("memory_usage")
+                    .with_line(line)
             })
         } else {
-            Err(PwrzvError::parse_error(&format!(
-                "No parseable parts found in vm_stat line: '{line}'"
-            )))
+            Err(PwrzvError::parse_error("no page count")
+                .context("vm_stat output")
+                .with_metric("memory_usage")
+                .with_line(line))
         }
     }
 
@@ -435,64 +491,45 @@ impl MacSystemMetrics {
 mod tests {
     use super::*;
 
-    #[tokio::test]
-    async fn test_metrics_fetch() {
+    #[test]
+    fn test_metrics_fetch() {
         println!("Testing real macOS system metrics with parallel execution...");
 
-        // Execute all metrics collection in parallel using tokio::join!
-        let (cpu_result, memory_result, network_result, system_resource_result) = tokio::join!(
-            MacSystemMetrics::get_cpu_metrics_consolidated(),
-            MacSystemMetrics::get_memory_metrics_consolidated(),
-            MacSystemMetrics::get_network_metrics_consolidated(),
-            MacSystemMetrics::get_system_resource_metrics_consolidated()
-        );
+        // Every collector runs on its own thread
+        let (metrics, reports) = MacSystemMetrics::collect(&CollectionConfig::default(), &|_| true);
+        println!("Metrics: {metrics:?}");
+        assert_eq!(reports.len(), COLLECTORS.len());
+        for report in &reports {
+            println!("{}: {}", report.name, report.status);
+        }
 
-        // Verify and print CPU metrics
-        let cpu_metrics = cpu_result.unwrap();
-        println!("CPU metrics: {cpu_metrics:?}");
-        // CPU usage can be None if parsing fails, which is valid behavior
-        if let Some(cpu_usage) = cpu_metrics.0 {
+        // Metrics can be None if parsing fails, which is valid behavior
+        if let Some(cpu_usage) = metrics.cpu_usage_ratio {
             assert!((0.0..=1.0).contains(&cpu_usage));
         }
-        // Load ratio can be None if parsing fails, which is valid behavior
-        if let Some(load_ratio) = cpu_metrics.1 {
+        if let Some(load_ratio) = metrics.cpu_load_ratio {
             assert!(load_ratio >= 0.0); // Load ratio can exceed 1.0
         }
-
-        // Verify and print memory metrics
-        let memory_metrics = memory_result.unwrap();
-        println!("Memory metrics: {memory_metrics:?}");
-        // Memory metrics can be None if parsing fails, which is valid behavior
-        if let Some(memory_usage) = memory_metrics.0 {
+        if let Some(memory_usage) = metrics.memory_usage_ratio {
             assert!((0.0..=1.0).contains(&memory_usage));
         }
-        if let Some(memory_compressed) = memory_metrics.1 {
+        if let Some(memory_compressed) = metrics.memory_compressed_ratio {
             assert!((0.0..=1.0).contains(&memory_compressed));
         }
-
-        // Verify and print network metrics
-        let network_metrics = network_result.unwrap();
-        println!("Network metrics: {network_metrics:?}");
-        // Network metrics can be None if parsing fails, which is valid behavior
-        if let Some(dropped_ratio) = network_metrics {
+        if let Some(dropped_ratio) = metrics.network_dropped_packets_ratio {
             assert!((0.0..=1.0).contains(&dropped_ratio));
         }
-
-        // Verify and print system resource metrics
-        let system_resource_metrics = system_resource_result.unwrap();
-        println!("System resource metrics: {system_resource_metrics:?}");
-        // System resource metrics can be None if parsing fails, which is valid behavior
-        if let Some(fd_usage) = system_resource_metrics.0 {
+        if let Some(fd_usage) = metrics.fd_usage_ratio {
             assert!((0.0..=1.0).contains(&fd_usage));
         }
-        if let Some(process_count_ratio) = system_resource_metrics.1 {
+        if let Some(process_count_ratio) = metrics.process_count_ratio {
             assert!(process_count_ratio >= 0.0); // Process count ratio can exceed 1.0
         }
     }
 
-    #[tokio::test]
-    async fn test_get_memory_metrics_consolidated() {
-        let result = MacSystemMetrics::get_memory_metrics_consolidated().await;
+    #[test]
+    fn test_get_memory_metrics_consolidated() {
+        let result = MacSystemMetrics::get_memory_metrics_consolidated(collector::DEFAULT_TIMEOUT);
         assert!(result.is_ok());
 
         let (memory_usage, memory_compressed) = result.unwrap();
@@ -511,9 +548,9 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_get_cpu_metrics_consolidated() {
-        let result = MacSystemMetrics::get_cpu_metrics_consolidated().await;
+    #[test]
+    fn test_get_cpu_metrics_consolidated() {
+        let result = MacSystemMetrics::get_cpu_metrics_consolidated(collector::DEFAULT_TIMEOUT);
         assert!(result.is_ok());
 
         let (cpu_usage, load_ratio) = result.unwrap();
@@ -532,9 +569,9 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_get_network_metrics_consolidated() {
-        let result = MacSystemMetrics::get_network_metrics_consolidated().await;
+    #[test]
+    fn test_get_network_metrics_consolidated() {
+        let result = MacSystemMetrics::get_network_metrics_consolidated(collector::DEFAULT_TIMEOUT);
         assert!(result.is_ok());
 
         let dropped_ratio = result.unwrap();
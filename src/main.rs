@@ -5,18 +5,33 @@
 use std::env;
 use std::process;
 
+use clap::builder::TypedValueParser;
 use clap::{Arg, ArgMatches, Command};
+use pwrzv::alert::AlertEngine;
+use pwrzv::calibrate::{self, CalibrationTarget};
+use pwrzv::collector::CollectorCost;
+use pwrzv::config::{self, PwrzvConfig};
+use pwrzv::daemon::{self, DaemonClient};
+use pwrzv::store::{self, HistoryStore, Resolution, RetentionPolicy};
 use pwrzv::{
-    PwrzvError, check_platform, get_platform_name, get_power_reserve_level_direct,
-    get_power_reserve_level_with_details_direct,
+    PowerReserveReport, PwrzvError, Sampler, UnitPowerReserve, check_platform, get_platform_name,
+    get_power_reserve_level_direct, get_power_reserve_report, get_running_units_power_reserve,
+    get_unit_power_reserve_level_with_details, level_description,
 };
+use pwrzv::{http, plot, timespec, trace};
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
 use std::time::Duration;
 use tokio::time::sleep;
+use tokio_stream::StreamExt;
 
 /// Application version
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Default listen address of the HTTP API
+const DEFAULT_HTTP_BIND: &str = "127.0.0.1:9191";
+
 /// Main function
 #[tokio::main]
 async fn main() {
@@ -25,11 +40,25 @@ async fn main() {
 
     // Execute different actions based on parameters
     if let Err(e) = run(matches).await {
-        eprintln!("Error: {e}");
+        eprintln!("Error: {}", error_chain(&e));
         process::exit(1);
     }
 }
 
+/// Format an error followed by the causes its message does not already include
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message = format!("{message}: {cause_message}");
+        }
+        source = cause.source();
+    }
+    message
+}
+
 /// Build command line interface
 ///
 /// Creates the CLI structure using clap with support for detailed output
@@ -46,6 +75,21 @@ async fn main() {
 ///   - If no format is specified, defaults to `text`
 /// - `--interval/-t SECONDS`: Set output refresh interval (default: 3 seconds)
 /// - `--once`: Show output once and exit
+/// - `--unit [UNIT]`: Measure a systemd unit's cgroup instead of the whole host;
+///   without a unit name, list all running units sorted by lowest reserve
+/// - `--alerts FILE`: Evaluate alert rules on every sample (also for `daemon` and `serve`)
+/// - `--store [DIR]`: Persist samples to a history store (also for `daemon` and `serve`)
+/// - `--config FILE`: Load curve and aggregation settings from a TOML file (all modes)
+///
+/// # Subcommands
+///
+/// - `daemon`: Sample continuously and answer queries on a Unix domain socket
+/// - `query`: Query a running daemon
+/// - `serve`: Sample continuously and serve a JSON API over HTTP
+/// - `history`: Show persisted history
+/// - `record`: Record raw metric values to an NDJSON trace
+/// - `replay`: Re-score a recorded trace, e.g. with a different `--config`
+/// - `calibrate`: Fit curves to a labelled trace and emit a configuration file
 fn build_cli() -> Command {
     Command::new("pwrzv")
         .version(VERSION)
@@ -73,7 +117,7 @@ fn build_cli() -> Command {
                 .long("interval")
                 .value_name("SECONDS")
                 .help("Set output refresh interval in seconds (default: 3)")
-                .value_parser(clap::value_parser!(u64)),
+                .value_parser(clap::value_parser!(u64).range(1..)),
         )
         .arg(
             Arg::new("once")
@@ -81,6 +125,341 @@ fn build_cli() -> Command {
                 .help("Show output once and exit")
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("unit")
+                .short('u')
+                .long("unit")
+                .value_name("UNIT")
+                .help(
+                    "Measure a systemd unit's cgroup (Linux only); \
+                     without UNIT, list all running units sorted by lowest reserve",
+                )
+                .num_args(0..=1)
+                .default_missing_value(""),
+        )
+        .arg(alerts_arg())
+        .args(store_args())
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("Load scoring configuration from a TOML file (default: $PWRZV_CONFIG)")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true),
+        )
+        .arg(
+            Arg::new("disable")
+                .long("disable")
+                .value_name("METRICS")
+                .help(
+                    "Skip metrics, by ID or ID prefix, comma-separated \
+                     (e.g. process_count or disk)",
+                )
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .value_name("METRICS")
+                .help("Collect only these metrics, by ID or ID prefix (e.g. cpu,memory)")
+                .value_delimiter(',')
+                .action(clap::ArgAction::Append)
+                .global(true),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Sample continuously and answer queries on a Unix domain socket")
+                .arg(socket_arg())
+                .arg(
+                    Arg::new("http")
+                        .long("http")
+                        .value_name("ADDR")
+                        .help("Also serve the HTTP JSON API on ADDR (e.g. 127.0.0.1:9191)"),
+                )
+                .arg(alerts_arg())
+                .args(store_args())
+                .args(sampler_args()),
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Sample continuously and serve a JSON API over HTTP")
+                .arg(
+                    Arg::new("bind")
+                        .short('b')
+                        .long("bind")
+                        .value_name("ADDR")
+                        .help("Address to listen on")
+                        .default_value(DEFAULT_HTTP_BIND),
+                )
+                .arg(alerts_arg())
+                .args(store_args())
+                .args(sampler_args()),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Query a running pwrzv daemon")
+                .arg(
+                    Arg::new("what")
+                        .value_name("QUERY")
+                        .help("What to query")
+                        .value_parser(["level", "report", "history", "subscribe"])
+                        .default_value("level"),
+                )
+                .arg(socket_arg())
+                .arg(Arg::new("since").long("since").value_name("TIME").help(
+                    "Only return history since TIME \
+                             (RFC 3339 timestamp or relative age such as 90s, 15m, 2h, 1d)",
+                ))
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format (text, json, yaml)")
+                        .value_parser(["text", "json", "yaml"])
+                        .default_value("text"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show persisted power reserve history")
+                .arg(
+                    Arg::new("store")
+                        .long("store")
+                        .value_name("DIR")
+                        .help("History store directory (default: $PWRZV_STORE, $XDG_STATE_HOME/pwrzv or ~/.local/state/pwrzv)")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .value_name("TIME")
+                        .help("Start of the range: timestamp (RFC 3339 or local YYYY-MM-DD HH:MM) or age such as 12h")
+                        .default_value("1h"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_name("TIME")
+                        .help("End of the range (default: now)"),
+                )
+                .arg(
+                    Arg::new("resolution")
+                        .short('r')
+                        .long("resolution")
+                        .value_name("RESOLUTION")
+                        .help("Data resolution")
+                        .value_parser(["auto", "raw", "1m", "1h"])
+                        .default_value("auto"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format (text, json, yaml)")
+                        .value_parser(["text", "json", "yaml"])
+                        .default_value("text"),
+                ),
+        )
+        .subcommand(
+            Command::new("record")
+                .about("Record raw metric values before scoring to an NDJSON trace")
+                .arg(
+                    Arg::new("out")
+                        .short('o')
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Append the trace to FILE instead of writing to stdout")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .short('t')
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .help("Set sampling interval in seconds (default: 3)")
+                        .value_parser(clap::value_parser!(u64).range(1..)),
+                )
+                .arg(
+                    Arg::new("count")
+                        .short('n')
+                        .long("count")
+                        .value_name("SAMPLES")
+                        .help("Stop after SAMPLES samples (default: until interrupted)")
+                        .value_parser(clap::value_parser!(u64).range(1..)),
+                )
+                .arg(
+                    Arg::new("label")
+                        .short('l')
+                        .long("label")
+                        .value_name("LABEL")
+                        .help("Label every sample for `pwrzv calibrate`")
+                        .value_parser(["healthy", "degraded"]),
+                ),
+        )
+        .subcommand(
+            Command::new("replay")
+                .about("Re-score a recorded trace with the current configuration")
+                .arg(
+                    Arg::new("trace")
+                        .value_name("TRACE")
+                        .help("NDJSON trace written by `pwrzv record`")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Output format (text, json, yaml)")
+                        .value_parser(["text", "json", "yaml"])
+                        .default_value("text"),
+                ),
+        )
+        .subcommand(
+            Command::new("calibrate")
+                .about("Fit metric curves to a labelled trace and emit a configuration file")
+                .arg(
+                    Arg::new("trace")
+                        .value_name("TRACE")
+                        .help("NDJSON traces with samples labelled healthy or degraded")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .num_args(1..)
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("target")
+                        .long("target")
+                        .value_name("SPEC")
+                        .help(
+                            "Percentile target such as \"p95 of healthy = 4\"; repeatable \
+                             (default: \"p95 of healthy = 4\" and \"p50 of degraded = 1.5\")",
+                        )
+                        .value_parser(clap::value_parser!(CalibrationTarget))
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("out")
+                        .short('o')
+                        .long("out")
+                        .value_name("FILE")
+                        .help("Write the configuration to FILE instead of stdout")
+                        .value_parser(clap::value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("curves")
+                .about("Plot each metric's response curve with its current value")
+                .arg(
+                    Arg::new("metric")
+                        .short('m')
+                        .long("metric")
+                        .value_name("ID")
+                        .help("Only show this metric; repeatable (e.g. memory_pressure)")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("at")
+                        .long("at")
+                        .value_name("VALUES")
+                        .help("Comma-separated raw values for the score table (default: 11 evenly spaced values)")
+                        .value_parser(clap::value_parser!(f32))
+                        .value_delimiter(',')
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the effective configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the effective curve of every metric and where each value comes from")
+                        .arg(
+                            Arg::new("format")
+                                .short('f')
+                                .long("format")
+                                .value_name("FORMAT")
+                                .help("Output format (text, json, yaml)")
+                                .value_parser(["text", "json", "yaml"])
+                                .default_value("text"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Report unknown or invalid PWRZV_* variables and configuration file errors"),
+                ),
+        )
+}
+
+/// Build the sampling arguments shared by the daemon and serve subcommands
+fn sampler_args() -> [Arg; 2] {
+    [
+        Arg::new("interval")
+            .short('t')
+            .long("interval")
+            .value_name("SECONDS")
+            .help("Set sampling interval in seconds (default: 3)")
+            .value_parser(clap::value_parser!(u64).range(1..)),
+        Arg::new("history")
+            .long("history")
+            .value_name("SAMPLES")
+            .help("Number of samples kept in memory (default: 1200)")
+            .value_parser(clap::value_parser!(usize)),
+    ]
+}
+
+/// Start a [`Sampler`] configured from the shared sampling arguments
+fn start_sampler(matches: &ArgMatches) -> Result<(Sampler, u64, usize), PwrzvError> {
+    let interval = matches.get_one::<u64>("interval").copied().unwrap_or(3);
+    let history = matches.get_one::<usize>("history").copied().unwrap_or(1200);
+    let sampler = Sampler::start(Duration::from_secs(interval), history)?;
+    Ok((sampler, interval, history))
+}
+
+/// Build the `--alerts` argument shared by continuous, daemon and serve modes
+fn alerts_arg() -> Arg {
+    Arg::new("alerts")
+        .long("alerts")
+        .value_name("FILE")
+        .help("Evaluate alert rules from a YAML file on every sample")
+        .value_parser(clap::value_parser!(PathBuf))
+}
+
+/// Build the history store arguments shared by continuous, daemon and serve modes
+fn store_args() -> [Arg; 2] {
+    [
+        Arg::new("store")
+            .long("store")
+            .value_name("DIR")
+            .help(
+                "Persist samples to a history store \
+                 (default: $PWRZV_STORE, $XDG_STATE_HOME/pwrzv or ~/.local/state/pwrzv)",
+            )
+            // Empty when given without a directory, which selects the default location
+            .value_parser(clap::builder::OsStringValueParser::new().map(PathBuf::from))
+            .num_args(0..=1)
+            .default_missing_value(""),
+        Arg::new("retention")
+            .long("retention")
+            .value_name("SPEC")
+            .help("History retention per resolution (default: raw=2d,1m=30d,1h=400d)")
+            .requires("store"),
+    ]
+}
+
+/// Build the `--socket` argument shared by the daemon and query subcommands
+fn socket_arg() -> Arg {
+    Arg::new("socket")
+        .short('s')
+        .long("socket")
+        .value_name("PATH")
+        .help("Unix socket path (default: $PWRZV_SOCKET or $XDG_RUNTIME_DIR/pwrzv.sock)")
+        .value_parser(clap::value_parser!(PathBuf))
 }
 
 /// Run main logic
@@ -111,6 +490,35 @@ fn build_cli() -> Command {
 /// - **macOS**: Full support via system commands
 /// - **Other platforms**: Returns error with helpful message
 async fn run(matches: ArgMatches) -> Result<(), PwrzvError> {
+    // `config check` reports a broken configuration file instead of failing on it
+    if let Some(("config", config_matches)) = matches.subcommand() {
+        return run_config(config_matches, matches.get_one::<PathBuf>("config"));
+    }
+
+    if let Some(path) = matches.get_one::<PathBuf>("config") {
+        config::set_global(PwrzvConfig::from_file(path)?);
+    }
+    apply_metric_selection(&matches)?;
+
+    // Querying a daemon needs no local collection, so it skips the platform check
+    // and keeps stdout clean for scripts
+    if let Some(("query", query_matches)) = matches.subcommand() {
+        return run_query(query_matches).await;
+    }
+    if let Some(("history", history_matches)) = matches.subcommand() {
+        return run_history(history_matches);
+    }
+    // Traces are written to stdout, so recording skips the platform banner as well
+    if let Some(("record", record_matches)) = matches.subcommand() {
+        return run_record(record_matches).await;
+    }
+    if let Some(("replay", replay_matches)) = matches.subcommand() {
+        return run_replay(replay_matches);
+    }
+    if let Some(("calibrate", calibrate_matches)) = matches.subcommand() {
+        return run_calibrate(calibrate_matches);
+    }
+
     // Check platform compatibility
     if let Err(e) = check_platform() {
         eprintln!("❌ Platform check failed: {e}");
@@ -120,12 +528,27 @@ async fn run(matches: ArgMatches) -> Result<(), PwrzvError> {
 
     println!("✅ Platform check passed for: {}", get_platform_name());
 
+    if let Some(("daemon", daemon_matches)) = matches.subcommand() {
+        return run_daemon(daemon_matches).await;
+    }
+
+    if let Some(("serve", serve_matches)) = matches.subcommand() {
+        return run_serve(serve_matches).await;
+    }
+
+    if let Some(("curves", curves_matches)) = matches.subcommand() {
+        return run_curves(curves_matches).await;
+    }
+
+    if let Some(unit) = matches.get_one::<String>("unit") {
+        return run_unit_mode(&matches, unit).await;
+    }
+
     // Check if single-shot mode is requested
     if matches.get_flag("once") {
         // Choose output method based on whether detailed information is needed
         if let Some(format) = matches.get_one::<String>("detailed") {
-            let (level, details) = get_power_reserve_level_with_details_direct().await?;
-            output_detailed_result(format, level, &details)?;
+            output_report(format, &get_power_reserve_report().await?)?;
         } else {
             let level = get_power_reserve_level_direct().await?;
             println!("{level:.2}");
@@ -135,12 +558,15 @@ async fn run(matches: ArgMatches) -> Result<(), PwrzvError> {
 
     // Continuous monitoring mode
     let output_interval = matches.get_one::<u64>("interval").copied().unwrap_or(3); // Default 3 second
+    let mut alerts = load_alerts(&matches)?;
+    let mut store = open_store(&matches)?;
 
     eprintln!("🔄 Starting continuous monitoring (interval: {output_interval}s)");
     eprintln!("💡 Press Ctrl+C to stop");
     eprintln!();
 
-    loop {
+    let mut samples = pwrzv::watch(Duration::from_secs(output_interval))?;
+    while let Some(sample) = samples.next().await {
         let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
 
         // Clear screen for better readability if detailed mode
@@ -149,32 +575,812 @@ async fn run(matches: ArgMatches) -> Result<(), PwrzvError> {
             println!("{now}"); // Show current time
         }
 
-        // Collect and output current status
-        if let Some(format) = matches.get_one::<String>("detailed") {
-            match get_power_reserve_level_with_details_direct().await {
-                Ok((level, details)) => {
-                    output_detailed_result(format, level, &details)?;
+        // Output the current sample
+        match sample {
+            Ok(report) => {
+                if let Some(format) = matches.get_one::<String>("detailed") {
+                    output_report(format, &report)?;
+                } else {
+                    println!("{now} Power Reserve: {:.2}", report.power_reserve_level);
+                    warn_failed_collectors(&report);
+                }
+                if let Some(engine) = alerts.as_mut() {
+                    dispatch_alerts(engine, &report);
                 }
-                Err(e) => {
-                    eprintln!("{now} ❌ Failed to collect metrics: {e}");
+                if let Some(store) = store.as_mut() {
+                    record_sample(store, &report);
                 }
             }
-        } else {
-            match get_power_reserve_level_direct().await {
-                Ok(level) => {
-                    println!("{now} Power Reserve: {level:.2}");
+            Err(e) => {
+                eprintln!("{now} ❌ Failed to collect metrics: {e}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Load alert rules from `--alerts`, if given
+fn load_alerts(matches: &ArgMatches) -> Result<Option<AlertEngine>, PwrzvError> {
+    let Some(path) = matches.get_one::<PathBuf>("alerts") else {
+        return Ok(None);
+    };
+    let engine = AlertEngine::from_file(path)?;
+    eprintln!(
+        "🔔 Loaded {} alert rule(s) from {}",
+        engine.rules().count(),
+        path.display()
+    );
+    Ok(Some(engine))
+}
+
+/// Evaluate alert rules against a report and send notifications in the background
+fn dispatch_alerts(engine: &mut AlertEngine, report: &PowerReserveReport) {
+    for event in engine.evaluate(report) {
+        eprintln!(
+            "🔔 Alert '{}' {} ({})",
+            event.rule,
+            event.state.as_str(),
+            event.condition
+        );
+        tokio::spawn(async move {
+            for result in event.notify().await {
+                if let Err(e) = result {
+                    eprintln!("❌ Alert '{}' notification failed: {e}", event.rule);
+                }
+            }
+        });
+    }
+}
+
+/// Open the history store from `--store`, if given
+fn open_store(matches: &ArgMatches) -> Result<Option<HistoryStore>, PwrzvError> {
+    let Some(dir) = matches.get_one::<PathBuf>("store") else {
+        return Ok(None);
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        store::default_store_dir()
+    } else {
+        dir.clone()
+    };
+    let retention = matches
+        .get_one::<String>("retention")
+        .map(|spec| spec.parse::<RetentionPolicy>())
+        .transpose()?
+        .unwrap_or_default();
+
+    let store = HistoryStore::open(&dir, retention)?;
+    eprintln!("💾 Recording history to {}", store.dir().display());
+    Ok(Some(store))
+}
+
+/// Persist a sample, reporting failures without stopping the monitor
+fn record_sample(store: &mut HistoryStore, report: &PowerReserveReport) {
+    if let Err(e) = store.record(report) {
+        eprintln!("❌ Failed to record history: {e}");
+    }
+}
+
+/// Persist every sample of a [`Sampler`]
+async fn run_recorder(mut store: HistoryStore, sampler: Sampler) {
+    let mut samples = sampler.subscribe();
+    drop(sampler);
+    loop {
+        match samples.recv().await {
+            Ok(report) => record_sample(&mut store, &report),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Evaluate alert rules against every sample of a [`Sampler`]
+async fn run_alerts(mut engine: AlertEngine, sampler: Sampler) {
+    let mut samples = sampler.subscribe();
+    drop(sampler);
+    loop {
+        match samples.recv().await {
+            Ok(report) => dispatch_alerts(&mut engine, &report),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Run daemon mode
+///
+/// Starts a background [`Sampler`] and serves it on a Unix domain socket (and
+/// optionally over HTTP) until interrupted with Ctrl+C or SIGTERM, then removes
+/// the socket file.
+async fn run_daemon(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let socket = socket_path(matches)?;
+    let alerts = load_alerts(matches)?;
+    let store = open_store(matches)?;
+    let (sampler, interval, history) = start_sampler(matches)?;
+    if let Some(engine) = alerts {
+        tokio::spawn(run_alerts(engine, sampler.clone()));
+    }
+    if let Some(store) = store {
+        tokio::spawn(run_recorder(store, sampler.clone()));
+    }
+
+    eprintln!(
+        "🔄 Starting daemon on {} (interval: {interval}s, history: {history} samples)",
+        socket.display()
+    );
+
+    let http_server = async {
+        match matches.get_one::<String>("http") {
+            Some(addr) => {
+                eprintln!("🌐 Serving HTTP API on http://{addr}");
+                http::serve(addr.as_str(), sampler.clone()).await
+            }
+            None => std::future::pending().await,
+        }
+    };
+    eprintln!("💡 Press Ctrl+C to stop");
+
+    let result = tokio::select! {
+        result = daemon::serve(&socket, sampler.clone()) => result,
+        result = http_server => result,
+        _ = shutdown_signal() => {
+            eprintln!("🛑 Shutting down daemon");
+            Ok(())
+        }
+    };
+
+    let _ = std::fs::remove_file(&socket);
+    result
+}
+
+/// Run HTTP server mode
+///
+/// Starts a background [`Sampler`] and serves the HTTP JSON API until
+/// interrupted with Ctrl+C or SIGTERM.
+async fn run_serve(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let bind = matches
+        .get_one::<String>("bind")
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_HTTP_BIND);
+    let alerts = load_alerts(matches)?;
+    let store = open_store(matches)?;
+    let (sampler, interval, history) = start_sampler(matches)?;
+    if let Some(engine) = alerts {
+        tokio::spawn(run_alerts(engine, sampler.clone()));
+    }
+    if let Some(store) = store {
+        tokio::spawn(run_recorder(store, sampler.clone()));
+    }
+
+    eprintln!(
+        "🌐 Serving HTTP API on http://{bind} (interval: {interval}s, history: {history} samples)"
+    );
+    eprintln!("💡 Press Ctrl+C to stop");
+
+    tokio::select! {
+        result = http::serve(bind, sampler) => result,
+        _ = shutdown_signal() => {
+            eprintln!("🛑 Shutting down server");
+            Ok(())
+        }
+    }
+}
+
+/// Wait for Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let mut terminate =
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(signal) => signal,
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+/// Run query mode
+///
+/// Connects to a running daemon and prints the requested data.
+async fn run_query(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let client = DaemonClient::new(socket_path(matches)?);
+    let format = matches
+        .get_one::<String>("format")
+        .map(String::as_str)
+        .unwrap_or("text");
+    let since = matches
+        .get_one::<String>("since")
+        .map(|value| timespec::parse_since(value, chrono::Utc::now()))
+        .transpose()?;
+
+    match matches
+        .get_one::<String>("what")
+        .map(String::as_str)
+        .unwrap_or("level")
+    {
+        "report" => output_report(format, &client.report().await?)?,
+        "history" => {
+            let history = client.history(since).await?;
+            match format {
+                "json" => println!("{}", serde_json::to_string_pretty(&history).unwrap()),
+                "yaml" => println!("{}", serde_yaml::to_string(&history).unwrap()),
+                _ => {
+                    for report in &history {
+                        println!("{}", format_report_line(report));
+                    }
+                }
+            }
+        }
+        "subscribe" => {
+            let mut subscription = client.subscribe().await?;
+            while let Some(report) = subscription.next().await? {
+                match format {
+                    "json" => println!("{}", serde_json::to_string(&report).unwrap()),
+                    "yaml" => println!("---\n{}", serde_yaml::to_string(&report).unwrap()),
+                    _ => println!("{}", format_report_line(&report)),
+                }
+            }
+        }
+        _ => println!("{:.2}", client.level().await?),
+    }
+    Ok(())
+}
+
+/// Run history mode
+///
+/// Reads a range of persisted samples or rollups from the history store.
+fn run_history(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let dir = matches
+        .get_one::<PathBuf>("store")
+        .cloned()
+        .unwrap_or_else(store::default_store_dir);
+    let store = HistoryStore::open_read_only(&dir)?;
+
+    let now = chrono::Utc::now();
+    let from = timespec::parse_since(
+        matches
+            .get_one::<String>("from")
+            .map_or("1h", String::as_str),
+        now,
+    )?;
+    let to = matches
+        .get_one::<String>("to")
+        .map(|value| timespec::parse_since(value, now))
+        .transpose()?
+        .unwrap_or(now);
+    let resolution = match matches
+        .get_one::<String>("resolution")
+        .map_or("auto", String::as_str)
+        .parse()?
+    {
+        Resolution::Auto => store.auto_resolution(from, to),
+        resolution => resolution,
+    };
+    let points = store.query(resolution, from, to)?;
+
+    match matches
+        .get_one::<String>("format")
+        .map_or("text", String::as_str)
+    {
+        "json" => println!("{}", serde_json::to_string_pretty(&points).unwrap()),
+        "yaml" => println!("{}", serde_yaml::to_string(&points).unwrap()),
+        _ => {
+            println!("Resolution: {resolution}");
+            println!(
+                "{:<19}  {:>5}  {:>5}  {:>5}  {:>7}",
+                "TIME", "MIN", "AVG", "MAX", "SAMPLES"
+            );
+            for point in &points {
+                println!(
+                    "{:<19}  {:>5.2}  {:>5.2}  {:>5.2}  {:>7}",
+                    point
+                        .timestamp
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M:%S"),
+                    point.min,
+                    point.avg,
+                    point.max,
+                    point.samples
+                );
+            }
+            if points.is_empty() {
+                eprintln!("No history between {from} and {to}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run record mode
+///
+/// Appends one [`trace::TraceRecord`] per sample to `--out` (or stdout) until
+/// `--count` samples are written or the process is interrupted.
+async fn run_record(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let interval = matches.get_one::<u64>("interval").copied().unwrap_or(3);
+    let count = matches.get_one::<u64>("count").copied();
+    let label = matches
+        .get_one::<String>("label")
+        .map(|label| label.parse())
+        .transpose()?;
+    let mut out: Box<dyn Write> = match matches.get_one::<PathBuf>("out") {
+        Some(path) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            eprintln!("💾 Recording trace to {}", path.display());
+            Box::new(file)
+        }
+        None => Box::new(std::io::stdout()),
+    };
+    eprintln!("🔄 Recording raw metrics (interval: {interval}s)");
+    eprintln!("💡 Press Ctrl+C to stop");
+
+    let recording = async {
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        let mut written = 0;
+        while count.is_none_or(|count| written < count) {
+            ticker.tick().await;
+            match trace::record().await {
+                Ok(mut record) => {
+                    record.label = label;
+                    writeln!(out, "{}", serde_json::to_string(&record).unwrap())?;
+                    out.flush()?;
+                    written += 1;
+                }
+                Err(e) => eprintln!("❌ Failed to collect metrics: {e}"),
+            }
+        }
+        Ok(())
+    };
+
+    tokio::select! {
+        result = recording => result,
+        _ = shutdown_signal() => Ok(()),
+    }
+}
+
+/// Run replay mode
+///
+/// Re-scores every sample of a trace with the effective configuration and
+/// compares the result with the level computed at recording time.
+fn run_replay(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let path = matches
+        .get_one::<PathBuf>("trace")
+        .expect("trace is required");
+    let records = trace::read_trace(path)?;
+    let config = config::global();
+    let replayed = records
+        .iter()
+        .map(|record| record.replay(&config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match matches
+        .get_one::<String>("format")
+        .map_or("text", String::as_str)
+    {
+        "json" => println!("{}", serde_json::to_string_pretty(&replayed).unwrap()),
+        "yaml" => println!("{}", serde_yaml::to_string(&replayed).unwrap()),
+        _ => {
+            println!(
+                "{:<19}  {:>8}  {:>8}  {:>6}  BOTTLENECK",
+                "TIME", "RECORDED", "REPLAYED", "DELTA"
+            );
+            for (record, report) in records.iter().zip(&replayed) {
+                let bottleneck = report
+                    .readings()
+                    .into_iter()
+                    .min_by(|a, b| a.1.score.total_cmp(&b.1.score))
+                    .map(|(id, reading)| format!("{id} ({:.3})", reading.value))
+                    .unwrap_or_default();
+                println!(
+                    "{:<19}  {:>8.2}  {:>8.2}  {:>+6.2}  {bottleneck}",
+                    record
+                        .timestamp
+                        .with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M:%S"),
+                    record.level,
+                    report.power_reserve_level,
+                    report.power_reserve_level - record.level,
+                );
+            }
+
+            if records.is_empty() {
+                eprintln!("No samples in {}", path.display());
+            } else {
+                let mean = |levels: &mut dyn Iterator<Item = f32>| {
+                    levels.sum::<f32>() / records.len() as f32
+                };
+                let changed = records
+                    .iter()
+                    .zip(&replayed)
+                    .filter(|(record, report)| {
+                        (report.power_reserve_level - record.level).abs() >= 0.01
+                    })
+                    .count();
+                println!();
+                println!(
+                    "{} samples, mean level {:.2} -> {:.2}, {changed} changed",
+                    records.len(),
+                    mean(&mut records.iter().map(|record| record.level)),
+                    mean(&mut replayed.iter().map(|report| report.power_reserve_level)),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run calibrate mode
+///
+/// Fits every metric's curve to the labelled samples of the given traces and
+/// writes the resulting configuration, merged into the effective one.
+fn run_calibrate(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let mut records = Vec::new();
+    for path in matches.get_many::<PathBuf>("trace").into_iter().flatten() {
+        records.extend(trace::read_trace(path)?);
+    }
+    let targets = match matches.get_many::<CalibrationTarget>("target") {
+        Some(targets) => targets.copied().collect(),
+        None => calibrate::DEFAULT_TARGETS
+            .iter()
+            .map(|target| target.parse())
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+
+    let calibration = calibrate::calibrate(&records, &targets, &config::global())?;
+    for fit in &calibration.fits {
+        eprintln!(
+            "✅ {}: midpoint {:.4}, steepness {:.4}",
+            fit.id, fit.midpoint, fit.steepness
+        );
+        if let Some(note) = &fit.note {
+            eprintln!("   ⚠️  {note}");
+        }
+    }
+    for (id, reason) in &calibration.skipped {
+        eprintln!("💡 {id}: unchanged ({reason})");
+    }
+
+    match matches.get_one::<PathBuf>("out") {
+        Some(path) => {
+            std::fs::write(path, calibration.to_toml())?;
+            eprintln!("💾 Wrote configuration to {}", path.display());
+        }
+        None => print!("{}", calibration.to_toml()),
+    }
+    Ok(())
+}
+
+/// Run curves mode
+///
+/// Plots the effective curve of each selected metric and prints a table of raw
+/// values and their scores, marking the metric's current value on both.
+/// Systemd unit metrics have no current value and are plotted without one.
+async fn run_curves(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let selected: Vec<&str> = matches
+        .get_many::<String>("metric")
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let at: Vec<f32> = matches
+        .get_many("at")
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+
+    let metrics = config::global().effective();
+    if let Some(unknown) = selected
+        .iter()
+        .find(|id| !metrics.iter().any(|metric| metric.id == **id))
+    {
+        let mut ids: Vec<_> = metrics.iter().map(|metric| metric.id.as_str()).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        eprintln!("❌ Unknown metric: {unknown}");
+        eprintln!("💡 Available metrics: {}", ids.join(", "));
+        process::exit(1);
+    }
+
+    let live = match trace::record().await {
+        Ok(record) => record.inputs,
+        Err(e) => {
+            eprintln!("⚠️  Failed to collect current values: {e}");
+            Default::default()
+        }
+    };
+
+    let platform = env::consts::OS;
+    for metric in metrics
+        .iter()
+        .filter(|metric| selected.is_empty() || selected.contains(&metric.id.as_str()))
+    {
+        let current = match metric.scope == platform {
+            true => live.get(&metric.id).copied(),
+            false => None,
+        };
+
+        let (min, mut max) = plot::value_range(&metric.curve);
+        let extremes = current.into_iter().chain(at.iter().copied());
+        if let Some(largest) = extremes.reduce(f32::max).filter(|&value| value > max) {
+            max = plot::nice_ceil(largest);
+        }
+
+        println!();
+        println!("[{}] {} ({})", metric.scope, metric.id, metric.label);
+        println!("Curve: {}", metric.curve);
+        println!();
+        print!("{}", plot::render(&metric.curve, (min, max), current));
+        println!();
+
+        let mut values = match at.is_empty() {
+            true => plot::sample_values((min, max), 11),
+            false => at.clone(),
+        };
+        values.extend(current);
+        values.sort_by(f32::total_cmp);
+        values.dedup();
+
+        println!("    {:>10}  {:>6}", "VALUE", "SCORE");
+        for value in values {
+            let marker = if Some(value) == current {
+                "  ← now"
+            } else {
+                ""
+            };
+            println!(
+                "    {:>10}  {:>6.3}{marker}",
+                plot::format_value(value),
+                metric.curve.score(value)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Apply `--only` and `--disable` to the process-wide configuration
+fn apply_metric_selection(matches: &ArgMatches) -> Result<(), PwrzvError> {
+    let names = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .into_iter()
+            .flatten()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect()
+    };
+    let only = names("only");
+    let disable = names("disable");
+    if only.is_empty() && disable.is_empty() {
+        return Ok(());
+    }
+
+    let mut config = (*config::global()).clone();
+    if !only.is_empty() {
+        config.enable_only(&only)?;
+    }
+    config.set_enabled(&disable, false)?;
+    config::set_global(config);
+    Ok(())
+}
+
+/// Run config mode
+///
+/// `show` prints the effective curve of every metric, `check` lists every
+/// configuration problem and fails if there is any.
+///
+/// # Arguments
+///
+/// * `matches` - Parsed arguments of the config subcommand
+/// * `path` - Configuration file given with `--config`
+fn run_config(matches: &ArgMatches, path: Option<&PathBuf>) -> Result<(), PwrzvError> {
+    let path = path
+        .cloned()
+        .or_else(|| env::var_os(config::CONFIG_ENV).map(PathBuf::from));
+
+    if let Some(("check", _)) = matches.subcommand() {
+        let issues = config::check(path.as_deref());
+        if issues.is_empty() {
+            eprintln!("✅ Configuration OK");
+            return Ok(());
+        }
+        for issue in &issues {
+            eprintln!("❌ {issue}");
+        }
+        eprintln!("💡 {} configuration problem(s) found", issues.len());
+        process::exit(1);
+    }
+
+    let show_matches = matches.subcommand_matches("show").unwrap_or(matches);
+    let loaded = match &path {
+        Some(path) => PwrzvConfig::from_file(path)?,
+        None => PwrzvConfig::default(),
+    };
+
+    #[derive(serde::Serialize)]
+    struct ConfigShow {
+        config_file: Option<PathBuf>,
+        aggregation: config::Aggregation,
+        metrics: Vec<config::EffectiveMetric>,
+    }
+    let output = ConfigShow {
+        config_file: path,
+        aggregation: loaded.aggregation,
+        metrics: loaded.effective(),
+    };
+    match show_matches.get_one::<String>("format").map(String::as_str) {
+        Some("json") => println!("{}", serde_json::to_string_pretty(&output).unwrap()),
+        Some("yaml") => print!("{}", serde_yaml::to_string(&output).unwrap()),
+        _ => {
+            match &output.config_file {
+                Some(path) => println!("Configuration file: {}", path.display()),
+                None => println!("Configuration file: none"),
+            }
+            println!(
+                "Aggregation: {}",
+                format!("{:?}", output.aggregation).to_lowercase()
+            );
+            let mut scope = "";
+            for metric in &output.metrics {
+                if metric.scope != scope {
+                    scope = &metric.scope;
+                    println!();
+                    println!("[{scope}]");
+                }
+                println!("{} ({})", metric.id, metric.label);
+                for param in &metric.params {
+                    println!(
+                        "    {:<10} {:<24} {}",
+                        param.name, param.value, param.source
+                    );
                 }
-                Err(e) => {
-                    eprintln!("{now} ❌ Failed to collect metrics: {e}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get the socket path from `--socket` or the default location
+fn socket_path(matches: &ArgMatches) -> Result<PathBuf, PwrzvError> {
+    match matches.get_one::<PathBuf>("socket") {
+        Some(path) => Ok(path.clone()),
+        None => daemon::default_socket_path(),
+    }
+}
+
+/// Format a report as a single timestamped line
+fn format_report_line(report: &PowerReserveReport) -> String {
+    let local = report.timestamp.with_timezone(&chrono::Local);
+    format!(
+        "{} Power Reserve: {:.2}",
+        local.format("%Y-%m-%d %H:%M:%S"),
+        report.power_reserve_level
+    )
+}
+
+/// Run systemd unit mode
+///
+/// With a unit name, reports the reserve of that unit's cgroup exactly like the
+/// host-wide mode. Without one, lists every running unit sorted by lowest reserve.
+///
+/// # Arguments
+///
+/// * `matches` - Parsed command line arguments from clap
+/// * `unit` - Unit name, or an empty string to list all running units
+async fn run_unit_mode(matches: &ArgMatches, unit: &str) -> Result<(), PwrzvError> {
+    let detailed = matches.get_one::<String>("detailed");
+
+    if matches.get_flag("once") {
+        if unit.is_empty() {
+            let units = get_running_units_power_reserve().await?;
+            output_units_result(detailed.map(String::as_str), &units)?;
+        } else {
+            let (level, details) = get_unit_power_reserve_level_with_details(unit).await?;
+            match detailed {
+                Some(format) => output_detailed_result(format, level, &details)?,
+                None => println!("{level:.2}"),
+            }
+        }
+        return Ok(());
+    }
+
+    let output_interval = matches.get_one::<u64>("interval").copied().unwrap_or(3);
+
+    eprintln!("🔄 Starting continuous unit monitoring (interval: {output_interval}s)");
+    eprintln!("💡 Press Ctrl+C to stop");
+    eprintln!();
+
+    loop {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+
+        if unit.is_empty() {
+            match get_running_units_power_reserve().await {
+                Ok(units) => {
+                    print!("\x1b[2J\x1b[H"); // Clear screen and move cursor to top
+                    println!("{now}");
+                    output_units_result(detailed.map(String::as_str), &units)?;
                 }
+                Err(e) => eprintln!("{now} ❌ Failed to collect unit metrics: {e}"),
+            }
+        } else {
+            match get_unit_power_reserve_level_with_details(unit).await {
+                Ok((level, details)) => match detailed {
+                    Some(format) => {
+                        print!("\x1b[2J\x1b[H");
+                        println!("{now}");
+                        output_detailed_result(format, level, &details)?;
+                    }
+                    None => println!("{now} {unit} Power Reserve: {level:.2}"),
+                },
+                Err(e) => eprintln!("{now} ❌ Failed to collect metrics for {unit}: {e}"),
             }
         }
 
-        // Wait for next output interval
         sleep(Duration::from_secs(output_interval)).await;
     }
 }
 
+/// Output the reserve of all running units
+///
+/// # Arguments
+///
+/// * `format` - Optional output format: "text", "json", or "yaml"; `None` prints a plain table
+/// * `units` - Unit reserves, already sorted by lowest reserve first
+fn output_units_result(format: Option<&str>, units: &[UnitPowerReserve]) -> Result<(), PwrzvError> {
+    let rows: Vec<_> = units
+        .iter()
+        .map(|u| {
+            serde_json::json!({
+                "unit": u.unit,
+                "power_reserve_level": u.level,
+                "level_description": level_description(u.level),
+                "metrics": u.details,
+            })
+        })
+        .collect();
+
+    match format {
+        Some("json") => {
+            let json_output = serde_json::json!({
+                "platform": get_platform_name(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "units": rows,
+                "total_units": units.len()
+            });
+            println!("{}", serde_json::to_string_pretty(&json_output).unwrap());
+        }
+        Some("yaml") => {
+            let yaml_data = serde_json::json!({
+                "platform": get_platform_name(),
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "units": rows,
+                "total_units": units.len()
+            });
+            println!("{}", serde_yaml::to_string(&yaml_data).unwrap());
+        }
+        _ => {
+            println!("{:<50} {:>7}", "UNIT", "RESERVE");
+            for u in units {
+                println!(
+                    "{:<50} {:>7.2} {}",
+                    u.unit,
+                    u.level,
+                    format_level_emoji(u.level)
+                );
+            }
+            if units.is_empty() {
+                println!("(no running units found)");
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Output detailed result
 ///
 /// Formats and outputs detailed system metrics in the specified format.
@@ -206,28 +1412,26 @@ fn output_detailed_result(
     level: f32,
     details: &HashMap<String, f32>,
 ) -> Result<(), PwrzvError> {
+    output_report(format, &PowerReserveReport::new(level, details.clone()))
+}
+
+/// Output a power reserve report
+///
+/// Shared by [`output_detailed_result`] and the daemon client so that every
+/// JSON/YAML consumer sees the same structure.
+///
+/// # Arguments
+///
+/// * `format` - Output format: "text", "json", or "yaml"
+/// * `report` - The report to output
+fn output_report(format: &str, report: &PowerReserveReport) -> Result<(), PwrzvError> {
+    let level = report.power_reserve_level;
     match format {
         "json" => {
-            let json_output = serde_json::json!({
-                "platform": get_platform_name(),
-                "timestamp": chrono::Utc::now().to_rfc3339(),
-                "power_reserve_level": level,
-                "level_description": format_level_description(level),
-                "metrics": details,
-                "total_metrics": details.len()
-            });
-            println!("{}", serde_json::to_string_pretty(&json_output).unwrap());
+            println!("{}", serde_json::to_string_pretty(report).unwrap());
         }
         "yaml" => {
-            let yaml_data = serde_json::json!({
-                "platform": get_platform_name(),
-                "timestamp": chrono::Utc::now().to_rfc3339(),
-                "power_reserve_level": level,
-                "level_description": format_level_description(level),
-                "metrics": details,
-                "total_metrics": details.len()
-            });
-            println!("{}", serde_yaml::to_string(&yaml_data).unwrap());
+            println!("{}", serde_yaml::to_string(report).unwrap());
         }
         // Default to text format for any other cases
         _ => {
@@ -242,12 +1446,15 @@ fn output_detailed_result(
                 level,
                 format_level_emoji(level)
             );
-            println!("   Status: {}", format_level_description(level));
+            println!("   Status: {}", report.level_description);
             println!();
 
-            if !details.is_empty() {
-                print_metrics_section(details);
+            if !report.metrics.is_empty() {
+                print_metrics_section(&report.metrics);
             }
+            print_notes_section(report);
+            print_collectors_section(report);
+            print_overhead_section(report);
 
             println!("───────────────────────────────────────────────────────────");
             println!("💡 Interpretation:");
@@ -268,7 +1475,7 @@ fn print_metrics_section(details: &HashMap<String, f32>) {
     println!("📈 Component Metrics:");
 
     let mut sorted_metrics: Vec<_> = details.iter().collect();
-    sorted_metrics.sort_by(|a, b| a.1.partial_cmp(b.1).unwrap());
+    sorted_metrics.sort_by(|a, b| a.1.total_cmp(b.1));
 
     for (key, value) in sorted_metrics {
         let status_emoji = format_level_emoji(*value);
@@ -277,18 +1484,66 @@ fn print_metrics_section(details: &HashMap<String, f32>) {
     println!();
 }
 
-/// Format level description based on numeric value
-fn format_level_description(level: f32) -> &'static str {
-    if level >= 4.0 {
-        "Abundant - Excellent performance"
-    } else if level >= 3.0 {
-        "High - Good performance"
-    } else if level >= 2.0 {
-        "Medium - Normal performance"
-    } else if level >= 1.0 {
-        "Low - Degraded performance"
-    } else {
-        "Critical - Poor performance"
+/// Print the notes of a report for text format
+fn print_notes_section(report: &PowerReserveReport) {
+    if report.notes.is_empty() {
+        return;
+    }
+
+    println!("📝 Notes:");
+    for note in &report.notes {
+        println!("   • {note}");
+    }
+    println!();
+}
+
+/// Print failed collectors for text format
+///
+/// Shows which metrics could not be measured, so a missing score is not
+/// mistaken for a healthy one.
+fn print_collectors_section(report: &PowerReserveReport) {
+    let failed: Vec<_> = report
+        .collectors
+        .iter()
+        .filter(|collector| collector.status.is_failed())
+        .collect();
+    if failed.is_empty() {
+        return;
+    }
+
+    println!("⚠️  Unavailable Collectors:");
+    for collector in failed {
+        println!(
+            "   {:<18} {:<34} {}",
+            collector.name,
+            collector.metrics.join(", "),
+            collector.status
+        );
+    }
+    println!();
+}
+
+/// Print what every collector cost for text format
+fn print_overhead_section(report: &PowerReserveReport) {
+    let costs = report.collectors.iter().filter_map(|c| c.cost.as_ref());
+    if report.collectors.is_empty() {
+        return;
+    }
+
+    println!("⏱️  Collection Overhead: {}", CollectorCost::total(costs));
+    for collector in &report.collectors {
+        match &collector.cost {
+            Some(cost) => println!("   {:<18} {cost}", collector.name),
+            None => println!("   {:<18} {}", collector.name, collector.status),
+        }
+    }
+    println!();
+}
+
+/// Warn on stderr about collectors that failed in a sample
+fn warn_failed_collectors(report: &PowerReserveReport) {
+    for collector in report.collectors.iter().filter(|c| c.status.is_failed()) {
+        eprintln!("⚠️  Collector '{}' {}", collector.name, collector.status);
     }
 }
 
@@ -311,6 +1566,22 @@ fn format_level_emoji(level: f32) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_error_chain() {
+        // Causes already in the message are not repeated
+        let error = PwrzvError::IoError(std::io::Error::other("boom"));
+        assert_eq!(error_chain(&error), "I/O error: boom");
+
+        let error = PwrzvError::ResourceAccessError {
+            resource: "daemon".to_string(),
+            source: Some(std::io::Error::other("refused")),
+        };
+        assert_eq!(
+            error_chain(&error),
+            "Failed to access system resource: daemon: refused"
+        );
+    }
+
     #[test]
     fn test_cli_parsing() {
         let app = build_cli();
@@ -349,6 +1620,261 @@ mod tests {
         assert_eq!(matches.get_one::<u64>("interval").unwrap(), &10);
     }
 
+    #[test]
+    fn test_cli_unit_parsing() {
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "--unit", "nginx.service"])
+            .unwrap();
+        assert_eq!(matches.get_one::<String>("unit").unwrap(), "nginx.service");
+
+        // Without a unit name, all running units are listed
+        let app = build_cli();
+        let matches = app.try_get_matches_from(vec!["pwrzv", "--unit"]).unwrap();
+        assert_eq!(matches.get_one::<String>("unit").unwrap(), "");
+    }
+
+    #[test]
+    fn test_cli_daemon_subcommand() {
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec![
+                "pwrzv",
+                "daemon",
+                "--socket",
+                "/tmp/x.sock",
+                "-t",
+                "5",
+            ])
+            .unwrap();
+
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "daemon");
+        assert_eq!(
+            sub.get_one::<PathBuf>("socket").unwrap(),
+            &PathBuf::from("/tmp/x.sock")
+        );
+        assert_eq!(sub.get_one::<u64>("interval").unwrap(), &5);
+    }
+
+    #[test]
+    fn test_cli_serve_subcommand() {
+        let app = build_cli();
+        let matches = app.try_get_matches_from(vec!["pwrzv", "serve"]).unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "serve");
+        assert_eq!(sub.get_one::<String>("bind").unwrap(), DEFAULT_HTTP_BIND);
+
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "daemon", "--http", "0.0.0.0:8080"])
+            .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.get_one::<String>("http").unwrap(), "0.0.0.0:8080");
+    }
+
+    #[test]
+    fn test_cli_alerts_parsing() {
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "--alerts", "rules.yaml"])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<PathBuf>("alerts").unwrap(),
+            &PathBuf::from("rules.yaml")
+        );
+
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "serve", "--alerts", "rules.yaml"])
+            .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert!(sub.get_one::<PathBuf>("alerts").is_some());
+    }
+
+    #[test]
+    fn test_cli_history_subcommand() {
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec![
+                "pwrzv",
+                "history",
+                "--from",
+                "2025-01-01 02:00",
+                "--to",
+                "2025-01-01 04:00",
+                "-r",
+                "1m",
+            ])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "history");
+        assert_eq!(sub.get_one::<String>("resolution").unwrap(), "1m");
+        assert_eq!(sub.get_one::<String>("format").unwrap(), "text");
+
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "daemon", "--store", "--retention", "raw=1d"])
+            .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.get_one::<PathBuf>("store").unwrap(), &PathBuf::new());
+
+        let app = build_cli();
+        assert!(
+            app.try_get_matches_from(vec!["pwrzv", "--retention", "raw=1d"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_query_subcommand() {
+        let app = build_cli();
+        let matches = app.try_get_matches_from(vec!["pwrzv", "query"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.get_one::<String>("what").unwrap(), "level");
+
+        let app = build_cli();
+        let matches = app
+            .try_get_matches_from(vec!["pwrzv", "query", "history", "--since", "15m"])
+            .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.get_one::<String>("what").unwrap(), "history");
+        assert_eq!(sub.get_one::<String>("since").unwrap(), "15m");
+    }
+
+    #[test]
+    fn test_cli_record_replay_subcommands() {
+        let matches = build_cli()
+            .try_get_matches_from(vec!["pwrzv", "record", "-o", "trace.ndjson", "-n", "5"])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "record");
+        assert_eq!(
+            sub.get_one::<PathBuf>("out").unwrap(),
+            &PathBuf::from("trace.ndjson")
+        );
+        assert_eq!(sub.get_one::<u64>("count").unwrap(), &5);
+
+        // --config is global, so it may follow the subcommand
+        let matches = build_cli()
+            .try_get_matches_from(vec![
+                "pwrzv",
+                "replay",
+                "trace.ndjson",
+                "--config",
+                "new.toml",
+            ])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "replay");
+        assert_eq!(
+            sub.get_one::<PathBuf>("trace").unwrap(),
+            &PathBuf::from("trace.ndjson")
+        );
+        assert_eq!(
+            sub.get_one::<PathBuf>("config").unwrap(),
+            &PathBuf::from("new.toml")
+        );
+
+        assert!(
+            build_cli()
+                .try_get_matches_from(vec!["pwrzv", "replay"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_calibrate_subcommand() {
+        let matches = build_cli()
+            .try_get_matches_from(vec![
+                "pwrzv",
+                "calibrate",
+                "db-healthy.ndjson",
+                "db-incident.ndjson",
+                "--target",
+                "p99 of healthy = 4.5",
+            ])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "calibrate");
+        assert_eq!(sub.get_many::<PathBuf>("trace").unwrap().count(), 2);
+        let targets: Vec<_> = sub
+            .get_many::<CalibrationTarget>("target")
+            .unwrap()
+            .collect();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].percentile, 99.0);
+
+        assert!(
+            build_cli()
+                .try_get_matches_from(vec!["pwrzv", "calibrate", "t.ndjson", "--target", "p95"])
+                .is_err()
+        );
+        assert!(
+            build_cli()
+                .try_get_matches_from(vec!["pwrzv", "record", "--label", "busy"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_curves_subcommand() {
+        let matches = build_cli()
+            .try_get_matches_from(vec![
+                "pwrzv",
+                "curves",
+                "--metric",
+                "memory_pressure",
+                "--at",
+                "0.1,0.3,0.5",
+            ])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "curves");
+        assert_eq!(
+            sub.get_many::<String>("metric")
+                .unwrap()
+                .collect::<Vec<_>>(),
+            ["memory_pressure"]
+        );
+        assert_eq!(
+            sub.get_many::<f32>("at")
+                .unwrap()
+                .copied()
+                .collect::<Vec<_>>(),
+            [0.1, 0.3, 0.5]
+        );
+
+        assert!(
+            build_cli()
+                .try_get_matches_from(vec!["pwrzv", "curves", "--at", "high"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_config_subcommand() {
+        let matches = build_cli()
+            .try_get_matches_from(vec!["pwrzv", "config", "show", "-f", "json"])
+            .unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        assert_eq!(name, "config");
+        let (name, show) = sub.subcommand().unwrap();
+        assert_eq!(name, "show");
+        assert_eq!(show.get_one::<String>("format").unwrap(), "json");
+
+        let matches = build_cli()
+            .try_get_matches_from(vec!["pwrzv", "--config", "pwrzv.toml", "config", "check"])
+            .unwrap();
+        assert!(matches.get_one::<PathBuf>("config").is_some());
+
+        assert!(
+            build_cli()
+                .try_get_matches_from(vec!["pwrzv", "config"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_cli_once_flag() {
         let app = build_cli();
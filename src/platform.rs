@@ -0,0 +1,173 @@
+//! Scored metrics of every supported platform
+//!
+//! The tables are compiled on every host so that traces recorded on one
+//! platform can be replayed and calibrated on another.
+
+use crate::sigmoid::MetricSpec;
+
+/// Scored Linux metrics and their default curves
+///
+/// Each curve can be overridden in the `[linux.<id>]` section of the
+/// configuration file or with `<env_prefix>_MIDPOINT` / `<env_prefix>_STEEPNESS`.
+pub(crate) const LINUX_METRICS: [MetricSpec; 13] = [
+    MetricSpec {
+        id: "cpu_usage",
+        label: "CPU Usage",
+        env_prefix: "PWRZV_LINUX_CPU_USAGE",
+        midpoint: 0.65,
+        steepness: 8.0,
+    },
+    MetricSpec {
+        id: "cpu_io_wait",
+        label: "CPU IO Wait",
+        env_prefix: "PWRZV_LINUX_CPU_IOWAIT",
+        midpoint: 0.20,
+        steepness: 20.0,
+    },
+    MetricSpec {
+        id: "cpu_steal",
+        label: "CPU Steal",
+        env_prefix: "PWRZV_LINUX_CPU_STEAL",
+        midpoint: 0.10,
+        steepness: 30.0,
+    },
+    MetricSpec {
+        id: "cpu_load",
+        label: "CPU Load",
+        env_prefix: "PWRZV_LINUX_CPU_LOAD",
+        midpoint: 1.2,
+        steepness: 5.0,
+    },
+    MetricSpec {
+        id: "memory_usage",
+        label: "Memory Usage",
+        env_prefix: "PWRZV_LINUX_MEMORY_USAGE",
+        midpoint: 0.85,
+        steepness: 18.0,
+    },
+    MetricSpec {
+        id: "memory_pressure",
+        label: "Memory Pressure",
+        env_prefix: "PWRZV_LINUX_MEMORY_PRESSURE",
+        midpoint: 0.30,
+        steepness: 12.0,
+    },
+    MetricSpec {
+        id: "disk_io_utilization",
+        label: "Disk IO Utilization",
+        env_prefix: "PWRZV_LINUX_DISK_IO",
+        midpoint: 0.70,
+        steepness: 10.0,
+    },
+    MetricSpec {
+        id: "network_dropped_packets",
+        label: "Network Dropped Packets",
+        env_prefix: "PWRZV_LINUX_NETWORK_DROPPED",
+        midpoint: 0.02,
+        steepness: 100.0,
+    },
+    MetricSpec {
+        id: "file_descriptors",
+        label: "File Descriptors",
+        env_prefix: "PWRZV_LINUX_FD",
+        midpoint: 0.90,
+        steepness: 25.0,
+    },
+    MetricSpec {
+        id: "process_count",
+        label: "Process Count",
+        env_prefix: "PWRZV_LINUX_PROCESS",
+        midpoint: 0.80,
+        steepness: 12.0,
+    },
+    MetricSpec {
+        id: "cpu_throttling",
+        label: "CPU Throttling",
+        env_prefix: "PWRZV_LINUX_CPU_THROTTLING",
+        midpoint: 0.50,
+        steepness: 10.0,
+    },
+    MetricSpec {
+        id: "thermal_load",
+        label: "Thermal Load",
+        env_prefix: "PWRZV_LINUX_THERMAL",
+        midpoint: 0.90,
+        steepness: 25.0,
+    },
+    MetricSpec {
+        id: "cpu_core_saturation",
+        label: "CPU Core Saturation",
+        env_prefix: "PWRZV_LINUX_CPU_CORE_SATURATION",
+        midpoint: 0.90,
+        steepness: 20.0,
+    },
+];
+
+/// Scored macOS metrics and their default curves
+///
+/// Each curve can be overridden in the `[macos.<id>]` section of the
+/// configuration file or with `<env_prefix>_MIDPOINT` / `<env_prefix>_STEEPNESS`.
+pub(crate) const MACOS_METRICS: [MetricSpec; 7] = [
+    MetricSpec {
+        id: "cpu_usage",
+        label: "CPU Usage",
+        env_prefix: "PWRZV_MACOS_CPU_USAGE",
+        midpoint: 0.60,
+        steepness: 8.0,
+    },
+    MetricSpec {
+        id: "cpu_load",
+        label: "CPU Load",
+        env_prefix: "PWRZV_MACOS_CPU_LOAD",
+        midpoint: 1.2,
+        steepness: 5.0,
+    },
+    MetricSpec {
+        id: "memory_usage",
+        label: "Memory Usage",
+        env_prefix: "PWRZV_MACOS_MEMORY_USAGE",
+        midpoint: 0.85,
+        steepness: 20.0,
+    },
+    MetricSpec {
+        id: "memory_compressed",
+        label: "Memory Compressed",
+        env_prefix: "PWRZV_MACOS_MEMORY_COMPRESSED",
+        midpoint: 0.60,
+        steepness: 15.0,
+    },
+    MetricSpec {
+        id: "network_dropped",
+        label: "Network Dropped",
+        env_prefix: "PWRZV_MACOS_NETWORK_DROPPED",
+        midpoint: 0.02,
+        steepness: 100.0,
+    },
+    MetricSpec {
+        id: "file_descriptors",
+        label: "File Descriptors",
+        env_prefix: "PWRZV_MACOS_FD",
+        midpoint: 0.90,
+        steepness: 30.0,
+    },
+    MetricSpec {
+        id: "process_count",
+        label: "Process Count",
+        env_prefix: "PWRZV_MACOS_PROCESS",
+        midpoint: 0.80,
+        steepness: 12.0,
+    },
+];
+
+/// Get the scored metrics of a platform
+///
+/// # Returns
+///
+/// The metric table, or `None` if pwrzv does not support the platform
+pub(crate) fn metrics_for(platform: &str) -> Option<&'static [MetricSpec]> {
+    match platform {
+        "linux" => Some(&LINUX_METRICS),
+        "macos" => Some(&MACOS_METRICS),
+        _ => None,
+    }
+}
@@ -0,0 +1,264 @@
+//! Curve plotting module
+//!
+//! Renders response curves as ASCII plots and score tables, so that curve
+//! parameters such as "midpoint 0.30, steepness 12" can be judged by the scores
+//! they produce. Used by `pwrzv curves`.
+
+use crate::curve::Curve;
+
+/// Number of value columns in a plot
+pub const PLOT_WIDTH: usize = 60;
+
+/// Number of score rows in a plot, from 5.0 down to 0.0 in steps of 0.5
+pub const PLOT_HEIGHT: usize = 11;
+
+/// Pressure at which a curve is considered saturated when choosing the range
+const SATURATION: f32 = 0.98;
+
+/// Choose a value range covering the interesting part of a curve
+///
+/// The range starts at 0 (all metrics are non-negative) and ends at a round
+/// number just past the value scoring 0.1, or past the last breakpoint.
+///
+/// # Arguments
+///
+/// * `curve` - The curve to plot
+///
+/// # Returns
+///
+/// `(min, max)` value range
+pub fn value_range(curve: &Curve) -> (f32, f32) {
+    let upper = match curve {
+        Curve::PiecewiseLinear { points } | Curve::Step { points } => {
+            points.last().map_or(1.0, |&[value, _]| value * 1.1)
+        }
+        _ => saturation_value(curve),
+    };
+    (0.0, nice_ceil(upper))
+}
+
+/// Find the value where a parametric curve saturates
+fn saturation_value(curve: &Curve) -> f32 {
+    if curve.evaluate(0.0) >= SATURATION {
+        return 1.0;
+    }
+
+    let mut high = 1.0f32;
+    while curve.evaluate(high) < SATURATION {
+        high *= 2.0;
+        if high > 1e6 {
+            return high;
+        }
+    }
+    let mut low = 0.0f32;
+    for _ in 0..32 {
+        let middle = (low + high) / 2.0;
+        if curve.evaluate(middle) < SATURATION {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    high
+}
+
+/// Round a positive number up to 1, 1.2, 1.5, 2, 2.5, 3, 4, 5, 6 or 8 times a power of ten
+pub fn nice_ceil(x: f32) -> f32 {
+    if !x.is_finite() || x <= 0.0 {
+        return 1.0;
+    }
+    // Divide for negative exponents so that e.g. 0.1 is exact as an f32 literal
+    let exponent = x.log10().floor() as i32;
+    let scale = |mantissa: f32| match exponent {
+        0.. => mantissa * 10f32.powi(exponent),
+        _ => mantissa / 10f32.powi(-exponent),
+    };
+    [1.0, 1.2, 1.5, 2.0, 2.5, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0]
+        .into_iter()
+        .map(scale)
+        .find(|&candidate| candidate >= x * (1.0 - 1e-6))
+        .unwrap_or_else(|| scale(10.0))
+}
+
+/// Get evenly spaced values over a range, both ends included
+pub fn sample_values((min, max): (f32, f32), count: usize) -> Vec<f32> {
+    match count {
+        0 => Vec::new(),
+        1 => vec![min],
+        _ => (0..count)
+            .map(|i| min + (max - min) * i as f32 / (count - 1) as f32)
+            .collect(),
+    }
+}
+
+/// Format a metric value compactly, without trailing zeros
+pub fn format_value(value: f32) -> String {
+    if value.abs() >= 1000.0 {
+        return format!("{value:.0}");
+    }
+    let formatted = format!("{value:.4}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "" | "-0" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Render a curve as an ASCII plot of score against value
+///
+/// The curve is drawn with `*`. A live value is drawn as a vertical `|` line
+/// with `o` at its score and annotated below the value axis.
+///
+/// # Arguments
+///
+/// * `curve` - The curve to plot
+/// * `range` - Value range of the horizontal axis
+/// * `live` - Current value of the metric, if known
+///
+/// # Returns
+///
+/// The plot as lines of text, each ending with a newline
+pub fn render(curve: &Curve, (min, max): (f32, f32), live: Option<f32>) -> String {
+    let span = (max - min).max(f32::EPSILON);
+    let column = |value: f32| {
+        let position = (value - min) / span * (PLOT_WIDTH - 1) as f32;
+        position.round().clamp(0.0, (PLOT_WIDTH - 1) as f32) as usize
+    };
+    let row = |score: f32| {
+        let position = (5.0 - score) / 5.0 * (PLOT_HEIGHT - 1) as f32;
+        position.round().clamp(0.0, (PLOT_HEIGHT - 1) as f32) as usize
+    };
+
+    let mut grid = vec![vec![' '; PLOT_WIDTH]; PLOT_HEIGHT];
+    for (c, value) in sample_values((min, max), PLOT_WIDTH)
+        .into_iter()
+        .enumerate()
+    {
+        grid[row(curve.score(value))][c] = '*';
+    }
+    if let Some(value) = live {
+        let c = column(value);
+        for line in grid.iter_mut() {
+            if line[c] == ' ' {
+                line[c] = '|';
+            }
+        }
+        grid[row(curve.score(value))][c] = 'o';
+    }
+
+    let mut output = String::new();
+    for (r, line) in grid.iter().enumerate() {
+        let score = 5.0 - 5.0 * r as f32 / (PLOT_HEIGHT - 1) as f32;
+        let label = if r % 2 == 0 {
+            format!("{score:>4.1}")
+        } else {
+            "    ".to_string()
+        };
+        let line: String = line.iter().collect();
+        output.push_str(&format!("{label} |{}\n", line.trim_end()));
+    }
+    output.push_str(&format!("     +{}\n", "-".repeat(PLOT_WIDTH)));
+
+    // Value labels at both ends and in the middle of the axis
+    let mut axis = vec![' '; PLOT_WIDTH + 12];
+    let labels = [
+        (0, format_value(min)),
+        (PLOT_WIDTH / 2, format_value((min + max) / 2.0)),
+        (PLOT_WIDTH - 1, format_value(max)),
+    ];
+    for (c, label) in labels {
+        let start = (c + 6).saturating_sub(label.len() / 2);
+        // Long labels such as 1e15 run past the plot
+        if axis.len() < start + label.len() {
+            axis.resize(start + label.len(), ' ');
+        }
+        for (i, ch) in label.chars().enumerate() {
+            axis[start + i] = ch;
+        }
+    }
+    output.push_str(axis.iter().collect::<String>().trim_end());
+    output.push('\n');
+
+    if let Some(value) = live {
+        let annotation = format!(
+            "now {} (score {:.2})",
+            format_value(value),
+            curve.score(value)
+        );
+        let c = column(value) + 6;
+        let line = if c + annotation.len() + 2 <= PLOT_WIDTH + 6 {
+            format!("{}^ {annotation}", " ".repeat(c))
+        } else {
+            format!(
+                "{}{annotation} ^",
+                " ".repeat(c.saturating_sub(annotation.len() + 1))
+            )
+        };
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logistic(midpoint: f32, steepness: f32) -> Curve {
+        Curve::Logistic {
+            midpoint,
+            steepness,
+        }
+    }
+
+    #[test]
+    fn test_value_range() {
+        assert_eq!(value_range(&logistic(0.65, 8.0)), (0.0, 1.2));
+        assert_eq!(value_range(&logistic(0.01, 50.0)), (0.0, 0.1));
+        assert_eq!(value_range(&logistic(1.2, 5.0)), (0.0, 2.0));
+
+        let points = Curve::PiecewiseLinear {
+            points: vec![[0.8, 5.0], [0.95, 1.0], [1.0, 0.0]],
+        };
+        assert_eq!(value_range(&points), (0.0, 1.2));
+    }
+
+    #[test]
+    fn test_nice_ceil_and_format_value() {
+        assert_eq!(nice_ceil(1.14), 1.2);
+        assert_eq!(nice_ceil(0.088), 0.1);
+        assert_eq!(nice_ceil(3.0), 3.0);
+        assert_eq!(nice_ceil(870.0), 1000.0);
+
+        assert_eq!(format_value(0.0), "0");
+        assert_eq!(format_value(0.65), "0.65");
+        assert_eq!(format_value(1.5), "1.5");
+        assert_eq!(format_value(12345.6), "12346");
+        assert_eq!(sample_values((0.0, 1.0), 3), vec![0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn test_render() {
+        let curve = logistic(0.5, 20.0);
+        let plot = render(&curve, (0.0, 1.0), None);
+        let lines: Vec<_> = plot.lines().collect();
+        assert_eq!(lines.len(), PLOT_HEIGHT + 2);
+        // Full score on the left, zero on the right
+        assert!(lines[0].starts_with(" 5.0 |*"));
+        assert!(lines[PLOT_HEIGHT - 1].ends_with('*'));
+        assert!(lines[PLOT_HEIGHT + 1].trim_start().starts_with('0'));
+        assert!(lines[PLOT_HEIGHT + 1].ends_with('1'));
+        // Only the score axis, no live marker
+        assert_eq!(plot.matches('|').count(), PLOT_HEIGHT);
+
+        let plot = render(&curve, (0.0, 1.0), Some(0.5));
+        assert!(plot.contains("^ now 0.5 (score 2.50)"));
+        // The marker sits on the 2.5 row
+        assert!(plot.lines().nth(PLOT_HEIGHT / 2).unwrap().contains('o'));
+
+        // Labels wider than the margin extend the axis line
+        let plot = render(&logistic(1e15, 1e-14), (0.0, 2e15), Some(1e15));
+        let axis = plot.lines().nth(PLOT_HEIGHT + 1).unwrap();
+        assert!(axis.ends_with(&format_value(2e15)), "{axis}");
+    }
+}
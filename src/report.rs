@@ -0,0 +1,415 @@
+//! Power reserve report module
+//!
+//! Defines the self-describing snapshot produced by each sample, shared by the
+//! CLI's detailed output, the daemon and its clients.
+
+use crate::collector::CollectorReport;
+use crate::config::PwrzvConfig;
+use crate::sigmoid::MetricSpec;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A single power reserve sample with detailed metrics
+///
+/// Serializes to the same JSON structure printed by `pwrzv --detailed json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PowerReserveReport {
+    /// Platform the sample was taken on ("linux", "macos")
+    pub platform: String,
+    /// Time the sample was taken
+    pub timestamp: DateTime<Utc>,
+    /// Power reserve level (1.0-5.0)
+    pub power_reserve_level: f32,
+    /// Human-readable description of the level
+    pub level_description: String,
+    /// Pressure scores for each available metric, keyed by display text
+    ///
+    /// The keys are for presentation; read values through [`readings`](Self::readings).
+    pub metrics: HashMap<String, f32>,
+    /// Raw value and score of each available metric, keyed by metric ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub readings: BTreeMap<String, MetricReading>,
+    /// Number of available metrics
+    pub total_metrics: usize,
+    /// Status of every collector, listing why missing metrics are missing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collectors: Vec<CollectorReport>,
+    /// Observations worth a closer look, e.g. a hypervisor taking much of the CPU
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// Number of CPUs the load was normalized to, where the platform counts them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_cpus: Option<EffectiveCpus>,
+}
+
+impl PowerReserveReport {
+    /// Create a report for the current platform stamped with the current time
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - Power reserve level (1.0-5.0)
+    /// * `metrics` - Pressure scores for each available metric
+    pub fn new(level: f32, metrics: HashMap<String, f32>) -> Self {
+        Self {
+            platform: std::env::consts::OS.to_string(),
+            timestamp: Utc::now(),
+            power_reserve_level: level,
+            level_description: level_description(level).to_string(),
+            total_metrics: metrics.len(),
+            metrics,
+            readings: BTreeMap::new(),
+            collectors: Vec::new(),
+            notes: Vec::new(),
+            effective_cpus: None,
+        }
+    }
+}
+
+impl PowerReserveReport {
+    /// Get the raw value and score of every metric, keyed by metric ID
+    ///
+    /// Reports without structured readings, such as those of older daemons,
+    /// fall back to parsing the display keys: metric IDs are the snake_case
+    /// form of the labels, e.g. `"Memory Pressure: 0.412 (Score: 1.870)"`
+    /// becomes `memory_pressure`, and values are rounded to the displayed digits.
+    pub fn readings(&self) -> BTreeMap<String, MetricReading> {
+        if !self.readings.is_empty() {
+            return self.readings.clone();
+        }
+        self.metrics
+            .iter()
+            .filter_map(|(key, &score)| {
+                let (label, rest) = key.split_once(": ")?;
+                let value = rest
+                    .split(" (")
+                    .next()?
+                    .trim_end_matches(',')
+                    .parse()
+                    .ok()?;
+                Some((metric_id(label), MetricReading { value, score }))
+            })
+            .collect()
+    }
+
+    /// Get the reading of a single metric by ID
+    pub fn reading(&self, id: &str) -> Option<MetricReading> {
+        match self.readings.get(id) {
+            Some(&reading) => Some(reading),
+            None => self.readings().remove(id),
+        }
+    }
+}
+
+/// Score the enabled metrics among raw values, keyed by metric ID
+///
+/// Metrics missing from `inputs` or disabled in `config` are left out.
+pub(crate) fn score_readings(
+    platform: &str,
+    specs: &[MetricSpec],
+    inputs: &BTreeMap<String, f32>,
+    config: &PwrzvConfig,
+) -> BTreeMap<String, MetricReading> {
+    specs
+        .iter()
+        .filter(|spec| config.metric_enabled(platform, spec.id))
+        .filter_map(|spec| {
+            let value = *inputs.get(spec.id)?;
+            let score = spec.curve(platform, config).score(value);
+            Some((spec.id.to_string(), MetricReading { value, score }))
+        })
+        .collect()
+}
+
+/// Calculate the power reserve level from raw values, keyed by metric ID
+///
+/// Metrics missing from `inputs` or disabled in `config` are skipped, and
+/// scores are combined with the configured weights. If no metrics are
+/// available, a default level of 3.0 is returned.
+///
+/// # Returns
+///
+/// * `level` - The power reserve level
+/// * `details` - The score of every metric, keyed by its label and value
+pub(crate) fn score_inputs(
+    platform: &str,
+    specs: &[MetricSpec],
+    inputs: &BTreeMap<String, f32>,
+    config: &PwrzvConfig,
+) -> (f32, HashMap<String, f32>) {
+    let mut details = HashMap::new();
+    let mut available_scores = Vec::new();
+
+    let readings = score_readings(platform, specs, inputs, config);
+    for spec in specs {
+        let Some(&MetricReading { value, score: n }) = readings.get(spec.id) else {
+            continue;
+        };
+        details.insert(format!("{}: {value:.3} (Score: {n:.3})", spec.label), n);
+        available_scores.push((n, config.metric_weight(platform, spec.id)));
+    }
+
+    // Combine the scores (by default the bottleneck determines power reserve)
+    let level = config
+        .aggregation
+        .apply_weighted(&available_scores)
+        .unwrap_or(3.0);
+    (level, details)
+}
+
+/// Raw value and 0-5 score of a single metric
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricReading {
+    /// Raw metric value before scoring (usually a ratio)
+    pub value: f32,
+    /// Score of the metric (0.0-5.0)
+    pub score: f32,
+}
+
+/// Where an effective CPU count comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CpuCountSource {
+    /// The `processor` entries of `/proc/cpuinfo`
+    Cpuinfo,
+    /// The CPU affinity mask of this process (`sched_getaffinity`)
+    Affinity,
+    /// The CPUs of this process's cgroup (`cpuset.cpus.effective`)
+    Cpuset,
+    /// The CPU bandwidth limit of this process's cgroup or an ancestor (`cpu.max`)
+    Quota,
+}
+
+impl fmt::Display for CpuCountSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            Self::Cpuinfo => "/proc/cpuinfo",
+            Self::Affinity => "sched_getaffinity",
+            Self::Cpuset => "cpuset.cpus.effective",
+            Self::Quota => "cpu.max quota",
+        };
+        f.write_str(source)
+    }
+}
+
+/// Number of CPUs available to this process
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct EffectiveCpus {
+    /// CPU count; fractional when it comes from a quota
+    pub count: f32,
+    /// Where the count comes from
+    pub source: CpuCountSource,
+}
+
+#[cfg(target_os = "linux")]
+impl EffectiveCpus {
+    /// Pick the smallest of several CPU counts
+    ///
+    /// On a tie the earlier candidate wins, so list the most specific source first.
+    pub(crate) fn smallest(
+        candidates: impl IntoIterator<Item = (f32, CpuCountSource)>,
+    ) -> Option<Self> {
+        candidates
+            .into_iter()
+            .filter(|&(count, _)| count > 0.0)
+            .reduce(|smallest, candidate| {
+                if candidate.0 < smallest.0 {
+                    candidate
+                } else {
+                    smallest
+                }
+            })
+            .map(|(count, source)| Self { count, source })
+    }
+
+    /// Describe the count for the detailed report
+    pub(crate) fn note(&self) -> String {
+        let count = (self.count * 100.0).round() / 100.0;
+        let unit = if count == 1.0 { "CPU" } else { "CPUs" };
+        format!("CPU load is normalized to {count} {unit} ({})", self.source)
+    }
+}
+
+/// Convert a metric label such as `CPU IO Wait` to its ID (`cpu_io_wait`)
+pub(crate) fn metric_id(label: &str) -> String {
+    label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The power reserve level of a report without its detailed metrics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelSample {
+    /// Time the sample was taken
+    pub timestamp: DateTime<Utc>,
+    /// Power reserve level (1.0-5.0)
+    pub power_reserve_level: f32,
+    /// Human-readable description of the level
+    pub level_description: String,
+}
+
+impl From<&PowerReserveReport> for LevelSample {
+    fn from(report: &PowerReserveReport) -> Self {
+        Self {
+            timestamp: report.timestamp,
+            power_reserve_level: report.power_reserve_level,
+            level_description: report.level_description.clone(),
+        }
+    }
+}
+
+/// Format level description based on numeric value
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::level_description;
+///
+/// assert_eq!(level_description(4.5), "Abundant - Excellent performance");
+/// ```
+pub fn level_description(level: f32) -> &'static str {
+    if level >= 4.0 {
+        "Abundant - Excellent performance"
+    } else if level >= 3.0 {
+        "High - Good performance"
+    } else if level >= 2.0 {
+        "Medium - Normal performance"
+    } else if level >= 1.0 {
+        "Low - Degraded performance"
+    } else {
+        "Critical - Poor performance"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_new() {
+        let mut metrics = HashMap::new();
+        metrics.insert("CPU Usage: 0.500 (Score: 3.000)".to_string(), 3.0);
+
+        let report = PowerReserveReport::new(3.0, metrics);
+        assert_eq!(report.platform, std::env::consts::OS);
+        assert_eq!(report.total_metrics, 1);
+        assert_eq!(report.level_description, "High - Good performance");
+    }
+
+    #[test]
+    fn test_report_json_roundtrip() {
+        let report = PowerReserveReport::new(2.5, HashMap::new());
+        let json = serde_json::to_value(&report).unwrap();
+
+        for key in [
+            "platform",
+            "timestamp",
+            "power_reserve_level",
+            "level_description",
+            "metrics",
+            "total_metrics",
+        ] {
+            assert!(json.get(key).is_some(), "Missing key '{key}'");
+        }
+
+        let parsed: PowerReserveReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, report);
+
+        let mut report = report;
+        report.effective_cpus = Some(EffectiveCpus {
+            count: 1.5,
+            source: CpuCountSource::Quota,
+        });
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["effective_cpus"],
+            serde_json::json!({ "count": 1.5, "source": "quota" })
+        );
+        let parsed: PowerReserveReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, report);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_effective_cpus() {
+        let cpus = EffectiveCpus::smallest([
+            (4.0, CpuCountSource::Cpuset),
+            (4.0, CpuCountSource::Affinity),
+            (96.0, CpuCountSource::Cpuinfo),
+        ])
+        .unwrap();
+        assert_eq!(cpus.source, CpuCountSource::Cpuset);
+        assert_eq!(
+            cpus.note(),
+            "CPU load is normalized to 4 CPUs (cpuset.cpus.effective)"
+        );
+
+        let cpus = EffectiveCpus::smallest([
+            (2.0 / 3.0, CpuCountSource::Quota),
+            (4.0, CpuCountSource::Cpuset),
+        ])
+        .unwrap();
+        assert_eq!(
+            cpus.note(),
+            "CPU load is normalized to 0.67 CPUs (cpu.max quota)"
+        );
+        assert_eq!(
+            EffectiveCpus::smallest([(0.0, CpuCountSource::Cpuset)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_report_readings() {
+        let mut metrics = HashMap::new();
+        metrics.insert("Memory Pressure: 0.412 (Score: 1.870)".to_string(), 1.87);
+        metrics.insert("CPU IO Wait: 0.050 (Score: 4.900)".to_string(), 4.9);
+        // macOS label format
+        metrics.insert("Process Count: 0.25, (Score: 4.5)".to_string(), 4.5);
+
+        let report = PowerReserveReport::new(1.87, metrics);
+        let readings = report.readings();
+        assert_eq!(readings.len(), 3);
+        assert_eq!(
+            report.reading("memory_pressure"),
+            Some(MetricReading {
+                value: 0.412,
+                score: 1.87
+            })
+        );
+        assert_eq!(readings["cpu_io_wait"].value, 0.05);
+        assert_eq!(readings["process_count"].score, 4.5);
+        assert!(report.reading("swap").is_none());
+
+        // Structured readings take precedence over the display keys
+        let mut report = report;
+        report.readings = BTreeMap::from([(
+            "memory_pressure".to_string(),
+            MetricReading {
+                value: 0.41234,
+                score: 1.87,
+            },
+        )]);
+        assert_eq!(report.reading("memory_pressure").unwrap().value, 0.41234);
+        assert_eq!(report.readings().len(), 1);
+    }
+
+    #[test]
+    fn test_metric_id() {
+        assert_eq!(metric_id("Disk IO Utilization"), "disk_io_utilization");
+        assert_eq!(metric_id("CPU Usage"), "cpu_usage");
+    }
+
+    #[test]
+    fn test_level_description() {
+        assert_eq!(level_description(4.0), "Abundant - Excellent performance");
+        assert_eq!(level_description(3.2), "High - Good performance");
+        assert_eq!(level_description(2.0), "Medium - Normal performance");
+        assert_eq!(level_description(1.5), "Low - Degraded performance");
+        assert_eq!(level_description(0.2), "Critical - Poor performance");
+    }
+}
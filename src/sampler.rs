@@ -0,0 +1,526 @@
+//! Background sampler module
+//!
+//! Samples the power reserve on a fixed interval in a background task and keeps
+//! the latest report plus a bounded history, so that many consumers can share a
+//! single collection loop.
+//!
+//! [`watch`] and [`watch_latest`] hand out samples of a sampler shared by every
+//! caller asking for the same interval, so subscribers don't multiply the
+//! collection cost.
+//!
+//! Rates such as disk activity and per-core usage are measured against the
+//! previous sample taken anywhere in the process, and the collection budget
+//! uses the latest cost of every collector in the process. Samplers running at
+//! different intervals therefore measure those rates over the time since
+//! whichever of them sampled last; prefer a single sampler per process.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::report::PowerReserveReport;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Weak;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::WatchStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Default time between samples for consumers of a shared sampler
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Number of reports buffered for each subscriber before it starts lagging
+const SUBSCRIBER_BUFFER: usize = 16;
+
+/// Latest sample as published to watchers; `None` until the first sample
+pub type LatestSample = Option<PwrzvResult<Arc<PowerReserveReport>>>;
+
+/// Samplers shared between [`watch`] and [`watch_latest`] callers, by interval
+static SHARED: Mutex<Vec<(Duration, Weak<SamplerShared>)>> = Mutex::new(Vec::new());
+
+/// Boxed report source used by the sampling loop
+type ReportSource = Arc<
+    dyn Fn() -> Pin<Box<dyn Future<Output = PwrzvResult<PowerReserveReport>> + Send>> + Send + Sync,
+>;
+
+/// Mutable sampler state guarded by a mutex
+#[derive(Debug, Default)]
+struct SamplerState {
+    latest: Option<Arc<PowerReserveReport>>,
+    history: VecDeque<Arc<PowerReserveReport>>,
+    last_error: Option<String>,
+}
+
+/// State shared between the sampling task and all sampler handles
+struct SamplerShared {
+    state: Mutex<SamplerState>,
+    sender: broadcast::Sender<Arc<PowerReserveReport>>,
+    latest: watch::Sender<LatestSample>,
+    history_capacity: usize,
+    interval: Duration,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Drop for SamplerShared {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+}
+
+/// Handle to a background power reserve sampling task
+///
+/// Cloning the handle is cheap and all clones observe the same samples.
+/// The background task stops when the last handle is dropped.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::Sampler;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> pwrzv::PwrzvResult<()> {
+///     let sampler = Sampler::start(Duration::from_secs(3), 1200)?;
+///     let mut samples = sampler.subscribe();
+///     while let Ok(report) = samples.recv().await {
+///         println!("Power Reserve: {:.2}", report.power_reserve_level);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Sampler {
+    shared: Arc<SamplerShared>,
+}
+
+impl std::fmt::Debug for Sampler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sampler")
+            .field("interval", &self.shared.interval)
+            .field("history_capacity", &self.shared.history_capacity)
+            .finish()
+    }
+}
+
+impl Sampler {
+    /// Start sampling the host's power reserve in the background
+    ///
+    /// Must be called from within a tokio runtime. The first sample is taken immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between samples
+    /// * `history_capacity` - Maximum number of reports kept in memory
+    ///
+    /// # Errors
+    ///
+    /// Returns an invalid value error if `interval` is zero.
+    pub fn start(interval: Duration, history_capacity: usize) -> PwrzvResult<Self> {
+        check_interval(interval)?;
+        Ok(Self::start_host(interval, history_capacity))
+    }
+
+    /// Start sampling the host with an interval known to be non-zero
+    fn start_host(interval: Duration, history_capacity: usize) -> Self {
+        Self::start_with(interval, history_capacity, || {
+            Box::pin(crate::get_power_reserve_report())
+        })
+    }
+
+    /// Start sampling with a custom report source
+    pub(crate) fn start_with<F>(interval: Duration, history_capacity: usize, source: F) -> Self
+    where
+        F: Fn() -> Pin<Box<dyn Future<Output = PwrzvResult<PowerReserveReport>> + Send>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let (sender, _) = broadcast::channel(SUBSCRIBER_BUFFER);
+        let shared = Arc::new(SamplerShared {
+            state: Mutex::new(SamplerState::default()),
+            sender,
+            latest: watch::Sender::new(None),
+            history_capacity: history_capacity.max(1),
+            interval,
+            task: Mutex::new(None),
+        });
+
+        let source: ReportSource = Arc::new(source);
+        let task = tokio::spawn(Self::run(Arc::downgrade(&shared), source, interval));
+        *shared.task.lock().unwrap() = Some(task);
+
+        Self { shared }
+    }
+
+    /// Get the sampler shared by all callers asking for `interval`
+    ///
+    /// Starts a new sampler if none is running for that interval yet. The
+    /// interval must have passed [`check_interval`].
+    pub(crate) fn shared(interval: Duration) -> Self {
+        Self::shared_with(interval, || Self::start_host(interval, 1))
+    }
+
+    /// Get the shared sampler for `interval`, starting it with `start` if needed
+    fn shared_with(interval: Duration, start: impl FnOnce() -> Self) -> Self {
+        let mut samplers = SHARED.lock().unwrap();
+        samplers.retain(|(_, shared)| shared.strong_count() > 0);
+
+        let running = samplers
+            .iter()
+            .filter(|(shared_interval, _)| *shared_interval == interval)
+            .filter_map(|(_, shared)| shared.upgrade())
+            .find(|shared| {
+                // The task is gone if the runtime it was spawned on has shut down
+                shared
+                    .task
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|task| !task.is_finished())
+            });
+        if let Some(shared) = running {
+            return Self { shared };
+        }
+
+        let sampler = start();
+        samplers.push((interval, Arc::downgrade(&sampler.shared)));
+        sampler
+    }
+
+    /// Sampling loop; exits once every handle has been dropped
+    async fn run(shared: Weak<SamplerShared>, source: ReportSource, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            let result = source().await;
+
+            let Some(shared) = shared.upgrade() else {
+                return;
+            };
+            shared.record(result);
+        }
+    }
+
+    /// Get the sampling interval
+    pub fn interval(&self) -> Duration {
+        self.shared.interval
+    }
+
+    /// Get the most recent report, if any sample has succeeded yet
+    pub fn latest(&self) -> Option<Arc<PowerReserveReport>> {
+        self.shared.state.lock().unwrap().latest.clone()
+    }
+
+    /// Get the error of the most recent sample, if it failed
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.state.lock().unwrap().last_error.clone()
+    }
+
+    /// Get all buffered reports taken at or after `since`, oldest first
+    ///
+    /// Passing `None` returns the whole buffered history.
+    pub fn history_since(&self, since: Option<DateTime<Utc>>) -> Vec<Arc<PowerReserveReport>> {
+        let state = self.shared.state.lock().unwrap();
+        state
+            .history
+            .iter()
+            .filter(|report| since.is_none_or(|since| report.timestamp >= since))
+            .cloned()
+            .collect()
+    }
+
+    /// Subscribe to every new report
+    ///
+    /// Slow subscribers that fall more than a few samples behind receive a
+    /// `Lagged` error and skip ahead to the newest report.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<PowerReserveReport>> {
+        self.shared.sender.subscribe()
+    }
+
+    /// Watch the latest sample, successful or not
+    ///
+    /// The receiver holds `None` until the first sample is taken. Unlike
+    /// [`subscribe`](Self::subscribe) it also sees failed samples, but a slow
+    /// reader only ever sees the newest one. The receiver reports the sender
+    /// closed once the last sampler handle is dropped.
+    pub fn watch(&self) -> watch::Receiver<LatestSample> {
+        self.shared.latest.subscribe()
+    }
+
+    /// Stream every sample, starting with the latest one if there is one
+    ///
+    /// The stream keeps the sampler running until it is dropped. A slow reader
+    /// skips ahead to the newest sample instead of lagging.
+    pub fn stream(
+        &self,
+    ) -> impl Stream<Item = PwrzvResult<Arc<PowerReserveReport>>> + Send + Unpin + use<> {
+        let sampler = self.clone();
+        WatchStream::new(self.watch()).filter_map(move |sample| {
+            let _keep_alive = &sampler;
+            sample
+        })
+    }
+}
+
+/// Stream power reserve samples taken every `interval`
+///
+/// All streams and receivers for the same interval share one background
+/// sampler, which stops once the last of them is dropped. Must be called from
+/// within a tokio runtime.
+///
+/// # Arguments
+///
+/// * `interval` - Time between samples
+///
+/// # Errors
+///
+/// Returns an invalid value error if `interval` is zero.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use tokio_stream::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> pwrzv::PwrzvResult<()> {
+///     let mut samples = pwrzv::watch(Duration::from_secs(5))?;
+///     while let Some(sample) = samples.next().await {
+///         match sample {
+///             Ok(report) => println!("Power Reserve: {:.2}", report.power_reserve_level),
+///             Err(e) => eprintln!("Sample failed: {e}"),
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn watch(
+    interval: Duration,
+) -> PwrzvResult<impl Stream<Item = PwrzvResult<Arc<PowerReserveReport>>> + Send + Unpin + 'static>
+{
+    check_interval(interval)?;
+    Ok(Sampler::shared(interval).stream())
+}
+
+/// Watch the latest power reserve sample taken every `interval`
+///
+/// The receiver always holds the newest sample (`None` until the first one is
+/// taken) and shares its sampler with [`watch()`] callers using the same
+/// interval. The sampler keeps running while any receiver is alive. Must be
+/// called from within a tokio runtime.
+///
+/// # Arguments
+///
+/// * `interval` - Time between samples
+///
+/// # Errors
+///
+/// Returns an invalid value error if `interval` is zero.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> pwrzv::PwrzvResult<()> {
+///     let mut latest = pwrzv::watch_latest(Duration::from_secs(5))?;
+///     while latest.changed().await.is_ok() {
+///         if let Some(Ok(report)) = &*latest.borrow_and_update() {
+///             println!("Power Reserve: {:.2}", report.power_reserve_level);
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn watch_latest(interval: Duration) -> PwrzvResult<watch::Receiver<LatestSample>> {
+    check_interval(interval)?;
+    let sampler = Sampler::shared(interval);
+    let receiver = sampler.watch();
+    tokio::spawn(async move { sampler.shared.latest.closed().await });
+    Ok(receiver)
+}
+
+/// Reject a zero sampling interval, which would sample in a busy loop
+pub(crate) fn check_interval(interval: Duration) -> PwrzvResult<()> {
+    if interval.is_zero() {
+        return Err(PwrzvError::invalid_value(
+            "the sampling interval must be greater than zero",
+        ));
+    }
+    Ok(())
+}
+
+impl SamplerShared {
+    /// Store a sample result and notify subscribers
+    fn record(&self, result: PwrzvResult<PowerReserveReport>) {
+        let result = result.map(Arc::new);
+        self.latest.send_replace(Some(result.clone()));
+
+        match result {
+            Ok(report) => {
+                {
+                    let mut state = self.state.lock().unwrap();
+                    state.latest = Some(Arc::clone(&report));
+                    state.last_error = None;
+                    state.history.push_back(Arc::clone(&report));
+                    while state.history.len() > self.history_capacity {
+                        state.history.pop_front();
+                    }
+                }
+                // No subscribers is not an error
+                let _ = self.sender.send(report);
+            }
+            Err(e) => {
+                self.state.lock().unwrap().last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Sampler producing levels 1.0, 2.0, 3.0, ... from a counter
+    fn counting_sampler(interval: Duration, capacity: usize) -> (Sampler, Arc<AtomicUsize>) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let source_counter = Arc::clone(&counter);
+        let sampler = Sampler::start_with(interval, capacity, move || {
+            let n = source_counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move { Ok(PowerReserveReport::new(n as f32, HashMap::new())) })
+        });
+        (sampler, counter)
+    }
+
+    #[tokio::test]
+    async fn test_sampler_keeps_bounded_history() {
+        let (sampler, _) = counting_sampler(Duration::from_millis(5), 3);
+        let mut rx = sampler.subscribe();
+        for _ in 0..5 {
+            rx.recv().await.unwrap();
+        }
+
+        let history = sampler.history_since(None);
+        assert_eq!(history.len(), 3);
+        assert!(history.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(
+            sampler.latest().unwrap().power_reserve_level,
+            history.last().unwrap().power_reserve_level
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sampler_history_since() {
+        let (sampler, _) = counting_sampler(Duration::from_millis(5), 100);
+        let mut rx = sampler.subscribe();
+        rx.recv().await.unwrap();
+        let cutoff = rx.recv().await.unwrap().timestamp;
+        rx.recv().await.unwrap();
+
+        let recent = sampler.history_since(Some(cutoff));
+        assert!(!recent.is_empty());
+        assert!(recent.iter().all(|r| r.timestamp >= cutoff));
+        assert!(recent.len() < sampler.history_since(None).len());
+    }
+
+    #[tokio::test]
+    async fn test_sampler_records_errors() {
+        let sampler = Sampler::start_with(Duration::from_millis(5), 10, || {
+            Box::pin(async { Err(crate::PwrzvError::collection_error("boom")) })
+        });
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        assert!(sampler.latest().is_none());
+        assert!(sampler.last_error().unwrap().contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_shared_sampler_is_reused() {
+        let interval = Duration::from_millis(7);
+        let (sampler, _) = counting_sampler(interval, 1);
+        let first = Sampler::shared_with(interval, || sampler);
+        let second = Sampler::shared_with(interval, || panic!("sampler started twice"));
+        assert!(Arc::ptr_eq(&first.shared, &second.shared));
+
+        let other = Sampler::shared_with(Duration::from_millis(8), || {
+            counting_sampler(Duration::from_millis(8), 1).0
+        });
+        assert!(!Arc::ptr_eq(&first.shared, &other.shared));
+    }
+
+    #[tokio::test]
+    async fn test_sampler_stream_yields_failures() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let source_counter = Arc::clone(&counter);
+        let sampler = Sampler::start_with(Duration::from_millis(5), 10, move || {
+            let n = source_counter.fetch_add(1, Ordering::SeqCst) + 1;
+            Box::pin(async move {
+                match n % 2 {
+                    0 => Err(crate::PwrzvError::collection_error("boom")),
+                    _ => Ok(PowerReserveReport::new(n as f32, HashMap::new())),
+                }
+            })
+        });
+        let mut samples = sampler.stream();
+        drop(sampler);
+
+        let (mut reports, mut failures) = (0, 0);
+        while reports == 0 || failures == 0 {
+            match samples.next().await.unwrap() {
+                Ok(_) => reports += 1,
+                Err(e) => {
+                    assert!(e.is_transient());
+                    failures += 1;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampler_watch_holds_latest() {
+        let (sampler, _) = counting_sampler(Duration::from_millis(5), 10);
+        let mut latest = sampler.watch();
+        latest.changed().await.unwrap();
+        latest.changed().await.unwrap();
+
+        let level = match &*latest.borrow_and_update() {
+            Some(Ok(report)) => report.power_reserve_level,
+            other => panic!("unexpected sample: {other:?}"),
+        };
+        assert!(level >= 2.0);
+
+        drop(sampler);
+        assert!(latest.changed().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval() {
+        for error in [
+            Sampler::start(Duration::ZERO, 1).unwrap_err(),
+            watch(Duration::ZERO).err().unwrap(),
+            watch_latest(Duration::ZERO).unwrap_err(),
+        ] {
+            assert!(matches!(error, PwrzvError::InvalidValue { .. }), "{error}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampler_stops_when_dropped() {
+        let (sampler, counter) = counting_sampler(Duration::from_millis(5), 10);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(sampler);
+
+        let stopped_at = counter.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(counter.load(Ordering::SeqCst) <= stopped_at + 1);
+    }
+}
@@ -0,0 +1,482 @@
+//! Load shedding middleware module
+//!
+//! A tower [`Layer`] that turns requests away while the host's power reserve
+//! is below a threshold. The level is read from a [`Sampler`] refreshed in the
+//! background, so admission control costs a mutex lock per request rather than
+//! a collection.
+//!
+//! Requests are shed either outright or, with [`LoadShedLayer::probabilistic`],
+//! with a probability growing with how far the level is below the threshold.
+//! Shed requests are answered with a response built by the configured closure,
+//! or held back for up to [`LoadShedLayer::delay`] in case the host recovers.
+//!
+//! The level is unknown before the first sample and once the latest successful
+//! sample is more than [`STALE_SAMPLES`] intervals old, e.g. because sampling
+//! keeps failing on an overloaded host. Requests are then admitted, or shed
+//! with [`LoadShedLayer::fail_closed`].
+//!
+//! Requires the `tower` feature.
+
+use crate::error::PwrzvResult;
+use crate::sampler::{DEFAULT_SAMPLE_INTERVAL, Sampler, check_interval};
+use chrono::Utc;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Number of sampling intervals after which the latest level is unknown
+pub const STALE_SAMPLES: u32 = 3;
+
+/// Level passed to the rejection closure when requests are shed on an unknown level
+const UNKNOWN_LEVEL: f32 = 1.0;
+
+/// Builds the response for a shed request from the current level
+type Reject<R> = Arc<dyn Fn(f32) -> R + Send + Sync>;
+
+/// When and how requests are shed
+struct ShedPolicy<R> {
+    threshold: f32,
+    floor: Option<f32>,
+    delay: Option<Duration>,
+    fail_closed: bool,
+    reject: Reject<R>,
+}
+
+impl<R> Clone for ShedPolicy<R> {
+    fn clone(&self) -> Self {
+        Self {
+            threshold: self.threshold,
+            floor: self.floor,
+            delay: self.delay,
+            fail_closed: self.fail_closed,
+            reject: Arc::clone(&self.reject),
+        }
+    }
+}
+
+impl<R> ShedPolicy<R> {
+    /// Decide whether to shed a request at `level`
+    ///
+    /// `roll` is uniformly distributed in `[0, 1)`; it is only consulted in
+    /// probabilistic mode, where the shed probability rises linearly from 0 at
+    /// the threshold to 1 at the floor.
+    fn should_shed(&self, level: f32, roll: f64) -> bool {
+        if level >= self.threshold {
+            return false;
+        }
+        match self.floor {
+            Some(floor) if floor < self.threshold => {
+                let probability = (self.threshold - level) / (self.threshold - floor);
+                roll < f64::from(probability.clamp(0.0, 1.0))
+            }
+            _ => true,
+        }
+    }
+
+    /// Decide whether to shed a request given the sampler's latest report
+    ///
+    /// Failed samples leave the latest report in place, so the level is read
+    /// from the latest successful sample unless it is stale.
+    ///
+    /// # Returns
+    ///
+    /// The level to pass to the rejection, or `None` to admit the request
+    fn shed_level(&self, sampler: &Sampler) -> Option<f32> {
+        let max_age = sampler.interval() * STALE_SAMPLES;
+        let level = sampler
+            .latest()
+            .filter(|report| {
+                // Timestamps from the future count as fresh
+                let age = (Utc::now() - report.timestamp).to_std().unwrap_or_default();
+                age <= max_age
+            })
+            .map(|report| report.power_reserve_level);
+        match level {
+            Some(level) => self.should_shed(level, random_roll()).then_some(level),
+            None => self.fail_closed.then_some(UNKNOWN_LEVEL),
+        }
+    }
+}
+
+/// Draw a number uniformly distributed in `[0, 1)`
+fn random_roll() -> f64 {
+    // Every `RandomState` is seeded differently, which is random enough to
+    // spread shedding across requests
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Tower layer shedding requests while the power reserve is low
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::shed::LoadShedLayer;
+/// use std::time::Duration;
+///
+/// # fn build() -> LoadShedLayer<&'static str> {
+/// // Shed some requests below 2.5 and all of them at 1.5, answering with a
+/// // canned response. With axum, the closure would return an
+/// // `axum::response::Response` such as `StatusCode::SERVICE_UNAVAILABLE`.
+/// LoadShedLayer::new(2.5, |_level| "503 Service Unavailable")
+///     .probabilistic(1.5)
+///     .delay(Duration::from_millis(200))
+/// # }
+/// ```
+pub struct LoadShedLayer<R> {
+    policy: Arc<ShedPolicy<R>>,
+    sampler: Option<Sampler>,
+    interval: Duration,
+}
+
+impl<R> Clone for LoadShedLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            policy: Arc::clone(&self.policy),
+            sampler: self.sampler.clone(),
+            interval: self.interval,
+        }
+    }
+}
+
+impl<R> std::fmt::Debug for LoadShedLayer<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadShedLayer")
+            .field("threshold", &self.policy.threshold)
+            .field("floor", &self.policy.floor)
+            .field("delay", &self.policy.delay)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl<R> LoadShedLayer<R> {
+    /// Shed every request while the level is below `threshold`
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - Power reserve level (1.0-5.0) below which requests are shed
+    /// * `reject` - Builds the response for a shed request from the current level
+    pub fn new(threshold: f32, reject: impl Fn(f32) -> R + Send + Sync + 'static) -> Self {
+        Self {
+            policy: Arc::new(ShedPolicy {
+                threshold,
+                floor: None,
+                delay: None,
+                fail_closed: false,
+                reject: Arc::new(reject),
+            }),
+            sampler: None,
+            interval: DEFAULT_SAMPLE_INTERVAL,
+        }
+    }
+
+    /// Shed requests with a probability proportional to the shortfall
+    ///
+    /// The probability is 0 at the threshold and reaches 1 at `floor`, so
+    /// traffic is trimmed gradually as the reserve drains.
+    pub fn probabilistic(self, floor: f32) -> Self {
+        self.with_policy(|policy| policy.floor = Some(floor))
+    }
+
+    /// Hold shed requests back for up to `delay` before rejecting them
+    ///
+    /// The request is re-evaluated on every new sample and forwarded as soon
+    /// as it would no longer be shed.
+    pub fn delay(self, delay: Duration) -> Self {
+        self.with_policy(|policy| policy.delay = Some(delay))
+    }
+
+    /// Shed every request while the level is unknown instead of admitting it
+    ///
+    /// The level is unknown before the first sample and once the latest
+    /// successful sample is more than [`STALE_SAMPLES`] intervals old. The
+    /// rejection is then built for level 1.0.
+    pub fn fail_closed(self) -> Self {
+        self.with_policy(|policy| policy.fail_closed = true)
+    }
+
+    /// Read the level from the shared sampler taking a sample every `interval`
+    ///
+    /// Defaults to [`DEFAULT_SAMPLE_INTERVAL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an invalid value error if `interval` is zero.
+    pub fn interval(mut self, interval: Duration) -> PwrzvResult<Self> {
+        check_interval(interval)?;
+        self.interval = interval;
+        Ok(self)
+    }
+
+    /// Read the level from an existing sampler, e.g. the one behind a daemon
+    pub fn sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Change the policy of this layer, leaving services already built unchanged
+    fn with_policy(mut self, change: impl FnOnce(&mut ShedPolicy<R>)) -> Self {
+        change(Arc::make_mut(&mut self.policy));
+        self
+    }
+}
+
+impl<S, R> Layer<S> for LoadShedLayer<R> {
+    type Service = LoadShed<S, R>;
+
+    /// Wrap `inner`; must be called from within a tokio runtime
+    fn layer(&self, inner: S) -> Self::Service {
+        let sampler = self
+            .sampler
+            .clone()
+            .unwrap_or_else(|| Sampler::shared(self.interval));
+        LoadShed {
+            inner,
+            policy: Arc::clone(&self.policy),
+            sampler,
+        }
+    }
+}
+
+/// Tower service shedding requests while the power reserve is low
+///
+/// Created by [`LoadShedLayer`].
+pub struct LoadShed<S, R> {
+    inner: S,
+    policy: Arc<ShedPolicy<R>>,
+    sampler: Sampler,
+}
+
+impl<S: Clone, R> Clone for LoadShed<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            policy: Arc::clone(&self.policy),
+            sampler: self.sampler.clone(),
+        }
+    }
+}
+
+impl<S: std::fmt::Debug, R> std::fmt::Debug for LoadShed<S, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadShed")
+            .field("inner", &self.inner)
+            .field("threshold", &self.policy.threshold)
+            .finish()
+    }
+}
+
+impl<S, Request, R> Service<Request> for LoadShed<S, R>
+where
+    S: Service<Request, Response = R> + Clone + Send + 'static,
+    S::Future: Send,
+    S::Error: Send,
+    Request: Send + 'static,
+    R: Send + 'static,
+{
+    type Response = R;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<R, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Keep the service that was polled ready; leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let Some(level) = self.policy.shed_level(&self.sampler) else {
+            return Box::pin(inner.call(request));
+        };
+        let policy = Arc::clone(&self.policy);
+        let Some(delay) = policy.delay else {
+            return Box::pin(async move { Ok((policy.reject)(level)) });
+        };
+
+        let mut samples = self.sampler.watch();
+        let sampler = self.sampler.clone();
+        Box::pin(async move {
+            let deadline = tokio::time::Instant::now() + delay;
+            let mut level = level;
+            loop {
+                match tokio::time::timeout_at(deadline, samples.changed()).await {
+                    Ok(Ok(())) => match policy.shed_level(&sampler) {
+                        Some(current) => level = current,
+                        None => return inner.call(request).await,
+                    },
+                    // Out of time, or the sampler stopped
+                    Ok(Err(_)) | Err(_) => return Ok((policy.reject)(level)),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PwrzvResult;
+    use crate::report::PowerReserveReport;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::sync::Mutex;
+
+    /// Service answering every request with "ok"
+    #[derive(Clone)]
+    struct Ok200;
+
+    impl Service<()> for Ok200 {
+        type Response = &'static str;
+        type Error = Infallible;
+        type Future = std::future::Ready<Result<&'static str, Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: ()) -> Self::Future {
+            std::future::ready(Ok("ok"))
+        }
+    }
+
+    /// Sampler reporting the levels in `levels` one after another, then the last one
+    async fn sampler_with_levels(levels: &[f32]) -> Sampler {
+        let levels = Arc::new(Mutex::new(levels.to_vec()));
+        let sampler = Sampler::start_with(Duration::from_millis(10), 10, move || {
+            let mut levels = levels.lock().unwrap();
+            let level = if levels.len() > 1 {
+                levels.remove(0)
+            } else {
+                levels[0]
+            };
+            Box::pin(async move { PwrzvResult::Ok(PowerReserveReport::new(level, HashMap::new())) })
+        });
+        sampler
+            .watch()
+            .wait_for(|sample| sample.is_some())
+            .await
+            .unwrap();
+        sampler
+    }
+
+    async fn call(service: &mut LoadShed<Ok200, &'static str>) -> &'static str {
+        std::future::poll_fn(|cx| service.poll_ready(cx))
+            .await
+            .unwrap();
+        service.call(()).await.unwrap()
+    }
+
+    fn policy(threshold: f32, floor: Option<f32>) -> ShedPolicy<()> {
+        ShedPolicy {
+            threshold,
+            floor,
+            delay: None,
+            fail_closed: false,
+            reject: Arc::new(|_| ()),
+        }
+    }
+
+    #[test]
+    fn test_should_shed() {
+        let hard = policy(2.5, None);
+        assert!(!hard.should_shed(2.5, 0.0));
+        assert!(hard.should_shed(2.4, 0.99));
+
+        let gradual = policy(3.0, Some(1.0));
+        assert!(!gradual.should_shed(3.0, 0.0));
+        // Halfway down, half of the requests are shed
+        assert!(gradual.should_shed(2.0, 0.49));
+        assert!(!gradual.should_shed(2.0, 0.51));
+        assert!(gradual.should_shed(1.0, 0.99));
+        assert!(gradual.should_shed(0.5, 0.99));
+    }
+
+    #[test]
+    fn test_random_roll_range() {
+        assert!(
+            (0..1000)
+                .map(|_| random_roll())
+                .all(|roll| (0.0..1.0).contains(&roll))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_shed_rejects_below_threshold() {
+        let layer = LoadShedLayer::new(2.5, |_| "shed");
+
+        let mut healthy = layer
+            .clone()
+            .sampler(sampler_with_levels(&[4.0]).await)
+            .layer(Ok200);
+        assert_eq!(call(&mut healthy).await, "ok");
+
+        let mut stressed = layer
+            .sampler(sampler_with_levels(&[1.5]).await)
+            .layer(Ok200);
+        assert_eq!(call(&mut stressed).await, "shed");
+    }
+
+    #[tokio::test]
+    async fn test_load_shed_unknown_level() {
+        // One low sample, then every sample fails
+        let failing = || {
+            let taken = Arc::new(Mutex::new(false));
+            Sampler::start_with(Duration::from_millis(10), 10, move || {
+                let first = !std::mem::replace(&mut *taken.lock().unwrap(), true);
+                Box::pin(async move {
+                    match first {
+                        true => Ok(PowerReserveReport::new(1.5, HashMap::new())),
+                        false => Err(crate::PwrzvError::collection_error("boom")),
+                    }
+                })
+            })
+        };
+
+        let sampler = failing();
+        sampler
+            .watch()
+            .wait_for(|sample| sample.is_some())
+            .await
+            .unwrap();
+        let mut open = LoadShedLayer::new(2.5, |_| "shed")
+            .sampler(sampler.clone())
+            .layer(Ok200);
+        let mut closed = LoadShedLayer::new(2.5, |_| "shed")
+            .fail_closed()
+            .sampler(sampler)
+            .layer(Ok200);
+        assert_eq!(call(&mut open).await, "shed");
+        assert_eq!(call(&mut closed).await, "shed");
+
+        // The low level goes stale while sampling fails
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(call(&mut open).await, "ok");
+        assert_eq!(call(&mut closed).await, "shed");
+    }
+
+    #[tokio::test]
+    async fn test_load_shed_delay_waits_for_recovery() {
+        let sampler = sampler_with_levels(&[1.5, 1.5, 4.0]).await;
+        let mut service = LoadShedLayer::new(2.5, |_| "shed")
+            .delay(Duration::from_secs(5))
+            .sampler(sampler)
+            .layer(Ok200);
+        assert_eq!(call(&mut service).await, "ok");
+
+        let sampler = sampler_with_levels(&[1.5]).await;
+        let mut service = LoadShedLayer::new(2.5, |_| "shed")
+            .delay(Duration::from_millis(30))
+            .sampler(sampler)
+            .layer(Ok200);
+        assert_eq!(call(&mut service).await, "shed");
+    }
+}
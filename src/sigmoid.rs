@@ -1,4 +1,9 @@
+use crate::config::{EffectiveMetric, EffectiveParam, PwrzvConfig, ValueSource};
+use crate::curve::Curve;
+use crate::error::PwrzvResult;
+use std::collections::BTreeSet;
 use std::env;
+use std::sync::Mutex;
 
 /// Sigmoid function configuration
 #[derive(Debug, Clone, Copy)]
@@ -34,32 +39,232 @@ impl Default for SigmoidFn {
     }
 }
 
+/// Convert sigmoid score to 5-point scale with decimal precision
+/// [0, 1.0] -> [5.0, 0.0]
+pub(crate) fn five_point_scale_with_decimal(score: f32) -> f32 {
+    let score = 5.0 * (1.0 - score);
+    // Retain 4 decimal places for precision
+    let factor = 10_000f32;
+    (score * factor).round() / factor
+}
+
+/// Read a numeric environment variable, `None` if unset or unparsable
+pub(crate) fn env_f32(name: &str) -> Option<f32> {
+    env::var(name).ok().and_then(|s| s.parse::<f32>().ok())
+}
+
+/// Curve settings already warned about, so a bad setting is reported once
+static WARNED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Print `message` on stderr unless a warning about `key` was already printed
+fn warn_once(key: String, message: String) {
+    if WARNED.lock().unwrap().insert(key) {
+        eprintln!("{message}");
+    }
+}
+
 // ================================
-// Environment variable helper for SigmoidFn configuration
+// Per-metric scoring parameters
 // ================================
 
-/// Create SigmoidFn from environment variables with fallback to defaults
-pub(crate) fn get_sigmoid_config(
-    env_prefix: &str,
-    default_midpoint: f32,
-    default_steepness: f32,
-) -> SigmoidFn {
-    let midpoint_env = format!("{env_prefix}_MIDPOINT");
-    let steepness_env = format!("{env_prefix}_STEEPNESS");
-
-    let midpoint = env::var(&midpoint_env)
-        .ok()
-        .and_then(|s| s.parse::<f32>().ok())
-        .unwrap_or(default_midpoint);
-
-    let steepness = env::var(&steepness_env)
-        .ok()
-        .and_then(|s| s.parse::<f32>().ok())
-        .unwrap_or(default_steepness);
-
-    SigmoidFn {
-        midpoint,
-        steepness,
+/// Identity and default curve of a scored metric
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MetricSpec {
+    /// Metric ID used in configuration files and traces, e.g. `cpu_usage`
+    pub(crate) id: &'static str,
+    /// Human-readable label used in detailed output, e.g. `CPU Usage`
+    pub(crate) label: &'static str,
+    /// Environment variable prefix, e.g. `PWRZV_LINUX_CPU_USAGE`
+    pub(crate) env_prefix: &'static str,
+    /// Default sigmoid midpoint
+    pub(crate) midpoint: f32,
+    /// Default sigmoid steepness
+    pub(crate) steepness: f32,
+}
+
+impl MetricSpec {
+    /// Resolve the metric's curve with precedence default < config file < environment
+    ///
+    /// The `_MIDPOINT` / `_STEEPNESS` environment variables set the parameters
+    /// of the logistic (and other parametric) curves. Overrides that make the
+    /// curve invalid, such as a negative steepness, are ignored with a warning
+    /// on stderr and the file curve is used; an invalid file curve falls back
+    /// to the built-in logistic curve.
+    pub(crate) fn curve(&self, platform: &str, config: &PwrzvConfig) -> Curve {
+        self.resolve_or_fallback(platform, config, &env_f32).curve
+    }
+
+    /// Resolve the metric's curve, falling back as [`MetricSpec::curve`] does
+    ///
+    /// # Arguments
+    ///
+    /// * `platform` - Configuration section the metric belongs to
+    /// * `config` - Configuration to read the metric's section from
+    /// * `env` - Reads a numeric environment variable
+    pub(crate) fn resolve_or_fallback(
+        &self,
+        platform: &str,
+        config: &PwrzvConfig,
+        env: &dyn Fn(&str) -> Option<f32>,
+    ) -> EffectiveMetric {
+        let e = match self.resolve_with(platform, config, env) {
+            Ok(effective) => return effective,
+            Err(e) => e,
+        };
+        match self.resolve_with(platform, config, &|_| None) {
+            Ok(effective) => {
+                warn_once(
+                    format!("{}_*", self.env_prefix),
+                    format!(
+                        "Warning: ignoring {}_MIDPOINT/_STEEPNESS: {}; using the configured curve",
+                        self.env_prefix,
+                        e.detail()
+                    ),
+                );
+                effective
+            }
+            Err(e) => {
+                warn_once(
+                    format!("{platform}.{}", self.id),
+                    format!(
+                        "Warning: ignoring the [{platform}.{}] curve: {}; using the built-in curve",
+                        self.id,
+                        e.detail()
+                    ),
+                );
+                self.fallback(platform)
+            }
+        }
+    }
+
+    /// Resolve the metric's curve along with the source of every parameter
+    ///
+    /// Environment overrides are read through `env`.
+    fn resolve_with(
+        &self,
+        platform: &str,
+        config: &PwrzvConfig,
+        env: &dyn Fn(&str) -> Option<f32>,
+    ) -> PwrzvResult<EffectiveMetric> {
+        let file = config.metric(platform, self.id);
+        let midpoint = env(&format!("{}_MIDPOINT", self.env_prefix))
+            .unwrap_or(file.midpoint.unwrap_or(self.midpoint));
+        let steepness = env(&format!("{}_STEEPNESS", self.env_prefix))
+            .unwrap_or(file.steepness.unwrap_or(self.steepness));
+        let curve = file.build_curve(midpoint, steepness)?;
+
+        let source = |suffix: &str, in_file: bool| {
+            let var = format!("{}_{suffix}", self.env_prefix);
+            if env(&var).is_some() {
+                ValueSource::Env(var)
+            } else if in_file {
+                ValueSource::File
+            } else {
+                ValueSource::Default
+            }
+        };
+        let param = |name: &'static str, value: String, source: ValueSource| EffectiveParam {
+            name,
+            value,
+            source,
+        };
+
+        let mut params = vec![param(
+            "curve",
+            curve.kind().to_string(),
+            if file.curve.is_some() {
+                ValueSource::File
+            } else {
+                ValueSource::Default
+            },
+        )];
+        match &curve {
+            Curve::Logistic {
+                midpoint,
+                steepness,
+            }
+            | Curve::Exponential {
+                midpoint,
+                steepness,
+            }
+            | Curve::Richards {
+                midpoint,
+                steepness,
+                ..
+            } => {
+                params.push(param(
+                    "midpoint",
+                    midpoint.to_string(),
+                    source("MIDPOINT", file.midpoint.is_some()),
+                ));
+                params.push(param(
+                    "steepness",
+                    steepness.to_string(),
+                    source("STEEPNESS", file.steepness.is_some()),
+                ));
+                if let Curve::Richards { asymmetry, .. } = &curve {
+                    params.push(param(
+                        "asymmetry",
+                        asymmetry.to_string(),
+                        if file.asymmetry.is_some() {
+                            ValueSource::File
+                        } else {
+                            ValueSource::Default
+                        },
+                    ));
+                }
+            }
+            Curve::PiecewiseLinear { points } | Curve::Step { points } => {
+                let points = points
+                    .iter()
+                    .map(|[value, score]| format!("[{value}, {score}]"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                params.push(param("points", format!("[{points}]"), ValueSource::File));
+            }
+        }
+        if let Some(enabled) = file.enabled {
+            params.push(param("enabled", enabled.to_string(), ValueSource::File));
+        }
+        if let Some(weight) = file.weight {
+            params.push(param("weight", weight.to_string(), ValueSource::File));
+        }
+
+        Ok(EffectiveMetric {
+            scope: platform.to_string(),
+            id: self.id.to_string(),
+            label: self.label.to_string(),
+            curve,
+            params,
+        })
+    }
+
+    /// Describe the built-in curve with every parameter from its default
+    pub(crate) fn fallback(&self, scope: &str) -> EffectiveMetric {
+        let param = |name: &'static str, value: String| EffectiveParam {
+            name,
+            value,
+            source: ValueSource::Default,
+        };
+        EffectiveMetric {
+            scope: scope.to_string(),
+            id: self.id.to_string(),
+            label: self.label.to_string(),
+            curve: self.default_curve(),
+            params: vec![
+                param("curve", "logistic".to_string()),
+                param("midpoint", self.midpoint.to_string()),
+                param("steepness", self.steepness.to_string()),
+            ],
+        }
+    }
+
+    /// Get the built-in curve of the metric
+    pub(crate) fn default_curve(&self) -> Curve {
+        Curve::Logistic {
+            midpoint: self.midpoint,
+            steepness: self.steepness,
+        }
     }
 }
 
@@ -67,6 +272,22 @@ pub(crate) fn get_sigmoid_config(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_five_point_scale_with_decimal() {
+        // Test the five_point_scale_with_decimal function
+        // [0, 1] -> [5.0, 1.0] linear mapping with 4 decimal precision
+        assert_eq!(five_point_scale_with_decimal(0.0), 5.0000); // 5 * (1 - 0) = 5
+        assert_eq!(five_point_scale_with_decimal(0.2), 4.0000); // 5 * (1 - 0.2) = 4 
+        assert_eq!(five_point_scale_with_decimal(0.4), 3.0000); // 5 * (1 - 0.4) = 3
+        assert_eq!(five_point_scale_with_decimal(0.6), 2.0000); // 5 * (1 - 0.6) = 2
+        assert_eq!(five_point_scale_with_decimal(0.8), 1.0000); // 5 * (1 - 0.8) = 1
+        assert_eq!(five_point_scale_with_decimal(1.0), 0.0000); // 5 * (1 - 1) = 0
+
+        // Test decimal precision
+        let score = five_point_scale_with_decimal(0.1234);
+        assert!((score - 4.3830).abs() < 0.0001); // 5 * (1 - 0.1234) = 4.383
+    }
+
     #[test]
     fn test_sigmoid_basic() {
         let f = SigmoidFn {
@@ -130,11 +351,90 @@ mod tests {
     }
 
     #[test]
-    fn test_get_sigmoid_config() {
-        // Test default configuration
-        let config = get_sigmoid_config("NONEXISTENT_ENV", 0.7, 15.0);
-        assert!((config.midpoint - 0.7).abs() < 0.01);
-        assert!((config.steepness - 15.0).abs() < 0.01);
+    fn test_metric_spec_curve() {
+        let spec = MetricSpec {
+            id: "cpu_usage",
+            label: "CPU Usage",
+            env_prefix: "PWRZV_TEST_UNSET_CPU_USAGE",
+            midpoint: 0.65,
+            steepness: 8.0,
+        };
+
+        let curve = spec.curve("linux", &PwrzvConfig::default());
+        assert_eq!(curve, spec.default_curve());
+
+        let config = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.8").unwrap();
+        let curve = spec.curve("linux", &config);
+        assert_eq!(
+            curve,
+            Curve::Logistic {
+                midpoint: 0.8,
+                steepness: 8.0
+            }
+        );
+
+        // Other platforms' sections do not apply
+        let curve = spec.curve("macos", &config);
+        assert_eq!(curve, spec.default_curve());
+
+        let config =
+            PwrzvConfig::from_toml("[linux.cpu_usage]\ncurve = \"step\"\npoints = [[0.9, 1.0]]")
+                .unwrap();
+        let curve = spec.curve("linux", &config);
+        assert_eq!(curve.kind(), crate::curve::CurveKind::Step);
+    }
+
+    #[test]
+    fn test_metric_spec_resolve_sources() {
+        let spec = MetricSpec {
+            id: "cpu_usage",
+            label: "CPU Usage",
+            env_prefix: "PWRZV_TEST_UNSET_CPU_USAGE",
+            midpoint: 0.65,
+            steepness: 8.0,
+        };
+
+        let config = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.8").unwrap();
+        let effective = spec.resolve_with("linux", &config, &env_f32).unwrap();
+        let sources: Vec<_> = effective
+            .params
+            .iter()
+            .map(|p| (p.name, p.value.as_str(), p.source.clone()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("curve", "logistic", ValueSource::Default),
+                ("midpoint", "0.8", ValueSource::File),
+                ("steepness", "8", ValueSource::Default),
+            ]
+        );
+
+        // An invalid configured curve falls back to the built-in curve
+        let mut config = PwrzvConfig::default();
+        config.linux.insert(
+            "cpu_usage".to_string(),
+            crate::config::MetricConfig {
+                steepness: Some(-1.0),
+                ..Default::default()
+            },
+        );
+        assert!(spec.resolve_with("linux", &config, &env_f32).is_err());
+        assert_eq!(spec.curve("linux", &config), spec.default_curve());
+
+        // Invalid environment overrides fall back to the configured curve
+        let config = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.8").unwrap();
+        let env = |var: &str| var.ends_with("_STEEPNESS").then_some(-1.0);
+        assert!(spec.resolve_with("linux", &config, &env).is_err());
+        let effective = spec.resolve_or_fallback("linux", &config, &env);
+        assert_eq!(
+            effective.curve,
+            Curve::Logistic {
+                midpoint: 0.8,
+                steepness: 8.0
+            }
+        );
+        assert_eq!(effective.params[1].source, ValueSource::File);
     }
 
     #[test]
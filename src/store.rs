@@ -0,0 +1,778 @@
+//! Persistent history store module
+//!
+//! Persists samples to append-only NDJSON segment files so that the reserve of
+//! a host can be inspected long after the fact, and downsamples them into
+//! 1-minute and 1-hour min/avg/max rollups with their own retention.
+//!
+//! ## Layout
+//!
+//! ```text
+//! <dir>/raw-2025-01-01.ndjson   full reports, one segment per UTC day
+//! <dir>/1m-2025-01-01.ndjson    1-minute rollups, one segment per UTC day
+//! <dir>/1h-2025-01.ndjson       1-hour rollups, one segment per UTC month
+//! ```
+//!
+//! A rollup is written once its bucket closes, i.e. when the first sample of
+//! the next bucket is recorded. Segments entirely older than the retention of
+//! their resolution are deleted.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::report::PowerReserveReport;
+use crate::timespec;
+use chrono::{DateTime, Datelike, Months, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Time span `[start, end)` covered by a segment
+type Span = (DateTime<Utc>, DateTime<Utc>);
+
+/// Environment variable overriding the default store directory
+pub const STORE_ENV: &str = "PWRZV_STORE";
+
+/// Maximum query range answered from raw samples in [`Resolution::Auto`] mode
+const AUTO_RAW_RANGE: Duration = Duration::from_secs(2 * 3600);
+
+/// Maximum query range answered from 1-minute rollups in [`Resolution::Auto`] mode
+const AUTO_MINUTE_RANGE: Duration = Duration::from_secs(2 * 86400);
+
+/// Get the default store directory
+///
+/// Uses `$PWRZV_STORE`, then `$XDG_STATE_HOME/pwrzv`, then `~/.local/state/pwrzv`.
+pub fn default_store_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(STORE_ENV) {
+        return PathBuf::from(dir);
+    }
+    if let Some(state) = std::env::var_os("XDG_STATE_HOME") {
+        return PathBuf::from(state).join("pwrzv");
+    }
+    match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(".local/state/pwrzv"),
+        None => std::env::temp_dir().join("pwrzv"),
+    }
+}
+
+// ================================
+// Resolutions and retention
+// ================================
+
+/// Resolution of stored history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Every recorded sample
+    Raw,
+    /// 1-minute rollups
+    Minute,
+    /// 1-hour rollups
+    Hour,
+    /// Finest resolution that keeps the result readable for the queried range
+    Auto,
+}
+
+impl Resolution {
+    /// File name prefix of the resolution's segments
+    fn prefix(self) -> &'static str {
+        match self {
+            Resolution::Raw | Resolution::Auto => "raw",
+            Resolution::Minute => "1m",
+            Resolution::Hour => "1h",
+        }
+    }
+
+    /// Length of a rollup bucket in seconds
+    fn bucket_secs(self) -> i64 {
+        match self {
+            Resolution::Minute => 60,
+            Resolution::Hour => 3600,
+            Resolution::Raw | Resolution::Auto => 1,
+        }
+    }
+
+    /// Name of the segment holding samples taken at `timestamp`
+    fn segment_name(self, timestamp: DateTime<Utc>) -> String {
+        match self {
+            Resolution::Hour => format!("1h-{}.ndjson", timestamp.format("%Y-%m")),
+            _ => format!("{}-{}.ndjson", self.prefix(), timestamp.format("%Y-%m-%d")),
+        }
+    }
+
+    /// Parse a segment file name into its time span
+    fn segment_span(self, name: &str) -> Option<Span> {
+        let period = name
+            .strip_prefix(self.prefix())?
+            .strip_prefix('-')?
+            .strip_suffix(".ndjson")?;
+        match self {
+            Resolution::Hour => {
+                let start = NaiveDate::parse_from_str(&format!("{period}-01"), "%Y-%m-%d").ok()?;
+                let end = start.checked_add_months(Months::new(1))?;
+                Some((utc_midnight(start), utc_midnight(end)))
+            }
+            _ => {
+                let start = NaiveDate::parse_from_str(period, "%Y-%m-%d").ok()?;
+                Some((utc_midnight(start), utc_midnight(start.succ_opt()?)))
+            }
+        }
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = PwrzvError;
+
+    fn from_str(s: &str) -> PwrzvResult<Self> {
+        match s {
+            "raw" => Ok(Resolution::Raw),
+            "1m" => Ok(Resolution::Minute),
+            "1h" => Ok(Resolution::Hour),
+            "auto" => Ok(Resolution::Auto),
+            _ => Err(PwrzvError::invalid_value(&format!(
+                "unknown resolution '{s}' (expected raw, 1m, 1h or auto)"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Auto => write!(f, "auto"),
+            other => write!(f, "{}", other.prefix()),
+        }
+    }
+}
+
+/// How long each resolution is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Retention of raw samples (default: 2 days)
+    pub raw: Duration,
+    /// Retention of 1-minute rollups (default: 30 days)
+    pub minute: Duration,
+    /// Retention of 1-hour rollups (default: 400 days)
+    pub hour: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            raw: Duration::from_secs(2 * 86400),
+            minute: Duration::from_secs(30 * 86400),
+            hour: Duration::from_secs(400 * 86400),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Get the retention of a resolution
+    pub fn for_resolution(&self, resolution: Resolution) -> Duration {
+        match resolution {
+            Resolution::Raw | Resolution::Auto => self.raw,
+            Resolution::Minute => self.minute,
+            Resolution::Hour => self.hour,
+        }
+    }
+}
+
+impl FromStr for RetentionPolicy {
+    type Err = PwrzvError;
+
+    /// Parse `raw=2d,1m=30d,1h=400d`; omitted resolutions keep their default
+    fn from_str(s: &str) -> PwrzvResult<Self> {
+        let mut policy = Self::default();
+        for part in s.split(',').filter(|part| !part.trim().is_empty()) {
+            let (resolution, duration) = part.split_once('=').ok_or_else(|| {
+                PwrzvError::invalid_value(&format!(
+                    "retention '{part}' is not RESOLUTION=DURATION (e.g. 1m=30d)"
+                ))
+            })?;
+            let duration = timespec::parse_duration(duration)?;
+            match resolution.trim().parse()? {
+                Resolution::Raw => policy.raw = duration,
+                Resolution::Minute => policy.minute = duration,
+                Resolution::Hour => policy.hour = duration,
+                Resolution::Auto => {
+                    return Err(PwrzvError::invalid_value(
+                        "retention cannot be set for the 'auto' resolution",
+                    ));
+                }
+            }
+        }
+        Ok(policy)
+    }
+}
+
+// ================================
+// Store
+// ================================
+
+/// Power reserve level over a span of time
+///
+/// For raw samples `min`, `avg` and `max` are all the sample's level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    /// Start of the span (or sample time for raw samples)
+    pub timestamp: DateTime<Utc>,
+    /// Lowest level
+    pub min: f32,
+    /// Average level
+    pub avg: f32,
+    /// Highest level
+    pub max: f32,
+    /// Number of raw samples aggregated
+    pub samples: u32,
+}
+
+impl From<&PowerReserveReport> for HistoryPoint {
+    fn from(report: &PowerReserveReport) -> Self {
+        let level = report.power_reserve_level;
+        Self {
+            timestamp: report.timestamp,
+            min: level,
+            avg: level,
+            max: level,
+            samples: 1,
+        }
+    }
+}
+
+/// Rollup bucket being accumulated
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    start: DateTime<Utc>,
+    min: f32,
+    max: f32,
+    sum: f64,
+    samples: u32,
+}
+
+impl Bucket {
+    fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            sum: 0.0,
+            samples: 0,
+        }
+    }
+
+    fn add(&mut self, point: &HistoryPoint) {
+        self.min = self.min.min(point.min);
+        self.max = self.max.max(point.max);
+        self.sum += f64::from(point.avg) * f64::from(point.samples);
+        self.samples += point.samples;
+    }
+
+    fn point(&self) -> HistoryPoint {
+        HistoryPoint {
+            timestamp: self.start,
+            min: self.min,
+            avg: (self.sum / f64::from(self.samples.max(1))) as f32,
+            max: self.max,
+            samples: self.samples,
+        }
+    }
+}
+
+/// Append-only history store with downsampling
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pwrzv::store::{HistoryStore, Resolution, RetentionPolicy};
+///
+/// fn main() -> Result<(), pwrzv::PwrzvError> {
+///     let mut store = HistoryStore::open("/var/lib/pwrzv", RetentionPolicy::default())?;
+///     store.record(&pwrzv::blocking::get_power_reserve_report()?)?;
+///
+///     let since = chrono::Utc::now() - chrono::Duration::hours(12);
+///     for point in store.query(Resolution::Auto, since, chrono::Utc::now())? {
+///         println!("{} {:.2}", point.timestamp, point.avg);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HistoryStore {
+    dir: PathBuf,
+    retention: RetentionPolicy,
+    minute: Option<Bucket>,
+    hour: Option<Bucket>,
+}
+
+impl HistoryStore {
+    /// Open (or create) a store directory
+    ///
+    /// Rollup buckets left open by a previous run are rebuilt from the stored
+    /// samples, so restarts do not lose data.
+    pub fn open(dir: impl AsRef<Path>, retention: RetentionPolicy) -> PwrzvResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .map_err(|e| PwrzvError::resource_io_error(&dir.display().to_string(), e))?;
+
+        let mut store = Self {
+            dir,
+            retention,
+            minute: None,
+            hour: None,
+        };
+        store.recover()?;
+        store.prune(Utc::now())?;
+        Ok(store)
+    }
+
+    /// Open an existing store for reading only
+    pub fn open_read_only(dir: impl AsRef<Path>) -> PwrzvResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        if !dir.is_dir() {
+            return Err(PwrzvError::resource_access_error(&format!(
+                "history store {} does not exist",
+                dir.display()
+            )));
+        }
+        Ok(Self {
+            dir,
+            retention: RetentionPolicy::default(),
+            minute: None,
+            hour: None,
+        })
+    }
+
+    /// Get the store directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Persist a sample and update the rollups
+    ///
+    /// Samples must be recorded in chronological order.
+    pub fn record(&mut self, report: &PowerReserveReport) -> PwrzvResult<()> {
+        let line = serde_json::to_string(report)
+            .map_err(|e| PwrzvError::invalid_value(&format!("failed to serialize report: {e}")))?;
+        self.append(Resolution::Raw, report.timestamp, &line)?;
+        self.roll_minute(&HistoryPoint::from(report))
+    }
+
+    /// Get the stored history between `from` and `to` (inclusive)
+    ///
+    /// [`Resolution::Auto`] picks raw samples for ranges up to 2 hours,
+    /// 1-minute rollups up to 2 days and 1-hour rollups beyond, falling back to
+    /// a coarser resolution when `from` lies outside the finer one's retention.
+    pub fn query(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> PwrzvResult<Vec<HistoryPoint>> {
+        let resolution = match resolution {
+            Resolution::Auto => self.auto_resolution(from, to),
+            other => other,
+        };
+        match resolution {
+            Resolution::Raw => Ok(self
+                .query_reports(from, to)?
+                .iter()
+                .map(HistoryPoint::from)
+                .collect()),
+            _ => self.read_segments(resolution, from, to, |point: &HistoryPoint| point.timestamp),
+        }
+    }
+
+    /// Get the stored raw reports between `from` and `to` (inclusive)
+    pub fn query_reports(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> PwrzvResult<Vec<PowerReserveReport>> {
+        self.read_segments(Resolution::Raw, from, to, |report: &PowerReserveReport| {
+            report.timestamp
+        })
+    }
+
+    /// Resolve [`Resolution::Auto`] for a query range
+    pub fn auto_resolution(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Resolution {
+        let range = (to - from).to_std().unwrap_or_default();
+        let age = (Utc::now() - from).to_std().unwrap_or_default();
+
+        if range <= AUTO_RAW_RANGE && age <= self.retention.raw {
+            Resolution::Raw
+        } else if range <= AUTO_MINUTE_RANGE && age <= self.retention.minute {
+            Resolution::Minute
+        } else {
+            Resolution::Hour
+        }
+    }
+
+    /// Delete segments that ended before the retention of their resolution
+    pub fn prune(&self, now: DateTime<Utc>) -> PwrzvResult<()> {
+        for resolution in [Resolution::Raw, Resolution::Minute, Resolution::Hour] {
+            let retention = chrono::Duration::from_std(self.retention.for_resolution(resolution))
+                .unwrap_or(chrono::Duration::MAX);
+            let Some(cutoff) = now.checked_sub_signed(retention) else {
+                continue;
+            };
+            for (path, (_, end)) in self.segments(resolution)? {
+                if end <= cutoff {
+                    fs::remove_file(&path).map_err(|e| {
+                        PwrzvError::resource_io_error(&path.display().to_string(), e)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Feed a raw sample into the 1-minute bucket, closing it when the minute changes
+    fn roll_minute(&mut self, point: &HistoryPoint) -> PwrzvResult<()> {
+        let start = bucket_start(point.timestamp, Resolution::Minute);
+        if let Some(bucket) = self.minute.filter(|bucket| bucket.start != start) {
+            let closed = bucket.point();
+            self.append_point(Resolution::Minute, &closed)?;
+            self.roll_hour(&closed)?;
+            self.minute = None;
+        }
+        self.minute
+            .get_or_insert_with(|| Bucket::new(start))
+            .add(point);
+        Ok(())
+    }
+
+    /// Feed a closed 1-minute rollup into the 1-hour bucket
+    fn roll_hour(&mut self, point: &HistoryPoint) -> PwrzvResult<()> {
+        let start = bucket_start(point.timestamp, Resolution::Hour);
+        if let Some(bucket) = self.hour.filter(|bucket| bucket.start != start) {
+            self.append_point(Resolution::Hour, &bucket.point())?;
+            self.hour = None;
+            self.prune(point.timestamp)?;
+        }
+        self.hour
+            .get_or_insert_with(|| Bucket::new(start))
+            .add(point);
+        Ok(())
+    }
+
+    /// Rebuild open buckets from rollups and samples written after the last closed ones
+    fn recover(&mut self) -> PwrzvResult<()> {
+        let epoch = DateTime::<Utc>::MIN_UTC;
+        let now = Utc::now() + chrono::Duration::days(1);
+
+        let last_hour = self.last_point(Resolution::Hour)?;
+        let hour_from = last_hour.map_or(epoch, |p| p.timestamp + chrono::Duration::hours(1));
+        for point in
+            self.read_segments(Resolution::Minute, hour_from, now, |p: &HistoryPoint| {
+                p.timestamp
+            })?
+        {
+            self.roll_hour(&point)?;
+        }
+
+        let last_minute = self.last_point(Resolution::Minute)?;
+        let minute_from = last_minute.map_or(epoch, |p| p.timestamp + chrono::Duration::minutes(1));
+        for report in self.query_reports(minute_from, now)? {
+            self.roll_minute(&HistoryPoint::from(&report))?;
+        }
+        Ok(())
+    }
+
+    /// Get the most recent rollup of a resolution
+    fn last_point(&self, resolution: Resolution) -> PwrzvResult<Option<HistoryPoint>> {
+        let Some((path, _)) = self.segments(resolution)?.pop() else {
+            return Ok(None);
+        };
+        let content = fs::read_to_string(&path).map_err(|e| PwrzvError::read_error(&path, e))?;
+        Ok(content
+            .lines()
+            .rev()
+            .find_map(|line| serde_json::from_str(line).ok()))
+    }
+
+    fn append_point(&self, resolution: Resolution, point: &HistoryPoint) -> PwrzvResult<()> {
+        let line = serde_json::to_string(point)
+            .map_err(|e| PwrzvError::invalid_value(&format!("failed to serialize rollup: {e}")))?;
+        self.append(resolution, point.timestamp, &line)
+    }
+
+    /// Append a line to the segment covering `timestamp`
+    fn append(
+        &self,
+        resolution: Resolution,
+        timestamp: DateTime<Utc>,
+        line: &str,
+    ) -> PwrzvResult<()> {
+        let path = self.dir.join(resolution.segment_name(timestamp));
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| PwrzvError::resource_io_error(&path.display().to_string(), e))?;
+        // One write per line keeps concurrent readers from seeing partial records
+        file.write_all(format!("{line}\n").as_bytes())
+            .map_err(|e| PwrzvError::resource_io_error(&path.display().to_string(), e))
+    }
+
+    /// List the segments of a resolution with their time spans, oldest first
+    fn segments(&self, resolution: Resolution) -> PwrzvResult<Vec<(PathBuf, Span)>> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| PwrzvError::read_error(&self.dir, e))?;
+
+        let mut segments: Vec<_> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let span = resolution.segment_span(&name)?;
+                Some((entry.path(), span))
+            })
+            .collect();
+        segments.sort_by_key(|(_, (start, _))| *start);
+        Ok(segments)
+    }
+
+    /// Read all records of a resolution between `from` and `to`, skipping damaged lines
+    fn read_segments<T: for<'de> Deserialize<'de>>(
+        &self,
+        resolution: Resolution,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        timestamp: impl Fn(&T) -> DateTime<Utc>,
+    ) -> PwrzvResult<Vec<T>> {
+        let mut records = Vec::new();
+        for (path, (start, end)) in self.segments(resolution)? {
+            if end <= from || start > to {
+                continue;
+            }
+            let content =
+                fs::read_to_string(&path).map_err(|e| PwrzvError::read_error(&path, e))?;
+            records.extend(
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str::<T>(line).ok())
+                    .filter(|record| (from..=to).contains(&timestamp(record))),
+            );
+        }
+        records.sort_by_key(&timestamp);
+        Ok(records)
+    }
+}
+
+/// Start of the rollup bucket containing `timestamp`
+fn bucket_start(timestamp: DateTime<Utc>, resolution: Resolution) -> DateTime<Utc> {
+    let secs = resolution.bucket_secs();
+    let start = timestamp.timestamp().div_euclid(secs) * secs;
+    DateTime::from_timestamp(start, 0).unwrap_or(timestamp)
+}
+
+/// Midnight UTC at the start of `date`
+fn utc_midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(date.year(), date.month(), date.day(), 0, 0, 0)
+        .single()
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn temp_store_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pwrzv-store-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn report(timestamp: DateTime<Utc>, level: f32) -> PowerReserveReport {
+        let mut report = PowerReserveReport::new(level, HashMap::new());
+        report.timestamp = timestamp;
+        report
+    }
+
+    /// Retention long enough that fixed test dates are never pruned
+    fn keep_forever() -> RetentionPolicy {
+        let forever = Duration::from_secs(100 * 365 * 86400);
+        RetentionPolicy {
+            raw: forever,
+            minute: forever,
+            hour: forever,
+        }
+    }
+
+    #[test]
+    fn test_parse_retention_policy() {
+        let policy: RetentionPolicy = "raw=1d, 1h=90d".parse().unwrap();
+        assert_eq!(policy.raw, Duration::from_secs(86400));
+        assert_eq!(policy.minute, RetentionPolicy::default().minute);
+        assert_eq!(policy.hour, Duration::from_secs(90 * 86400));
+
+        assert!("raw".parse::<RetentionPolicy>().is_err());
+        assert!("5m=1d".parse::<RetentionPolicy>().is_err());
+        assert!("auto=1d".parse::<RetentionPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_segment_span() {
+        let (start, end) = Resolution::Raw
+            .segment_span("raw-2025-01-31.ndjson")
+            .unwrap();
+        assert_eq!(start, at("2025-01-31T00:00:00Z"));
+        assert_eq!(end, at("2025-02-01T00:00:00Z"));
+
+        let (start, end) = Resolution::Hour.segment_span("1h-2025-12.ndjson").unwrap();
+        assert_eq!(start, at("2025-12-01T00:00:00Z"));
+        assert_eq!(end, at("2026-01-01T00:00:00Z"));
+
+        assert!(
+            Resolution::Minute
+                .segment_span("raw-2025-01-31.ndjson")
+                .is_none()
+        );
+        assert!(Resolution::Raw.segment_span("raw-junk.ndjson").is_none());
+    }
+
+    #[test]
+    fn test_record_and_rollups() {
+        let dir = temp_store_dir("rollups");
+        let mut store = HistoryStore::open(&dir, keep_forever()).unwrap();
+
+        // Two minutes of samples every 20s, then one sample in the next hour
+        let start = at("2025-01-01T02:59:00Z");
+        for (i, level) in [2.0, 3.0, 4.0, 1.0, 2.0, 3.0].iter().enumerate() {
+            let timestamp = start + chrono::Duration::seconds(20 * i as i64);
+            store.record(&report(timestamp, *level)).unwrap();
+        }
+        store
+            .record(&report(at("2025-01-01T03:01:00Z"), 5.0))
+            .unwrap();
+
+        let from = at("2025-01-01T00:00:00Z");
+        let to = at("2025-01-02T00:00:00Z");
+        assert_eq!(store.query(Resolution::Raw, from, to).unwrap().len(), 7);
+
+        let minutes = store.query(Resolution::Minute, from, to).unwrap();
+        assert_eq!(minutes.len(), 2);
+        assert_eq!(minutes[0].timestamp, at("2025-01-01T02:59:00Z"));
+        assert_eq!(
+            (minutes[0].min, minutes[0].avg, minutes[0].max),
+            (2.0, 3.0, 4.0)
+        );
+        assert_eq!(minutes[0].samples, 3);
+        assert_eq!(
+            (minutes[1].min, minutes[1].avg, minutes[1].max),
+            (1.0, 2.0, 3.0)
+        );
+
+        // The 02:00 hour closed when the 03:00 minute bucket was fed in
+        let hours = store.query(Resolution::Hour, from, to).unwrap();
+        assert_eq!(hours.len(), 1);
+        assert_eq!(hours[0].timestamp, at("2025-01-01T02:00:00Z"));
+        assert_eq!((hours[0].min, hours[0].avg, hours[0].max), (2.0, 3.0, 4.0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reopen_recovers_open_buckets() {
+        let dir = temp_store_dir("recover");
+        let mut store = HistoryStore::open(&dir, keep_forever()).unwrap();
+        store
+            .record(&report(at("2025-01-01T03:00:00Z"), 2.0))
+            .unwrap();
+        store
+            .record(&report(at("2025-01-01T03:00:30Z"), 4.0))
+            .unwrap();
+        drop(store);
+
+        let mut store = HistoryStore::open(&dir, keep_forever()).unwrap();
+        store
+            .record(&report(at("2025-01-01T03:01:00Z"), 5.0))
+            .unwrap();
+
+        let minutes = store
+            .query(
+                Resolution::Minute,
+                at("2025-01-01T00:00:00Z"),
+                at("2025-01-02T00:00:00Z"),
+            )
+            .unwrap();
+        assert_eq!(minutes.len(), 1);
+        assert_eq!(minutes[0].avg, 3.0);
+        assert_eq!(minutes[0].samples, 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_query_range_and_damaged_lines() {
+        let dir = temp_store_dir("range");
+        let mut store = HistoryStore::open(&dir, keep_forever()).unwrap();
+        for hour in 0..4 {
+            let timestamp = at("2025-01-01T00:00:00Z") + chrono::Duration::hours(hour);
+            store.record(&report(timestamp, hour as f32)).unwrap();
+        }
+        // A record cut short by a crash is skipped
+        let segment = dir.join("raw-2025-01-01.ndjson");
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(b"{\"platform\":\"li").unwrap();
+
+        let points = store
+            .query(
+                Resolution::Raw,
+                at("2025-01-01T01:00:00Z"),
+                at("2025-01-01T02:00:00Z"),
+            )
+            .unwrap();
+        let levels: Vec<f32> = points.iter().map(|p| p.avg).collect();
+        assert_eq!(levels, vec![1.0, 2.0]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_removes_expired_segments() {
+        let dir = temp_store_dir("prune");
+        let retention = RetentionPolicy {
+            raw: Duration::from_secs(86400),
+            ..keep_forever()
+        };
+        let mut store = HistoryStore::open(&dir, retention).unwrap();
+        store
+            .record(&report(at("2025-01-01T12:00:00Z"), 3.0))
+            .unwrap();
+        store
+            .record(&report(at("2025-01-03T12:00:00Z"), 3.0))
+            .unwrap();
+
+        store.prune(at("2025-01-03T12:00:00Z")).unwrap();
+        assert!(!dir.join("raw-2025-01-01.ndjson").exists());
+        assert!(dir.join("raw-2025-01-03.ndjson").exists());
+        assert!(dir.join("1m-2025-01-01.ndjson").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_auto_resolution() {
+        let dir = temp_store_dir("auto");
+        let store = HistoryStore::open(&dir, RetentionPolicy::default()).unwrap();
+        let now = Utc::now();
+
+        let pick = |age_hours: i64, range_hours: i64| {
+            let from = now - chrono::Duration::hours(age_hours);
+            store.auto_resolution(from, from + chrono::Duration::hours(range_hours))
+        };
+        assert_eq!(pick(1, 1), Resolution::Raw);
+        assert_eq!(pick(12, 12), Resolution::Minute);
+        // Outside the raw retention even though the range is short
+        assert_eq!(pick(24 * 5, 1), Resolution::Minute);
+        assert_eq!(pick(24 * 7, 24 * 7), Resolution::Hour);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,164 @@
+//! Time specification parsing module
+//!
+//! Parses the human-friendly durations and timestamps accepted on the command
+//! line and in query strings, such as `90s`, `15m`, `2h`, `1d` or RFC 3339 timestamps.
+
+use crate::error::{PwrzvError, PwrzvResult};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::time::Duration;
+
+/// Parse a duration such as `500ms`, `90s`, `15m`, `2h` or `1d`
+///
+/// A bare number is interpreted as seconds.
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::timespec::parse_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+/// assert_eq!(parse_duration("60").unwrap(), Duration::from_secs(60));
+/// ```
+pub fn parse_duration(value: &str) -> PwrzvResult<Duration> {
+    let value = value.trim();
+    let invalid = || {
+        PwrzvError::invalid_value(&format!(
+            "'{value}' is not a duration like 500ms, 90s, 15m, 2h or 1d"
+        ))
+    };
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount = amount.parse::<f64>().map_err(|_| invalid())?;
+
+    let seconds = match unit.trim() {
+        "ms" => amount / 1000.0,
+        "" | "s" => amount,
+        "m" => amount * 60.0,
+        "h" => amount * 3600.0,
+        "d" => amount * 86400.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| PwrzvError::invalid_value(&format!("'{value}' is out of range")))
+}
+
+/// Parse a point in time: a timestamp or a relative age such as `15m`
+///
+/// Timestamps are RFC 3339, or local time written as `2025-01-01 03:00`,
+/// `2025-01-01 03:00:00` or `2025-01-01` (midnight).
+///
+/// # Arguments
+///
+/// * `value` - The value to parse
+/// * `now` - Reference time for relative ages
+///
+/// # Example
+///
+/// ```rust
+/// use pwrzv::timespec::parse_since;
+///
+/// let now = chrono::Utc::now();
+/// let since = parse_since("2h", now).unwrap();
+/// assert_eq!(now - since, chrono::Duration::hours(2));
+/// ```
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> PwrzvResult<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Some(timestamp) = parse_local(value) {
+        return Ok(timestamp);
+    }
+
+    let age = parse_duration(value).map_err(|_| {
+        PwrzvError::invalid_value(&format!(
+            "'{value}' is neither a timestamp (RFC 3339 or YYYY-MM-DD [HH:MM[:SS]]) \
+             nor an age like 90s, 15m, 2h or 1d"
+        ))
+    })?;
+    let out_of_range = || PwrzvError::invalid_value(&format!("'{value}' is out of range"));
+    let age = chrono::Duration::from_std(age).map_err(|_| out_of_range())?;
+    now.checked_sub_signed(age).ok_or_else(out_of_range)
+}
+
+/// Parse a timestamp without offset in the local time zone
+fn parse_local(value: &str) -> Option<DateTime<Utc>> {
+    let naive = [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+    })?;
+
+    // Ambiguous local times (DST fall-back) resolve to the earlier instant
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(900));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86400));
+        assert_eq!(parse_duration("1.5s").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_duration("60").unwrap(), Duration::from_secs(60));
+
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5 weeks").is_err());
+        let huge = format!("1{}d", "0".repeat(300));
+        assert!(matches!(
+            parse_duration(&huge),
+            Err(PwrzvError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = DateTime::parse_from_rfc3339("2025-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let since = parse_since("15m", now).unwrap();
+        assert_eq!(now - since, chrono::Duration::minutes(15));
+
+        let since = parse_since("2025-01-01T03:00:00Z", now).unwrap();
+        assert_eq!(since.to_rfc3339(), "2025-01-01T03:00:00+00:00");
+
+        let local = Local
+            .with_ymd_and_hms(2025, 1, 1, 3, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_since("2025-01-01 03:00", now).unwrap(), local);
+        assert_eq!(parse_since("2025-01-01T03:00:00", now).unwrap(), local);
+        assert_eq!(
+            parse_since("2025-01-01", now).unwrap() + chrono::Duration::hours(3),
+            local
+        );
+
+        assert!(parse_since("yesterday", now).is_err());
+        assert!(parse_since("", now).is_err());
+        assert!(matches!(
+            parse_since("1000000000d", now),
+            Err(PwrzvError::InvalidValue { .. })
+        ));
+    }
+}
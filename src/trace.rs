@@ -0,0 +1,211 @@
+//! Trace recording and replay module
+//!
+//! A trace is an NDJSON file of [`TraceRecord`]s holding the raw metric values
+//! of each sample before sigmoid scoring. Replaying a trace re-runs the
+//! calculator on the recorded values, so curves and aggregation can be tuned
+//! against real incidents after the fact.
+
+use crate::config::PwrzvConfig;
+use crate::error::{PwrzvError, PwrzvResult};
+use crate::platform::metrics_for;
+use crate::report::{PowerReserveReport, score_inputs, score_readings};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Operator judgement of a recorded sample, used for calibration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleLabel {
+    /// The system was behaving normally
+    Healthy,
+    /// The system was visibly struggling
+    Degraded,
+}
+
+impl SampleLabel {
+    /// Get the label name as used in traces
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SampleLabel::Healthy => "healthy",
+            SampleLabel::Degraded => "degraded",
+        }
+    }
+}
+
+impl std::fmt::Display for SampleLabel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SampleLabel {
+    type Err = PwrzvError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "healthy" => Ok(SampleLabel::Healthy),
+            "degraded" => Ok(SampleLabel::Degraded),
+            _ => Err(PwrzvError::invalid_value(&format!(
+                "unknown sample label '{s}' (expected healthy or degraded)"
+            ))),
+        }
+    }
+}
+
+/// Raw metric values of a single sample
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Time the sample was taken
+    pub timestamp: DateTime<Utc>,
+    /// Platform the sample was taken on ("linux", "macos")
+    pub platform: String,
+    /// Power reserve level computed when the sample was recorded
+    pub level: f32,
+    /// Raw metric values before scoring, keyed by metric ID
+    pub inputs: BTreeMap<String, f32>,
+    /// Optional label for calibration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<SampleLabel>,
+}
+
+impl TraceRecord {
+    /// Re-run the calculator on the recorded values with a configuration
+    ///
+    /// The values are scored with the metrics and the configuration section
+    /// of the platform the trace was recorded on, whichever host replays it.
+    ///
+    /// # Returns
+    ///
+    /// A report stamped with the recorded timestamp, or an error if pwrzv does
+    /// not support the recorded platform
+    pub fn replay(&self, config: &PwrzvConfig) -> PwrzvResult<PowerReserveReport> {
+        let specs = metrics_for(&self.platform)
+            .ok_or_else(|| PwrzvError::unsupported_platform(&self.platform))?;
+
+        let (level, details) = score_inputs(&self.platform, specs, &self.inputs, config);
+        let mut report = PowerReserveReport::new(level, details);
+        report.readings = score_readings(&self.platform, specs, &self.inputs, config);
+        report.timestamp = self.timestamp;
+        Ok(report)
+    }
+}
+
+/// Collect the raw metric values of the current system
+///
+/// The recorded level is scored with the process-wide configuration.
+///
+/// # Example
+///
+/// ```rust
+/// #[tokio::main]
+/// async fn main() -> Result<(), pwrzv::PwrzvError> {
+///     let record = pwrzv::trace::record().await?;
+///     println!("{}", serde_json::to_string(&record).unwrap());
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub async fn record() -> PwrzvResult<TraceRecord> {
+    crate::spawn_blocking(crate::blocking::record_trace).await
+}
+
+/// Parse NDJSON trace content, ignoring blank lines
+pub fn parse_trace(content: &str) -> PwrzvResult<Vec<TraceRecord>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                PwrzvError::parse_error(&format!("trace line {}: {e}", index + 1)).with_line(line)
+            })
+        })
+        .collect()
+}
+
+/// Read a trace file
+pub fn read_trace(path: impl AsRef<Path>) -> PwrzvResult<Vec<TraceRecord>> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(|e| PwrzvError::read_error(path, e))?;
+    parse_trace(&content).map_err(|e| e.context(&path.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace_record(platform: &str) -> TraceRecord {
+        TraceRecord {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            platform: platform.to_string(),
+            level: 2.5,
+            inputs: BTreeMap::from([
+                ("cpu_usage".to_string(), 0.65),
+                ("memory_usage".to_string(), 0.1),
+            ]),
+            label: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_trace() {
+        let record = trace_record("linux");
+        let line = serde_json::to_string(&record).unwrap();
+        let content = format!("{line}\n\n{line}\n");
+
+        let records = parse_trace(&content).unwrap();
+        assert_eq!(records, vec![record.clone(), record]);
+
+        let labelled = r#"{"timestamp":"2023-11-14T22:13:20Z","platform":"linux","level":2.5,"inputs":{},"label":"degraded"}"#;
+        let records = parse_trace(labelled).unwrap();
+        assert_eq!(records[0].label, Some(SampleLabel::Degraded));
+
+        let error = parse_trace(&format!("{line}\nnot json\n")).unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_replay_with_different_config() {
+        let record = trace_record("linux");
+
+        let report = record.replay(&PwrzvConfig::default()).unwrap();
+        assert_eq!(report.timestamp, record.timestamp);
+        assert_eq!(report.power_reserve_level, 2.5);
+        assert_eq!(report.total_metrics, 2);
+        assert_eq!(
+            report.readings["cpu_usage"],
+            crate::MetricReading {
+                value: 0.65,
+                score: 2.5
+            }
+        );
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PowerReserveReport>(&json).unwrap(),
+            report
+        );
+
+        let relaxed = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.9").unwrap();
+        let report = record.replay(&relaxed).unwrap();
+        assert!(report.power_reserve_level > 4.0);
+    }
+
+    #[test]
+    fn test_replay_with_recorded_platform() {
+        // The macOS curves and section apply on any host
+        let record = trace_record("macos");
+        let report = record.replay(&PwrzvConfig::default()).unwrap();
+        assert!(report.readings["cpu_usage"].score < 2.5);
+
+        let relaxed = PwrzvConfig::from_toml("[linux.cpu_usage]\nmidpoint = 0.9").unwrap();
+        assert_eq!(record.replay(&relaxed).unwrap(), report);
+
+        let record = trace_record("plan9");
+        assert!(matches!(
+            record.replay(&PwrzvConfig::default()),
+            Err(PwrzvError::UnsupportedPlatform { .. })
+        ));
+    }
+}